@@ -0,0 +1,34 @@
+//! AT 响应的通用字段解析
+//!
+//! `+CGDCONT`、`+CMGR` 文本模式头部、`+CSCA`、`^LTEFREQLOCK` 等响应都是逗号分隔、字符串字段带引号，
+//! 引号内还可能出现逗号（APN、时间戳），统一在这里切分。
+
+/// 按逗号切分字段，引号内的逗号不切分，并去掉字段两侧的引号
+pub fn split_fields(rest: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in rest.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut current).trim().to_string()),
+            _ => current.push(c),
+        }
+    }
+    fields.push(current.trim().to_string());
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_commas_inside_quotes() {
+        assert_eq!(
+            split_fields(" \"REC UNREAD\",\"+8613800138000\",,\"24/01/01,12:00:00+32\""),
+            ["REC UNREAD", "+8613800138000", "", "24/01/01,12:00:00+32"]
+        );
+        assert_eq!(split_fields(""), [""]);
+    }
+}
@@ -0,0 +1,84 @@
+//! WebSocket 指令审计日志
+//!
+//! 与系统日志分开，只记录每条被受理的指令：时间、客户端 IP、本连接内的序号、执行结果和指令本身。
+//! 一条指令有多个回复（如分片导出）时每个回复记一行；BATCH 与多指令输入按实际执行的每条指令分别记录。
+//! 携带 PIN 或账号密码的指令只保留指令名。
+
+use chrono::Local;
+use log::warn;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::IpAddr;
+use std::sync::{Mutex, OnceLock};
+
+static AUDIT_FILE: OnceLock<Mutex<File>> = OnceLock::new();
+
+/// 参数中含 PIN 或凭据的指令名（大写比较，在整条记录中查找，不要求 `AT+` 前缀，
+/// 宽松输入的 `cpin=1234`、`+CPIN="1234"` 同样匹配）
+const SENSITIVE_COMMANDS: &[&str] = &["CPIN=", "CPWD=", "CLCK=", "CGAUTH=", "AUTHDATA="];
+
+/// 启动时调用，path 为空表示不记录
+pub fn init(path: &str) {
+    if path.is_empty() {
+        return;
+    }
+    match OpenOptions::new().create(true).append(true).open(path) {
+        Ok(file) => {
+            let _ = AUDIT_FILE.set(Mutex::new(file));
+        }
+        Err(e) => warn!("Failed to open audit log {}: {}", path, e),
+    }
+}
+
+/// 追加一条审计记录，success 为 None 表示回复不是标准的 {success,...} 结构
+pub fn record(client: Option<IpAddr>, seq: u64, command: &str, success: Option<bool>) {
+    let Some(file) = AUDIT_FILE.get() else {
+        return;
+    };
+    let client = client.map(|ip| ip.to_string()).unwrap_or_else(|| "-".to_string());
+    let status = match success {
+        Some(true) => "ok",
+        Some(false) => "error",
+        None => "-",
+    };
+    let line = format!(
+        "{}\t{}\t#{}\t{}\t{}\n",
+        Local::now().to_rfc3339(),
+        client,
+        seq,
+        status,
+        redact(command)
+    );
+    if let Err(e) = file.lock().unwrap().write_all(line.as_bytes()) {
+        warn!("Failed to write audit log: {}", e);
+    }
+}
+
+/// 敏感指令去掉参数，其余指令中的换行转义后原样记录
+///
+/// 敏感指令可能夹在 BATCH 等载荷中间，从最早出现的敏感参数处截断，之后的内容一律不记录。
+fn redact(command: &str) -> String {
+    let trimmed = command.trim();
+    // 只转换 ASCII，字节偏移与原串一致
+    let upper = trimmed.to_ascii_uppercase();
+    if let Some(end) = SENSITIVE_COMMANDS.iter().filter_map(|p| upper.find(p).map(|i| i + p.len())).min() {
+        return format!("{}<redacted>", &trimmed[..end]);
+    }
+    trimmed.replace('\r', "\\r").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_pin_and_credentials() {
+        assert_eq!(redact("at+cpin=\"1234\""), "at+cpin=<redacted>");
+        assert_eq!(redact("AT+CGAUTH=1,1,\"user\",\"pass\""), "AT+CGAUTH=<redacted>");
+        assert_eq!(redact("AT+CPIN?"), "AT+CPIN?");
+        assert_eq!(redact("+CPIN=\"1234\""), "+CPIN=<redacted>");
+        assert_eq!(redact("cpin=1234"), "cpin=<redacted>");
+        assert_eq!(redact("AT+CSQ\r\n"), "AT+CSQ");
+        assert_eq!(redact("BATCH:[\"AT+CSQ\",\"AT+CPIN=\\\"1234\\\"\"]"), "BATCH:[\"AT+CSQ\",\"AT+CPIN=<redacted>");
+    }
+}
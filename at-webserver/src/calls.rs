@@ -0,0 +1,182 @@
+//! 来电记录
+//!
+//! `CallHandler` 收到 RING / +CLIP 时写入，`GET_CALL_HISTORY` 读取。
+//! 一次来电模组会每隔几秒重复上报 RING 与 +CLIP，间隔很短的连续事件合并为一条记录。
+//! 开启 `call_history_persist` 后每次变化都写入文件，重启后恢复。
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// 保留的来电记录条数
+const CALL_HISTORY_LEN: usize = 50;
+
+/// 与上一条记录间隔在此秒数内的事件视为同一次来电
+const CALL_MERGE_SECS: i64 = 30;
+
+const PERSIST_PATH: &str = "/etc/at-webserver.calls.json";
+
+static HISTORY: OnceLock<Mutex<CallHistory>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallEvent {
+    /// 来电号码，只收到 RING 时为 "Unknown"
+    pub number: String,
+    /// 最后一次上报的状态：RING / CLIP
+    pub status: String,
+    /// 首次振铃时间
+    pub time: DateTime<Local>,
+    /// 本次来电振铃上报次数
+    pub rings: u32,
+}
+
+struct CallHistory {
+    events: VecDeque<CallEvent>,
+    /// 最近一次上报的时间，长时间振铃时据此继续合并
+    last_report: Option<DateTime<Local>>,
+    persist: bool,
+}
+
+/// 启动时调用，开启持久化时从文件恢复记录
+pub fn init(persist: bool) {
+    let events = if persist {
+        crate::ring_file::load(PERSIST_PATH, CALL_HISTORY_LEN, "call history")
+    } else {
+        VecDeque::new()
+    };
+    let _ = HISTORY.set(Mutex::new(CallHistory { events, last_report: None, persist }));
+}
+
+fn history_lock() -> &'static Mutex<CallHistory> {
+    HISTORY.get_or_init(|| Mutex::new(CallHistory { events: VecDeque::new(), last_report: None, persist: false }))
+}
+
+/// 记录一次来电上报，`number` 为 None 表示只有 RING
+pub fn record(number: Option<&str>, status: &str) {
+    let now = Local::now();
+    let mut history = history_lock().lock().unwrap();
+    let same_call = history.last_report.is_some_and(|last| (now - last).num_seconds() <= CALL_MERGE_SECS);
+    history.last_report = Some(now);
+
+    match history.events.back_mut().filter(|_| same_call) {
+        Some(last) => {
+            if let Some(number) = number {
+                last.number = number.to_string();
+            }
+            last.status = status.to_string();
+            if status == "RING" {
+                last.rings += 1;
+            }
+        }
+        None => {
+            let event = CallEvent {
+                number: number.unwrap_or("Unknown").to_string(),
+                status: status.to_string(),
+                time: now,
+                rings: u32::from(status == "RING"),
+            };
+            crate::ring_file::push_capped(&mut history.events, CALL_HISTORY_LEN, event);
+        }
+    }
+
+    if history.persist {
+        crate::ring_file::save(PERSIST_PATH, &history.events, "call history");
+    }
+}
+
+/// 来电号码显示状态（+CLIP 的 CLI validity）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CliValidity {
+    Valid,
+    /// 主叫隐藏号码
+    Withheld,
+    /// 网络无法提供号码（跨网、公用电话等）
+    Unavailable,
+}
+
+/// 一条 `+CLIP:` 上报
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipInfo {
+    /// 号码，隐藏或不可用时为 None
+    pub number: Option<String>,
+    /// 号码类型：129 未知/本地，145 国际（带 +），161 国内
+    pub number_type: Option<u8>,
+    pub validity: CliValidity,
+}
+
+impl ClipInfo {
+    /// 记录与通知中显示的号码
+    pub fn display_number(&self) -> String {
+        match (&self.number, self.validity) {
+            (Some(number), _) => number.clone(),
+            (None, CliValidity::Withheld) => "Withheld".to_string(),
+            (None, _) => "Unavailable".to_string(),
+        }
+    }
+}
+
+/// 解析 `+CLIP: "<number>",<type>[,<subaddr>,<satype>[,<alpha>[,<CLI validity>]]]`
+///
+/// validity 0 有效、1 隐藏、2 及以上为不可用。未上报 validity 时号码为空视为隐藏；
+/// validity 表明号码无效时即使带了号码也不采用。国际号码（type 145）缺少 `+` 时补上。
+pub fn parse_clip(line: &str) -> Option<ClipInfo> {
+    let rest = line.trim().strip_prefix("+CLIP:")?;
+    let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+    let raw_number = fields.first()?.trim_matches('"');
+    let number_type: Option<u8> = fields.get(1).and_then(|t| t.parse().ok());
+    let validity = match fields.get(5).and_then(|v| v.parse::<u8>().ok()) {
+        Some(0) => CliValidity::Valid,
+        Some(1) => CliValidity::Withheld,
+        Some(_) => CliValidity::Unavailable,
+        None if raw_number.is_empty() => CliValidity::Withheld,
+        None => CliValidity::Valid,
+    };
+    let number = (validity == CliValidity::Valid && !raw_number.is_empty()).then(|| {
+        if number_type == Some(145) && !raw_number.starts_with('+') {
+            format!("+{}", raw_number)
+        } else {
+            raw_number.to_string()
+        }
+    });
+    let validity = if validity == CliValidity::Valid && number.is_none() { CliValidity::Unavailable } else { validity };
+    Some(ClipInfo { number, number_type, validity })
+}
+
+/// 最近的来电记录（时间倒序）
+pub fn history() -> Vec<CallEvent> {
+    history_lock().lock().unwrap().events.iter().rev().cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clip_with_type_and_validity() {
+        let clip = parse_clip("+CLIP: \"8613800138000\",145,,,,0").unwrap();
+        assert_eq!(clip.number.as_deref(), Some("+8613800138000"));
+        assert_eq!(clip.number_type, Some(145));
+        assert_eq!(clip.validity, CliValidity::Valid);
+
+        let clip = parse_clip("+CLIP: \"13800138000\",129").unwrap();
+        assert_eq!(clip.number.as_deref(), Some("13800138000"));
+        assert_eq!(clip.validity, CliValidity::Valid);
+    }
+
+    #[test]
+    fn withheld_and_unavailable_numbers() {
+        let clip = parse_clip("+CLIP: \"\",128,,,,1").unwrap();
+        assert_eq!(clip.number, None);
+        assert_eq!(clip.validity, CliValidity::Withheld);
+        assert_eq!(clip.display_number(), "Withheld");
+
+        // 未上报 validity 时空号码按隐藏处理
+        assert_eq!(parse_clip("+CLIP: \"\",128").unwrap().validity, CliValidity::Withheld);
+
+        let clip = parse_clip("+CLIP: \"\",129,,,,2").unwrap();
+        assert_eq!(clip.validity, CliValidity::Unavailable);
+        assert_eq!(clip.display_number(), "Unavailable");
+    }
+}
@@ -0,0 +1,61 @@
+//! 外部工具探测
+//!
+//! 网络配置依赖 OpenWrt 的 `uci` / `ifup` 等命令。在非 OpenWrt 环境（开发机、精简固件）
+//! 上缺少这些命令时，每次重连都去执行只会得到含糊的失败日志。启动时探测一次，
+//! 缺少必需工具就整体停用网络配置子系统，只提示一次。
+
+use log::{info, warn};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// 网络配置必需的命令
+const NETWORK_TOOLS: &[&str] = &["uci", "ifup", "ifdown", "ifstatus"];
+
+/// 可选命令，缺少时相应功能降级
+const OPTIONAL_TOOLS: &[&str] = &["fw4", "ping", "ip"];
+
+static NETWORK_AVAILABLE: OnceLock<bool> = OnceLock::new();
+static DISABLED_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// 在 PATH 中查找可执行文件
+fn find_in_path(tool: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(tool).is_file()))
+        .unwrap_or(false)
+}
+
+/// 启动时探测并记录可用的外部工具
+pub fn probe() {
+    let mut missing = Vec::new();
+    for &tool in NETWORK_TOOLS.iter().chain(OPTIONAL_TOOLS) {
+        if find_in_path(tool) {
+            info!("External tool available: {}", tool);
+        } else {
+            info!("External tool missing: {}", tool);
+            missing.push(tool);
+        }
+    }
+    let network_init = Path::new("/etc/init.d/network").exists();
+    if !network_init {
+        info!("External tool missing: /etc/init.d/network");
+    }
+
+    let available = NETWORK_TOOLS.iter().all(|tool| !missing.contains(tool));
+    if !available {
+        warn!(
+            "Network setup disabled: required tools not found ({}). AT/SMS features keep working.",
+            NETWORK_TOOLS.iter().filter(|t| missing.contains(t)).copied().collect::<Vec<_>>().join(", ")
+        );
+    }
+    let _ = NETWORK_AVAILABLE.set(available);
+}
+
+/// 网络配置子系统是否可用；不可用时第一次调用记录一条警告
+pub fn network_setup_available() -> bool {
+    let available = *NETWORK_AVAILABLE.get_or_init(|| NETWORK_TOOLS.iter().all(|tool| find_in_path(tool)));
+    if !available && !DISABLED_WARNED.swap(true, Ordering::Relaxed) {
+        warn!("Skipping network setup: uci/ifup are not available on this system");
+    }
+    available
+}
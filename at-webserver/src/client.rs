@@ -1,11 +1,13 @@
-use crate::config::Config;
-use crate::connection::{ATConnection, NetworkATConnection, SerialATConnection};
-use crate::handlers::{CallHandler, MemoryFullHandler, MessageHandler, NetworkSignalHandler, NewSMSHandler, NdisStatHandler, PDCPDataHandler};
-use crate::models::{ATResponse, CommandSender, ConnectionType};
-use crate::notifications::NotificationManager;
+use crate::command_rules;
+use crate::config::{AtConfig, Config};
+use crate::connection::{ATConnection, ConnectionError, NetworkATConnection, SerialATConnection, SimulatedATConnection};
+use crate::handlers::{CallHandler, DirectSmsHandler, ImsHandler, MemoryFullHandler, MessageHandler, NetworkSignalHandler, NewSMSHandler, NdisStatHandler, PDCPDataHandler, SimHandler, StatusReportHandler};
+use crate::models::{ATResponse, AtCommand, CommandSender, ConnectionType};
+use crate::notifications::{NotificationManager, NotificationType};
 use log::{error, info, warn, debug};
+use std::sync::Mutex;
 use std::time::Duration;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{mpsc, oneshot, Notify};
 use tokio::time::{sleep, timeout};
 
 #[derive(Clone)]
@@ -17,11 +19,16 @@ impl ATClient {
     pub fn new(config: Config, notifications: NotificationManager) -> Self {
         let (tx, rx) = mpsc::channel(32);
         let tx_clone = tx.clone();
-        
-        tokio::spawn(async move {
-            let mut actor = ATClientActor::new(config, notifications, rx, tx_clone);
-            actor.run().await;
-        });
+
+        tokio::spawn(supervise(rx, ACTOR_RESTART_DELAY, move |actor_rx| {
+            let config = config.clone();
+            let notifications = notifications.clone();
+            let cmd_tx = tx_clone.clone();
+            tokio::spawn(async move {
+                let mut actor = ATClientActor::new(config, notifications, actor_rx, cmd_tx);
+                actor.run().await;
+            })
+        }));
 
         Self { tx }
     }
@@ -32,7 +39,7 @@ impl ATClient {
 
     pub async fn send_command(&self, cmd: String) -> anyhow::Result<ATResponse> {
         let (tx, rx) = oneshot::channel();
-        self.tx.send((cmd, tx)).await.map_err(|_| anyhow::anyhow!("Failed to send command"))?;
+        self.tx.send((cmd.into(), tx)).await.map_err(|_| anyhow::anyhow!("Failed to send command"))?;
         match rx.await {
             Ok(resp) => Ok(resp),
             Err(_) => Err(anyhow::anyhow!("Failed to receive response")),
@@ -40,41 +47,182 @@ impl ATClient {
     }
 }
 
+/// `SET_CONNECTION` 在运行时选择的连接方式，None 表示沿用配置；Actor 重启后同样生效
+static CONNECTION_OVERRIDE: Mutex<Option<ConnectionType>> = Mutex::new(None);
+
+/// 通知 Actor 断开当前连接，按新的连接方式重连
+static CONNECTION_SWITCH: Notify = Notify::const_new();
+
+/// 当前 Actor 的 URC 分发通道，供 INJECT_URC 注入模拟上报；Actor 重启后替换为新通道
+static URC_INJECT: Mutex<Option<mpsc::Sender<String>>> = Mutex::new(None);
+
+/// 把一行模拟的主动上报送入 URC 分发流程，与模组真实上报一样交给各 Handler 并广播 raw_data
+///
+/// `+CMT:` / `+CDS:` 与 PDU 之间用 `\n` 分隔，与 client 拼接后的格式一致。
+pub async fn inject_urc(line: &str) -> anyhow::Result<()> {
+    let tx = URC_INJECT
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("AT client is not running"))?;
+    let line = line.trim().to_string();
+    if line.is_empty() {
+        return Err(anyhow::anyhow!("Empty URC line"));
+    }
+    warn!("Injecting synthetic URC: {}", line);
+    if let Some(ws) = crate::server::WS_BROADCASTER.get().filter(|_| !crate::urc::is_muted_line(&line)) {
+        let _ = ws.send(serde_json::json!({"type": "raw_data", "data": line}).to_string());
+    }
+    tx.send(line).await.map_err(|_| anyhow::anyhow!("URC dispatcher is not running"))
+}
+
+/// 当前实际使用的连接方式
+pub fn active_connection_type(config: &AtConfig) -> ConnectionType {
+    CONNECTION_OVERRIDE.lock().unwrap().unwrap_or(config.connection_type)
+}
+
+/// 切换连接方式：Actor 断开当前连接后用配置中对应的串口/网络参数重连，不需要重启服务
+pub fn switch_connection(kind: ConnectionType) {
+    info!("Switching AT connection to {:?}", kind);
+    *CONNECTION_OVERRIDE.lock().unwrap() = Some(kind);
+    // notify_one 会保留一次许可：切换请求到达时 Actor 正在执行指令也不会丢失
+    CONNECTION_SWITCH.notify_one();
+}
+
+/// Actor 异常退出后重启前的等待
+const ACTOR_RESTART_DELAY: Duration = Duration::from_secs(1);
+
+type CommandReceiver = mpsc::Receiver<(AtCommand, oneshot::Sender<ATResponse>)>;
+
+/// Actor 看门狗：对外的指令通道由这里持有，逐条转发给当前的 Actor。
+/// Actor 任务 panic 或退出时记录错误并用新通道重新拉起一个，
+/// 各处持有的 `CommandSender` 不受影响；崩溃时正在执行的指令以接收失败告终。
+async fn supervise<F>(mut rx: CommandReceiver, restart_delay: Duration, mut spawn_actor: F)
+where
+    F: FnMut(CommandReceiver) -> tokio::task::JoinHandle<()>,
+{
+    // 转发时发现 Actor 已退出而退回的指令，交给下一个 Actor
+    let mut pending = None;
+    loop {
+        let (actor_tx, actor_rx) = mpsc::channel(32);
+        let mut handle = spawn_actor(actor_rx);
+
+        loop {
+            let item = match pending.take() {
+                Some(item) => item,
+                None => tokio::select! {
+                    result = &mut handle => {
+                        log_actor_exit(result);
+                        break;
+                    }
+                    item = rx.recv() => match item {
+                        Some(item) => item,
+                        // 所有发送端都已释放，不会再有指令
+                        None => {
+                            handle.abort();
+                            return;
+                        }
+                    },
+                },
+            };
+            if let Err(mpsc::error::SendError(item)) = actor_tx.send(item).await {
+                pending = Some(item);
+                log_actor_exit((&mut handle).await);
+                break;
+            }
+        }
+
+        sleep(restart_delay).await;
+    }
+}
+
+fn log_actor_exit(result: Result<(), tokio::task::JoinError>) {
+    match result {
+        Err(e) if e.is_panic() => error!("AT client actor panicked: {}, restarting", e),
+        Err(e) => error!("AT client actor failed: {}, restarting", e),
+        Ok(()) => error!("AT client actor exited unexpectedly, restarting"),
+    }
+}
+
+/// 单条指令收发各阶段的等待时间
+///
+/// 生产环境使用 `Default`，测试中整体缩短，避免真实等待数秒。
+#[derive(Debug, Clone, Copy)]
+struct CommandTiming {
+    /// 发送前的静默期，让上一条指令迟到的尾巴落入接收缓存
+    settle: Duration,
+    /// 发送前抽干滞留数据的窗口
+    drain: Duration,
+    /// 超过这么久没有收到本指令的响应行即判定超时
+    response: Duration,
+    /// 单次读取的等待上限，决定超时检查的粒度
+    poll: Duration,
+    /// 等待 `>` 提示符的指令发出后，超过这么久仍未收到提示符即放弃
+    prompt: Duration,
+}
+
+impl Default for CommandTiming {
+    fn default() -> Self {
+        Self {
+            settle: Duration::from_millis(100),
+            drain: Duration::from_millis(200),
+            response: Duration::from_secs(10),
+            poll: Duration::from_secs(1),
+            prompt: Duration::from_secs(5),
+        }
+    }
+}
+
 struct ATClientActor {
     config: Config,
     notifications: NotificationManager,
-    rx: mpsc::Receiver<(String, oneshot::Sender<ATResponse>)>,
+    rx: mpsc::Receiver<(AtCommand, oneshot::Sender<ATResponse>)>,
     connection: Option<Box<dyn ATConnection>>,
     handlers: Vec<Box<dyn MessageHandler>>,
     cmd_tx: CommandSender,
     buffer: Vec<u8>,
     urc_tx: mpsc::Sender<String>, // 新增专门用于分发 URC 的通道
+    timing: CommandTiming,
+    /// 因 `session_max_age` 主动断开，重连后需要重新下发 URC 配置
+    reinit_pending: bool,
+    /// 连接意外断开的时间，重连成功时据此计算中断时长并通知
+    outage_since: Option<std::time::Instant>,
+    /// 连续超时的指令数，收到任何数据即清零，达到 `wedge_timeout_count` 时判定模组卡死
+    consecutive_timeouts: u32,
+    /// 当前连接使用的连接方式，SET_CONNECTION 切到不同方式时才需要重连
+    connected_type: Option<ConnectionType>,
 }
 
 impl ATClientActor {
     fn new(
         config: Config, 
         notifications: NotificationManager, 
-        rx: mpsc::Receiver<(String, oneshot::Sender<ATResponse>)>,
+        rx: mpsc::Receiver<(AtCommand, oneshot::Sender<ATResponse>)>,
         cmd_tx: CommandSender,
     ) -> Self {
         // 建立一个解耦的 URC 分发通道
         let (urc_tx, mut urc_rx) = mpsc::channel::<String>(100);
+        *URC_INJECT.lock().unwrap() = Some(urc_tx.clone());
         let notifs = notifications.clone();
         let cmd_tx_clone = cmd_tx.clone();
+        let signal_query_cmd = config.at_config.signal_query_cmd.clone();
         
         // 【解除死锁的核心】：在独立的后台协程中处理 URC，防止 Handler 再次发送 AT 指令时阻塞主 Actor
         tokio::spawn(async move {
             let mut async_handlers: Vec<Box<dyn MessageHandler>> = vec![
                 Box::new(CallHandler),
                 Box::new(MemoryFullHandler),
+                Box::new(SimHandler::new()),
+                Box::new(ImsHandler::new()),
                 Box::new(NewSMSHandler::new(
                     config.notification_config.sms_delete_after_forward,
                     config.notification_config.delete_mms_notification,
                 )),
+                Box::new(DirectSmsHandler::new()),
+                Box::new(StatusReportHandler),
                 Box::new(NdisStatHandler),
                 Box::new(PDCPDataHandler),
-                Box::new(NetworkSignalHandler::new()),
+                Box::new(NetworkSignalHandler::new(&signal_query_cmd)),
             ];
             while let Some(line) = urc_rx.recv().await {
                 for handler in &mut async_handlers {
@@ -88,15 +236,23 @@ impl ATClientActor {
         let handlers: Vec<Box<dyn MessageHandler>> = vec![
             Box::new(CallHandler),
             Box::new(MemoryFullHandler),
+            Box::new(SimHandler::new()),
+            Box::new(ImsHandler::new()),
             Box::new(NewSMSHandler::new(
                 config.notification_config.sms_delete_after_forward,
                 config.notification_config.delete_mms_notification,
             )),
+            Box::new(DirectSmsHandler::new()),
+            Box::new(StatusReportHandler),
             Box::new(NdisStatHandler),
             Box::new(PDCPDataHandler),
-            Box::new(NetworkSignalHandler::new()),
+            Box::new(NetworkSignalHandler::new(&config.at_config.signal_query_cmd)),
         ];
 
+        let timing = CommandTiming {
+            prompt: Duration::from_secs(config.at_config.prompt_timeout.max(1)),
+            ..CommandTiming::default()
+        };
         Self {
             config,
             notifications,
@@ -106,16 +262,41 @@ impl ATClientActor {
             cmd_tx,
             buffer: Vec::new(),
             urc_tx,
+            timing,
+            reinit_pending: false,
+            outage_since: None,
+            consecutive_timeouts: 0,
+            connected_type: None,
         }
     }
 
     async fn run(&mut self) {
         loop {
             if self.connection.is_none() || !self.connection.as_ref().unwrap().is_connected() {
+                self.wait_for_serial_device().await;
                 if !self.connect().await {
                     sleep(Duration::from_secs(5)).await;
                     continue;
                 }
+                if self.connected_type == Some(ConnectionType::Serial) {
+                    // strip_echo=auto 时串口按无回显处理：每次打开串口都关一次回显（模组重启后会恢复默认的 ATE1）
+                    let cmd_tx = self.cmd_tx.clone();
+                    tokio::spawn(async move {
+                        match crate::retry::send_with_retry(&cmd_tx, "ATE0", false).await {
+                            Ok(resp) if resp.success => debug!("Command echo disabled on serial port"),
+                            Ok(resp) => warn!("Failed to disable command echo: {:?}", resp.error),
+                            Err(e) => warn!("Failed to disable command echo: {}", e),
+                        }
+                    });
+                }
+                if std::mem::take(&mut self.reinit_pending) {
+                    // 指令要经过本 Actor 的 process_loop 执行，只能放到独立任务里发
+                    let config = self.config.clone();
+                    let cmd_tx = self.cmd_tx.clone();
+                    tokio::spawn(async move {
+                        crate::dial_monitor::configure_urc_reporting(&config, &cmd_tx).await;
+                    });
+                }
             }
             
             self.process_loop().await;
@@ -123,13 +304,42 @@ impl ATClientActor {
         }
     }
 
+    /// 串口设备节点不存在（USB 被拔出）时不再每 5 秒尝试打开刷错误日志，
+    /// 改为每秒检查一次节点，只在消失和恢复时各记一条日志并通知前端，节点出现后立即重连
+    async fn wait_for_serial_device(&mut self) {
+        if active_connection_type(&self.config.at_config) != ConnectionType::Serial {
+            return;
+        }
+        let path = std::path::PathBuf::from(&self.config.at_config.serial.port);
+        if path.exists() {
+            return;
+        }
+
+        warn!("Serial device {} is missing (unplugged?), pausing reconnection until it reappears", path.display());
+        broadcast_device_missing(true);
+        while !path.exists() {
+            // 等待期间切换到网络连接时不再等串口
+            if active_connection_type(&self.config.at_config) != ConnectionType::Serial {
+                broadcast_device_missing(false);
+                return;
+            }
+            sleep(SERIAL_PRESENCE_POLL).await;
+        }
+        info!("Serial device {} reappeared, reconnecting", path.display());
+        broadcast_device_missing(false);
+        // 节点刚出现时驱动可能还没初始化完，稍等再打开
+        sleep(SERIAL_PRESENCE_POLL).await;
+    }
+
     async fn connect(&mut self) -> bool {
-        let mut connection: Box<dyn ATConnection> = match self.config.at_config.connection_type {
+        let kind = active_connection_type(&self.config.at_config);
+        let mut connection: Box<dyn ATConnection> = match kind {
             ConnectionType::Network => {
                 Box::new(NetworkATConnection::new(
                     self.config.at_config.network.host.clone(),
                     self.config.at_config.network.port,
                     self.config.at_config.network.timeout,
+                    self.config.at_config.network.keepalive(),
                 ))
             },
             ConnectionType::Serial => {
@@ -138,11 +348,20 @@ impl ATClientActor {
                     self.config.at_config.serial.baudrate,
                 ))
             }
+            ConnectionType::Simulation => Box::new(SimulatedATConnection::new()),
         };
 
         match connection.connect().await {
             Ok(_) => {
                 self.connection = Some(connection);
+                self.connected_type = Some(kind);
+                crate::metrics::record_connected();
+                broadcast_connection_state(true);
+                if let Some(since) = self.outage_since.take() {
+                    let outage = since.elapsed();
+                    info!("AT connection restored after {}s", outage.as_secs());
+                    self.notify_connection(format!("与模组的连接已恢复，中断 {}", format_outage(outage)), Some(outage));
+                }
                 true
             }
             Err(e) => {
@@ -154,6 +373,8 @@ impl ATClientActor {
 
     async fn process_loop(&mut self) {
         let mut buf = [0u8; 1024];
+        let max_age = self.config.at_config.session_max_age;
+        let mut session_deadline = (max_age > 0).then(|| tokio::time::Instant::now() + Duration::from_secs(max_age));
 
         loop {
             if let Some(conn) = &self.connection {
@@ -171,24 +392,81 @@ impl ATClientActor {
                     // Actually, we should just handle sending here or pass `conn` to helper.
                     // To satisfy borrow checker, we pass `conn` and `&mut self.buffer` etc. separately.
                     
-                    if let Err(e) = Self::send_command_and_wait(
+                    let cmd_text = cmd.cmd.clone();
+                    let mut timed_out = false;
+                    let (resp_tx, resp_rx) = oneshot::channel();
+                    let result = Self::send_command_and_wait(
                         conn, 
                         &mut self.buffer, 
                         &self.handlers, 
                         &self.urc_tx,
+                        &self.timing,
                         cmd, 
-                        reply_tx
-                    ).await {
+                        resp_tx
+                    ).await;
+                    if let Ok(resp) = resp_rx.await {
+                        timed_out = resp.error.as_deref() == Some("Timeout");
+                        crate::sms_report::observe(&cmd_text, &resp);
+                        let _ = reply_tx.send(resp);
+                    }
+                    if let Err(e) = result {
                          error!("Error processing command: {}", e);
-                         if e.to_string().contains("Closed") || e.to_string().contains("Not connected") {
-                            self.connection = None;
+                         if e.is_disconnect() {
+                            self.mark_disconnected(false);
                             break; 
                          }
                     }
+
+                    // 链路仍"在线"但模组对所有指令都不再应答：断开重连，按配置尝试复位或执行恢复脚本
+                    self.consecutive_timeouts = if timed_out { self.consecutive_timeouts + 1 } else { 0 };
+                    let limit = self.config.at_config.wedge_timeout_count;
+                    if limit > 0 && self.consecutive_timeouts >= limit {
+                        let count = std::mem::take(&mut self.consecutive_timeouts);
+                        error!("Modem did not answer {} consecutive commands, treating it as wedged", count);
+                        let reset_cmd = self.config.at_config.wedge_reset_cmd.trim();
+                        if !reset_cmd.is_empty() && command_rules::sanitize_command(reset_cmd).is_ok() {
+                            warn!("Sending {} to the wedged modem", reset_cmd);
+                            let _ = conn.send(format!("{}\r", reset_cmd).as_bytes()).await;
+                        }
+                        let _ = conn.close().await;
+                        // 用专门的卡死通知代替普通的断开通知
+                        self.outage_since = Some(std::time::Instant::now());
+                        self.mark_disconnected(false);
+                        self.notify_connection(format!("模组连续 {} 条指令无响应，已断开重连", count), None);
+                        let script = self.config.at_config.wedge_recovery_script.trim().to_string();
+                        if !script.is_empty() {
+                            tokio::spawn(run_recovery_script(script));
+                        }
+                        break;
+                    }
+                }
+                // 定时重连只在两条指令之间触发：select 在指令执行期间不会轮询到这里
+                _ = session_expiry(session_deadline) => {
+                    if !self.buffer.is_empty() {
+                        // 还有未收完的 URC，稍后再断
+                        session_deadline = Some(tokio::time::Instant::now() + SESSION_IDLE_RETRY);
+                        continue;
+                    }
+                    info!("AT session reached session_max_age ({}s), reconnecting", max_age);
+                    let _ = conn.close().await;
+                    self.mark_disconnected(true);
+                    self.reinit_pending = true;
+                    break;
+                }
+                _ = CONNECTION_SWITCH.notified() => {
+                    // 连接建立前就已按新方式连上时，残留的许可不再触发重连
+                    if self.connected_type == Some(active_connection_type(&self.config.at_config)) {
+                        continue;
+                    }
+                    info!("Dropping current AT connection to switch transport");
+                    let _ = conn.close().await;
+                    self.mark_disconnected(true);
+                    break;
                 }
                 res = conn.receive(&mut buf) => {
                     match res {
                         Ok(n) if n > 0 => {
+                            self.consecutive_timeouts = 0;
                             self.buffer.extend_from_slice(&buf[..n]);
                             Self::process_buffer_lines(
                                 &mut self.buffer, 
@@ -198,12 +476,12 @@ impl ATClientActor {
                         }
                         Ok(_) => {
                             warn!("Connection closed (EOF)");
-                            self.connection = None;
+                            self.mark_disconnected(false);
                             break;
                         }
                         Err(e) => {
                             error!("Read error: {}", e);
-                            self.connection = None;
+                            self.mark_disconnected(false);
                             break;
                         }
                     }
@@ -212,46 +490,85 @@ impl ATClientActor {
         }
     }
 
+    /// 丢弃失效连接：清空旧连接残留的半截数据，并通知前端连接已断开
+    ///
+    /// `planned` 为主动断开（定时重连、切换连接方式），不发送断开/恢复通知。
+    fn mark_disconnected(&mut self, planned: bool) {
+        self.connection = None;
+        self.buffer.clear();
+        crate::metrics::record_disconnected();
+        broadcast_connection_state(false);
+        if !planned && self.outage_since.is_none() {
+            self.outage_since = Some(std::time::Instant::now());
+            self.notify_connection("与模组的连接已断开，正在重连".to_string(), None);
+        }
+    }
+
+    /// 按 `notify_connection` 发送连接状态通知，放到独立任务里避免推送阻塞 Actor
+    fn notify_connection(&self, message: String, outage: Option<Duration>) {
+        let notifications = self.notifications.clone();
+        let data = serde_json::json!({
+            "state": if outage.is_some() { "connected" } else { "disconnected" },
+            "outageSecs": outage.map(|d| d.as_secs()),
+        });
+        tokio::spawn(async move {
+            notifications.notify_with_data("AT", &message, NotificationType::Connection, Some(data)).await;
+        });
+    }
+
     async fn send_command_and_wait(
         conn: &mut Box<dyn ATConnection>,
         buffer: &mut Vec<u8>,
         handlers: &[Box<dyn MessageHandler>],
         urc_tx: &mpsc::Sender<String>,
-        cmd: String,
+        timing: &CommandTiming,
+        cmd: AtCommand,
         reply_tx: oneshot::Sender<ATResponse>
-    ) -> anyhow::Result<()> {
+    ) -> Result<(), ConnectionError> {
         
         // 1. 先休眠：给模块 100ms 喘息时间，同时让上一条指令迟到的尾巴(如 OK)落入操作系统的接收缓存
-        sleep(Duration::from_millis(100)).await;
+        sleep(timing.settle).await;
 
         // 2. 发射前清膛：抽干缓存中滞留数据（200ms 窗口，覆盖 URC 风暴尾巴）
         let mut buf = [0u8; 1024];
-        while let Ok(Ok(n)) = timeout(Duration::from_millis(200), conn.receive(&mut buf)).await {
+        while let Ok(Ok(n)) = timeout(timing.drain, conn.receive(&mut buf)).await {
             if n == 0 { break; }
             buffer.extend_from_slice(&buf[..n]);
         }
         
         // 悄悄处理掉滞留数据里可能混杂的有用 URC（如短信），但绝不触发网页全局刷新
+        let mut cmt_pending = false;
         while let Some(line) = extract_next_line(buffer) {
+            let Some(line) = join_cmt_pdu(line, buffer) else {
+                cmt_pending = true;
+                break;
+            };
             if Self::is_urc(handlers, &line) {
                 let _ = urc_tx.send(line).await;
             }
         }
         
         // 【终极防粘包杀招】：如果 buffer 里还有没换行的半截孤儿字符（比如单独的 'O' 或 '\r'），直接抹杀！
-        buffer.clear();
+        // 例外：+CMT/+CDS 头已到而 PDU 行未到齐时保留，PDU 到达后在下面的收集循环里拼好交给 URC
+        if !cmt_pending {
+            buffer.clear();
+        }
 
-        let clean_cmd = cmd.trim();
+        // 所有路径下发的指令在这里统一校验，夹带换行/控制字符的直接拒绝
+        let response_timeout = cmd.timeout.unwrap_or(timing.response);
+        let cmd = cmd.cmd;
+        let clean_cmd = match command_rules::sanitize_command(&cmd) {
+            Ok(clean_cmd) => clean_cmd,
+            Err(e) => {
+                warn!("Rejected command {:?}: {}", cmd, e);
+                let _ = reply_tx.send(ATResponse::error(e));
+                return Ok(());
+            }
+        };
         debug!("Sending Command: {}", clean_cmd);
+        crate::metrics::record_command();
         
-        // 智能提取当前查询的期望前缀
-        let expected_prefix = if clean_cmd.starts_with("AT") {
-            let core = &clean_cmd[2..];
-            let end = core.find(|c: char| c == '?' || c == '=').unwrap_or(core.len());
-            &core[..end]
-        } else {
-            ""
-        };
+        let mut collector = ResponseCollector::new(clean_cmd);
 
         // 3. 发射指令（严格对齐 Python 原版，只能发送 \r，绝对不能有 \n！）
         if let Err(e) = conn.send(clean_cmd.as_bytes()).await {
@@ -263,84 +580,62 @@ impl ATClientActor {
              return Ok(());
         }
 
-        let start = std::time::Instant::now();
-        let timeout_dur = Duration::from_secs(10);
-        let mut response_data = String::new();
+        // 超时从最后一次收到本指令的响应行开始计算，
+        // 超长输出（如 AT+CMGL 列出大量短信）只要还在持续返回就不会被截断
+        let mut last_progress = std::time::Instant::now();
+        let sent_at = last_progress;
         
         loop {
-            if start.elapsed() > timeout_dur {
+            if last_progress.elapsed() > response_timeout {
+                crate::metrics::record_timeout();
                 let _ = reply_tx.send(ATResponse::error("Timeout".to_string()));
                 return Ok(());
             }
+            // 等待提示符的指令单独计时，模组迟迟不给 `>` 时尽快失败，并发 ESC 防止提示符迟到后模组一直等正文
+            if collector.terminator == Terminator::Prompt && sent_at.elapsed() > timing.prompt {
+                warn!("Prompt not received within {:?} for {}", timing.prompt, clean_cmd);
+                let _ = conn.send(b"\x1B").await;
+                let _ = reply_tx.send(ATResponse::error("Prompt not received".to_string()));
+                return Ok(());
+            }
 
-            match timeout(Duration::from_secs(1), conn.receive(&mut buf)).await {
+            match timeout(timing.poll, conn.receive(&mut buf)).await {
                 Ok(Ok(n)) => {
                     if n == 0 { 
                          let _ = reply_tx.send(ATResponse::error("Connection closed".to_string()));
-                         anyhow::bail!("Closed");
+                         return Err(ConnectionError::Closed);
                     }
                     buffer.extend_from_slice(&buf[..n]);
                     
                     while let Some(line) = extract_next_line(buffer) {
+                        let Some(line) = join_cmt_pdu(line, buffer) else { break };
                         debug!("RCV: {}", line);
-                        
-                        // 校验这行数据是不是针对我们当前命令的回应
-                        let mut is_my_response = false;
-                        if !expected_prefix.is_empty() && line.starts_with(expected_prefix) {
-                            is_my_response = true;
-                        }
-
-                        // URC bypass: lines starting with ^ or + that are not this command's response
-                        let is_urc_line = !is_my_response
-                            && (line.starts_with('^') || line.starts_with('+'))
-                            && line != "OK"
-                            && !line.contains("ERROR");
-
-                        if is_urc_line {
-                            if Self::is_urc(handlers, &line) {
-                                let _ = urc_tx.send(line.clone()).await;
+                        match collector.feed(line) {
+                            LineOutcome::Urc(line) => {
+                                if Self::is_urc(handlers, &line) {
+                                    let _ = urc_tx.send(line.clone()).await;
+                                }
+                                if let Some(tx) = crate::server::WS_BROADCASTER.get().filter(|_| !crate::urc::is_muted_line(&line)) {
+                                    let ws_msg = serde_json::json!({
+                                        "type": "raw_data",
+                                        "data": line
+                                    }).to_string();
+                                    let _ = tx.send(ws_msg);
+                                }
                             }
-                            if let Some(tx) = crate::server::WS_BROADCASTER.get() {
-                                let ws_msg = serde_json::json!({
-                                    "type": "raw_data",
-                                    "data": line
-                                }).to_string();
-                                let _ = tx.send(ws_msg);
+                            LineOutcome::Pending => {
+                                last_progress = std::time::Instant::now();
+                            }
+                            LineOutcome::Done(resp) => {
+                                let _ = reply_tx.send(resp);
+                                return Ok(());
                             }
-                            continue;
-                        }
-                        // 正常的查询结果，精准拼装
-                        if line == "OK" {
-                             response_data.push_str("OK");
-                             
-                             // 【终极绝杀补丁】：Vue 前端严格模式兼容 (Prefix Forging)
-                             // 如果 Vue 期望一个前缀，但模块返回的是纯数据（如 CGSN 的 IMEI）或纯 OK，我们强行伪造前缀骗过 Vue 的校验
-                             if !expected_prefix.is_empty() && !response_data.contains(expected_prefix) {
-                                 let data_only = response_data.replace("OK", "").trim().to_string();
-                                 if data_only.is_empty() {
-                                     // 纯 OK 响应（如 AT+CMGF=0），追加伪造的前缀
-                                     response_data = format!("{}\r\nOK", expected_prefix);
-                                 } else {
-                                     // 纯数据响应（如 AT+CGSN 返回 864...），按标准格式拼装前缀
-                                     response_data = format!("{}: {}\r\nOK", expected_prefix, data_only);
-                                 }
-                             }
-                             
-                             let _ = reply_tx.send(ATResponse::ok(Some(response_data)));
-                             return Ok(());
-                        } else if line.contains("ERROR") {
-                             response_data.push_str(&line);
-                             let _ = reply_tx.send(ATResponse::error(response_data));
-                             return Ok(());
-                        } else if line.starts_with(">") {
-                             response_data.push_str(&line);
-                             let _ = reply_tx.send(ATResponse::ok(Some(response_data))); 
-                             return Ok(());
-                        } else {
-                             response_data.push_str(&line);
-                             response_data.push_str("\r\n");
                         }
                     }
+                    if let Some(resp) = collector.take_prompt(buffer) {
+                        let _ = reply_tx.send(resp);
+                        return Ok(());
+                    }
                 },
                 Ok(Err(e)) => {
                      let _ = reply_tx.send(ATResponse::error(e.to_string()));
@@ -357,11 +652,12 @@ impl ATClientActor {
         urc_tx: &mpsc::Sender<String>
     ) {
          while let Some(line) = extract_next_line(buffer) {
+             let Some(line) = join_cmt_pdu(line, buffer) else { break };
              debug!("URC/Idle: {}", line);
              if Self::is_urc(handlers, &line) {
                  let _ = urc_tx.send(line.clone()).await;
                  // 【修复】：只有真正的 URC 才全局广播，避免触发前端死循环
-                 if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+                 if let Some(tx) = crate::server::WS_BROADCASTER.get().filter(|_| !crate::urc::is_muted_line(&line)) {
                      let _ = tx.send(serde_json::json!({"type": "raw_data", "data": line}).to_string());
                  }
              }
@@ -376,6 +672,208 @@ impl ATClientActor {
     }
 }
 
+/// 单行响应的归类结果
+#[derive(Debug)]
+enum LineOutcome {
+    /// 不属于当前指令的主动上报，交给 URC 分发
+    Urc(String),
+    /// 已并入响应，继续等待终止行
+    Pending,
+    /// 收到终止行，响应完整
+    Done(ATResponse),
+}
+
+/// 需要先等 `>` 提示符、再由调用方单独发送正文的指令
+const PROMPT_COMMANDS: &[&str] = &["AT+CMGS=", "AT+CMGW=", "AT+CMGC="];
+
+/// 正文（以 Ctrl-Z 结尾）提交后模组先回的结果行，随后才是 OK
+const SUBMIT_RESULT_PREFIXES: &[&str] = &["+CMGS", "+CMGW", "+CMGC"];
+
+/// 指令的真正终止行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Terminator {
+    /// OK / ERROR
+    Final,
+    /// `>` 提示符（ERROR 同样终止）
+    Prompt,
+    /// 正文提交：`+CMGS: <mr>` 等中间结果并入响应，OK / ERROR 终止
+    Submit,
+}
+
+impl Terminator {
+    fn for_command(clean_cmd: &str) -> Self {
+        let upper = clean_cmd.to_uppercase();
+        if clean_cmd.ends_with('\x1A') {
+            Terminator::Submit
+        } else if PROMPT_COMMANDS.iter().any(|p| upper.starts_with(p)) {
+            Terminator::Prompt
+        } else {
+            Terminator::Final
+        }
+    }
+}
+
+/// 单条指令的响应收集器：逐行喂入，直到该指令的终止行（见 `Terminator`）
+///
+/// 与 IO 解耦，跨多次 `receive` 的分段数据只要按行喂入即可完整拼装。
+struct ResponseCollector {
+    /// 当前查询的期望前缀，如 `AT+CMGL=4` -> `+CMGL`
+    expected_prefix: String,
+    terminator: Terminator,
+    response_data: String,
+}
+
+impl ResponseCollector {
+    fn new(clean_cmd: &str) -> Self {
+        // 智能提取当前查询的期望前缀
+        let expected_prefix = match clean_cmd.strip_prefix("AT") {
+            Some(core) => {
+                let end = core.find(['?', '=']).unwrap_or(core.len());
+                core[..end].to_string()
+            }
+            None => String::new(),
+        };
+        Self {
+            expected_prefix,
+            terminator: Terminator::for_command(clean_cmd),
+            response_data: String::new(),
+        }
+    }
+
+    /// 模组发出的 `>` 提示符后面通常不带换行，按行切分永远等不到它。
+    /// 等待提示符的指令在缓冲区只剩 `>` 时直接结束。
+    fn take_prompt(&mut self, buffer: &mut Vec<u8>) -> Option<ATResponse> {
+        if self.terminator != Terminator::Prompt
+            || !String::from_utf8_lossy(buffer).trim().starts_with('>')
+        {
+            return None;
+        }
+        buffer.clear();
+        self.response_data.push('>');
+        Some(ATResponse::ok(Some(std::mem::take(&mut self.response_data))))
+    }
+
+    fn feed(&mut self, line: String) -> LineOutcome {
+        let expected_prefix = self.expected_prefix.as_str();
+
+        // 校验这行数据是不是针对我们当前命令的回应
+        let is_my_response = (!expected_prefix.is_empty() && line.starts_with(expected_prefix))
+            || (self.terminator == Terminator::Submit
+                && SUBMIT_RESULT_PREFIXES.iter().any(|p| line.starts_with(p)));
+
+        // URC bypass: lines starting with ^ or + that are not this command's response
+        let is_urc_line = !is_my_response
+            && (line.starts_with('^') || line.starts_with('+'))
+            && line != "OK"
+            && !line.contains("ERROR");
+
+        if is_urc_line {
+            return LineOutcome::Urc(line);
+        }
+
+        let response_data = &mut self.response_data;
+        // 正常的查询结果，精准拼装
+        if line == "OK" {
+            response_data.push_str("OK");
+
+            // 【终极绝杀补丁】：Vue 前端严格模式兼容 (Prefix Forging)
+            // 如果 Vue 期望一个前缀，但模块返回的是纯数据（如 CGSN 的 IMEI）或纯 OK，我们强行伪造前缀骗过 Vue 的校验
+            if !expected_prefix.is_empty() && !response_data.contains(expected_prefix) {
+                let data_only = response_data.replace("OK", "").trim().to_string();
+                if data_only.is_empty() {
+                    // 纯 OK 响应（如 AT+CMGF=0），追加伪造的前缀
+                    *response_data = format!("{}\r\nOK", expected_prefix);
+                } else {
+                    // 纯数据响应（如 AT+CGSN 返回 864...），按标准格式拼装前缀
+                    *response_data = format!("{}: {}\r\nOK", expected_prefix, data_only);
+                }
+            }
+
+            LineOutcome::Done(ATResponse::ok(Some(std::mem::take(response_data))))
+        } else if line.contains("ERROR") {
+            response_data.push_str(&line);
+            LineOutcome::Done(ATResponse::error(std::mem::take(response_data)))
+        } else if self.terminator == Terminator::Prompt && line.starts_with('>') {
+            response_data.push_str(&line);
+            LineOutcome::Done(ATResponse::ok(Some(std::mem::take(response_data))))
+        } else {
+            response_data.push_str(&line);
+            response_data.push_str("\r\n");
+            LineOutcome::Pending
+        }
+    }
+}
+
+/// 定时重连时缓冲区仍有数据，推迟多久再试
+const SESSION_IDLE_RETRY: Duration = Duration::from_secs(1);
+
+/// 到达会话最长时间；未设置时永不触发
+async fn session_expiry(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// 串口设备节点缺失时检查其是否重新出现的间隔
+const SERIAL_PRESENCE_POLL: Duration = Duration::from_secs(1);
+
+/// 广播串口设备节点的消失/恢复
+fn broadcast_device_missing(missing: bool) {
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = serde_json::json!({
+            "type": "connection",
+            "data": {
+                "state": if missing { "device_missing" } else { "device_present" }
+            }
+        }).to_string();
+        let _ = tx.send(msg);
+    }
+}
+
+/// 广播模组连接状态变化，前端可据此在重连后重新拉取信号/状态
+fn broadcast_connection_state(connected: bool) {
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = serde_json::json!({
+            "type": "connection",
+            "data": {
+                "state": if connected { "connected" } else { "disconnected" }
+            }
+        }).to_string();
+        let _ = tx.send(msg);
+    }
+}
+
+/// 恢复脚本最长执行时间，超时后结束脚本
+const RECOVERY_SCRIPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 模组卡死后执行 `wedge_recovery_script`，不阻塞 Actor 重连
+async fn run_recovery_script(script: String) {
+    warn!("Running modem recovery script {}", script);
+    let mut cmd = tokio::process::Command::new(&script);
+    cmd.kill_on_drop(true);
+    match tokio::time::timeout(RECOVERY_SCRIPT_TIMEOUT, cmd.status()).await {
+        Ok(Ok(status)) if status.success() => info!("Recovery script {} finished", script),
+        Ok(Ok(status)) => warn!("Recovery script {} exited with {}", script, status),
+        Ok(Err(e)) => error!("Failed to run recovery script {}: {}", script, e),
+        Err(_) => error!("Recovery script {} timed out after {}s", script, RECOVERY_SCRIPT_TIMEOUT.as_secs()),
+    }
+}
+
+/// 中断时长的可读形式，如 `2分5秒`、`1小时3分`
+fn format_outage(d: Duration) -> String {
+    let secs = d.as_secs();
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}秒", s),
+        (0, m, s) => format!("{}分{}秒", m, s),
+        (h, m, _) => format!("{}小时{}分", h, m),
+    }
+}
+
+/// 按 `\n` 取出一行
+///
+/// 多字节 UTF-8 字符的各字节都不会是 0x0A，所以只在换行处切分不会截断字符：跨 receive 到达的半个字符
+/// 会留在缓冲区里，等换行到达后整行一起解码。
 fn extract_next_line(buffer: &mut Vec<u8>) -> Option<String> {
     if let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
         // 直接使用切片读取，避免 collect 产生额外的 Vec<u8> 内存分配
@@ -390,3 +888,305 @@ fn extract_next_line(buffer: &mut Vec<u8>) -> Option<String> {
     }
     None
 }
+
+/// `+CMT:` 直投短信、`+CDS:` 状态报告的 PDU 在下一行，把两行拼成一条 URC（以 `\n` 分隔）
+/// 交给 DirectSmsHandler / StatusReportHandler。
+/// PDU 行尚未收全时把头部放回缓冲区并返回 None，等下一批数据到达再拼。
+fn join_cmt_pdu(line: String, buffer: &mut Vec<u8>) -> Option<String> {
+    if !line.starts_with("+CMT:") && !line.starts_with("+CDS:") {
+        return Some(line);
+    }
+    if let Some(pdu) = extract_next_line(buffer) {
+        return Some(format!("{}\n{}", line, pdu));
+    }
+    let mut restored = format!("{}\r\n", line).into_bytes();
+    restored.append(buffer);
+    *buffer = restored;
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::mock::MockATConnection;
+
+    /// 按任意切分的字节块喂给收集器，模拟跨多次 receive 的数据
+    fn collect_chunks(cmd: &str, chunks: &[&str]) -> (Option<ATResponse>, Vec<String>) {
+        let mut collector = ResponseCollector::new(cmd);
+        let mut buffer = Vec::new();
+        let mut urcs = Vec::new();
+        for chunk in chunks {
+            buffer.extend_from_slice(chunk.as_bytes());
+            while let Some(line) = extract_next_line(&mut buffer) {
+                match collector.feed(line) {
+                    LineOutcome::Urc(line) => urcs.push(line),
+                    LineOutcome::Pending => {}
+                    LineOutcome::Done(resp) => return (Some(resp), urcs),
+                }
+            }
+        }
+        (None, urcs)
+    }
+
+    #[test]
+    fn formats_outage_duration() {
+        assert_eq!(format_outage(Duration::from_secs(42)), "42秒");
+        assert_eq!(format_outage(Duration::from_secs(125)), "2分5秒");
+        assert_eq!(format_outage(Duration::from_secs(3780)), "1小时3分");
+    }
+
+    #[test]
+    fn multibyte_split_across_reads_is_preserved() {
+        let line = "^EONS: 1,\"中国联通\"\r\n".as_bytes();
+        // 在"中"字的三个字节中间切开
+        let split = line.iter().position(|&b| b >= 0x80).unwrap() + 1;
+        let mut buffer = line[..split].to_vec();
+        assert!(extract_next_line(&mut buffer).is_none());
+        buffer.extend_from_slice(&line[split..]);
+        assert_eq!(extract_next_line(&mut buffer).unwrap(), "^EONS: 1,\"中国联通\"");
+    }
+
+    /// 测试用时序：整体缩短到毫秒级
+    const FAST: CommandTiming = CommandTiming {
+        settle: Duration::from_millis(1),
+        drain: Duration::from_millis(5),
+        response: Duration::from_millis(50),
+        poll: Duration::from_millis(10),
+        prompt: Duration::from_millis(20),
+    };
+
+    /// 通过 MockATConnection 跑一遍 send_command_and_wait，返回回复、执行结果、转发的 URC 与实际发送的字节
+    async fn run_command(
+        conn: MockATConnection,
+        cmd: &str,
+    ) -> (ATResponse, Result<(), ConnectionError>, Vec<String>, String) {
+        let sent = conn.sent();
+        let mut conn: Box<dyn ATConnection> = Box::new(conn);
+        let handlers: Vec<Box<dyn MessageHandler>> = vec![Box::new(NewSMSHandler::new(false, false)), Box::new(StatusReportHandler)];
+        let (urc_tx, mut urc_rx) = mpsc::channel(16);
+        let (reply_tx, reply_rx) = oneshot::channel();
+        let mut buffer = Vec::new();
+
+        let result = ATClientActor::send_command_and_wait(
+            &mut conn, &mut buffer, &handlers, &urc_tx, &FAST, cmd.into(), reply_tx,
+        )
+        .await;
+        let resp = reply_rx.await.expect("every command must get a reply");
+        let mut urcs = Vec::new();
+        while let Ok(line) = urc_rx.try_recv() {
+            urcs.push(line);
+        }
+        let sent = String::from_utf8(sent.lock().unwrap().clone()).unwrap();
+        (resp, result, urcs, sent)
+    }
+
+    #[tokio::test]
+    async fn mock_command_completes_on_ok() {
+        let conn = MockATConnection::new().reply(&["\r\n+CSQ: 20,99\r\n", "\r\nOK\r\n"]);
+        let (resp, result, urcs, sent) = run_command(conn, "AT+CSQ").await;
+        assert!(result.is_ok());
+        assert!(resp.success);
+        assert_eq!(resp.data.unwrap(), "+CSQ: 20,99\r\nOK");
+        assert!(urcs.is_empty());
+        assert_eq!(sent, "AT+CSQ\r");
+    }
+
+    #[tokio::test]
+    async fn simulated_connection_answers_common_queries() {
+        let mut sim = SimulatedATConnection::new();
+        sim.connect().await.unwrap();
+        let mut conn: Box<dyn ATConnection> = Box::new(sim);
+        let handlers: Vec<Box<dyn MessageHandler>> = Vec::new();
+        let (urc_tx, _urc_rx) = mpsc::channel(16);
+        let mut buffer = Vec::new();
+        for (cmd, expected) in [("AT+CSQ", Some("+CSQ: 24,99\r\nOK")), ("AT+COPS?", Some("+COPS: 0,2,\"46000\",7\r\nOK")), ("AT^FOO?", None)] {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            ATClientActor::send_command_and_wait(&mut conn, &mut buffer, &handlers, &urc_tx, &FAST, cmd.into(), reply_tx)
+                .await
+                .unwrap();
+            let resp = reply_rx.await.unwrap();
+            assert_eq!(resp.success, expected.is_some(), "{}", cmd);
+            assert_eq!(resp.data.as_deref(), expected.filter(|_| resp.success));
+        }
+    }
+
+    #[tokio::test]
+    async fn mock_command_reports_error() {
+        let conn = MockATConnection::new().reply(&["\r\n+CME ERROR: 10\r\n"]);
+        let (resp, result, urcs, _) = run_command(conn, "AT+CPIN?").await;
+        assert!(result.is_ok());
+        assert!(!resp.success);
+        assert_eq!(resp.error.unwrap(), "+CME ERROR: 10");
+        assert!(urcs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn mock_command_times_out_without_reply() {
+        let (resp, result, _, sent) = run_command(MockATConnection::new(), "AT^SLOW").await;
+        // 超时只是本条指令失败，连接本身仍然可用
+        assert!(result.is_ok());
+        assert!(!resp.success);
+        assert_eq!(resp.error.unwrap(), "Timeout");
+        assert_eq!(sent, "AT^SLOW\r");
+    }
+
+    #[tokio::test]
+    async fn mock_missing_prompt_fails_before_response_timeout() {
+        let (resp, result, _, sent) = run_command(MockATConnection::new(), "AT+CMGS=20").await;
+        assert!(result.is_ok());
+        assert!(!resp.success);
+        assert_eq!(resp.error.unwrap(), "Prompt not received");
+        // 放弃时发 ESC 取消，避免迟到的提示符让模组一直等待正文
+        assert_eq!(sent, "AT+CMGS=20\r\x1B");
+    }
+
+    #[tokio::test]
+    async fn mock_urc_mid_response_is_forwarded_not_collected() {
+        let conn = MockATConnection::new().reply(&[
+            "\r\n+CPMS: \"SM\",8,10",
+            "\r\n+CMTI: \"SM\",5\r\n",
+            "\r\nOK\r\n",
+        ]);
+        let (resp, result, urcs, _) = run_command(conn, "AT+CPMS?").await;
+        assert!(result.is_ok());
+        assert!(resp.success);
+        assert_eq!(resp.data.unwrap(), "+CPMS: \"SM\",8,10\r\nOK");
+        assert_eq!(urcs, vec!["+CMTI: \"SM\",5".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn mock_echo_is_not_treated_as_urc() {
+        // ATE1 时模组先回显指令本身，回显行留在响应里，由 server 侧的输出过滤去掉
+        let conn = MockATConnection::new().reply(&["AT+CSQ\r\r\n+CSQ: 20,99\r\n\r\nOK\r\n"]);
+        let (resp, result, urcs, _) = run_command(conn, "AT+CSQ").await;
+        assert!(result.is_ok());
+        assert!(resp.success);
+        assert_eq!(resp.data.unwrap(), "AT+CSQ\r\n+CSQ: 20,99\r\nOK");
+        assert!(urcs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn mock_stale_tail_does_not_finish_next_command() {
+        // 上一条指令迟到的 OK 与夹带的 URC 在发送前被抽干，URC 照常分发
+        let conn = MockATConnection::new()
+            .pending("OK\r\n+CMTI: \"SM\",3\r\n")
+            .reply(&["\r\n+CGSN: 864000000000000\r\n\r\nOK\r\n"]);
+        let (resp, _, urcs, _) = run_command(conn, "AT+CGSN").await;
+        assert_eq!(resp.data.unwrap(), "+CGSN: 864000000000000\r\nOK");
+        assert_eq!(urcs, vec!["+CMTI: \"SM\",3".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn mock_eof_reports_closed() {
+        let conn = MockATConnection::new().reply(&[""]);
+        let (resp, result, _, _) = run_command(conn, "AT").await;
+        assert!(!resp.success);
+        assert!(matches!(result, Err(ConnectionError::Closed)));
+    }
+
+    #[test]
+    fn chunked_cmgl_keeps_every_record() {
+        let pdu1 = "00040B913108108300F0000062016121436523054AB29A0C02";
+        let pdu2 = "00040B913108108300F0000062016121436523054AB29A0C04";
+        let full = format!(
+            "+CMGL: 0,1,,24\r\n{}\r\n+CMTI: \"SM\",5\r\n+CMGL: 1,1,,24\r\n{}\r\n\r\nOK\r\n",
+            pdu1, pdu2
+        );
+        // 在记录中间、行尾 \r 与 \n 之间等位置切开
+        let chunks = [&full[..9], &full[9..30], &full[30..75], &full[75..76], &full[76..118], &full[118..]];
+
+        let (resp, urcs) = collect_chunks("AT+CMGL=4", &chunks);
+        let resp = resp.expect("response should complete on OK");
+        assert!(resp.success);
+        assert_eq!(urcs, vec!["+CMTI: \"SM\",5".to_string()]);
+
+        let data = resp.data.unwrap();
+        assert_eq!(crate::pdu::extract_pdus(&data), vec![pdu1.to_string(), pdu2.to_string()]);
+        assert!(data.ends_with("OK"));
+    }
+
+    #[test]
+    fn submit_waits_for_final_ok_after_result_line() {
+        let (resp, urcs) = collect_chunks("0011000B915121551532F40000AA0AE8329BFD4697D9EC37\x1A", &["\r\n+CMGS: 12\r\n", "\r\nOK\r\n"]);
+        let resp = resp.expect("submit should complete on OK");
+        assert!(resp.success);
+        assert!(urcs.is_empty());
+        assert_eq!(resp.data.unwrap(), "+CMGS: 12\r\nOK");
+    }
+
+    #[test]
+    fn prompt_without_newline_finishes_prompt_command() {
+        let mut collector = ResponseCollector::new("AT+CMGS=24");
+        let mut buffer = b"\r\n> ".to_vec();
+        assert!(extract_next_line(&mut buffer).is_none());
+        let resp = collector.take_prompt(&mut buffer).expect("prompt should finish AT+CMGS");
+        assert_eq!(resp.data.unwrap(), ">");
+
+        // 非提示符指令中出现的 `>` 只是普通数据行
+        let (resp, _) = collect_chunks("AT^CUSTOM", &["> data\r\n"]);
+        assert!(resp.is_none());
+    }
+
+    #[test]
+    fn cmt_header_waits_for_pdu_line() {
+        let mut buffer = b"+CMT: ,24\r\n0891".to_vec();
+        let line = extract_next_line(&mut buffer).unwrap();
+        assert!(join_cmt_pdu(line, &mut buffer).is_none());
+
+        buffer.extend_from_slice(b"683108\r\n");
+        let line = extract_next_line(&mut buffer).unwrap();
+        assert_eq!(join_cmt_pdu(line, &mut buffer).unwrap(), "+CMT: ,24\n0891683108");
+        assert!(buffer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn split_status_report_before_command_is_not_dropped() {
+        let conn = MockATConnection::new()
+            .pending("\r\n+CDS: 25\r\n0891")
+            .reply(&["683108\r\n", "\r\n+CSQ: 20,99\r\n\r\nOK\r\n"]);
+        let (resp, result, urcs, _) = run_command(conn, "AT+CSQ").await;
+        assert!(result.is_ok());
+        assert_eq!(resp.data.unwrap(), "+CSQ: 20,99\r\nOK");
+        assert_eq!(urcs, vec!["+CDS: 25\n0891683108"]);
+    }
+
+    #[test]
+    fn incomplete_response_is_not_finished_early() {
+        let (resp, _) = collect_chunks("AT+CMGL=4", &["+CMGL: 0,1,,24\r\n0004", "0B91\r\n"]);
+        assert!(resp.is_none());
+    }
+
+    #[tokio::test]
+    async fn supervisor_restarts_panicked_actor() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let (tx, rx) = mpsc::channel(4);
+        let spawned = Arc::new(AtomicUsize::new(0));
+        let counter = spawned.clone();
+        tokio::spawn(supervise(rx, Duration::from_millis(1), move |mut actor_rx: CommandReceiver| {
+            let incarnation = counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(async move {
+                while let Some((cmd, reply)) = actor_rx.recv().await {
+                    if incarnation == 0 {
+                        panic!("simulated actor crash on {}", cmd.cmd);
+                    }
+                    let _ = reply.send(ATResponse::ok(Some(cmd.cmd)));
+                }
+            })
+        }));
+
+        // 第一个 Actor 在执行中崩溃，调用方只拿到接收失败
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(("AT".into(), reply_tx)).await.unwrap();
+        assert!(reply_rx.await.is_err());
+
+        // 同一个发送端在重启后继续可用
+        let (reply_tx, reply_rx) = oneshot::channel();
+        tx.send(("AT+CSQ".into(), reply_tx)).await.unwrap();
+        let resp = timeout(Duration::from_secs(1), reply_rx).await.unwrap().unwrap();
+        assert!(resp.success);
+        assert_eq!(resp.data.as_deref(), Some("AT+CSQ"));
+        assert_eq!(spawned.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,209 @@
+//! 指令级规则表：集中声明个别 AT 指令的请求改写与响应清洗方式
+//!
+//! 新增特殊指令时只需在 `RULES` 中追加一项，不必再往 `handle_client` 里塞分支。
+
+/// 指令级的响应清洗方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFilter {
+    /// 只做连接级的清洗（空行、回显）
+    Default,
+    /// 另外去掉最后一行的 OK
+    StripOk,
+}
+
+/// 连接级的响应清洗开关，每个 WebSocket 连接各自持有
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseTrim {
+    /// 去掉回显：仅当第一行与指令完全相同时去掉该行。模组已 ATE0 关闭回显时应关闭，
+    /// 否则恰好与指令相同的正常响应行会被误删
+    pub strip_echo: bool,
+}
+
+struct CommandRule {
+    /// 指令前缀（区分大小写，与模组回显一致）
+    prefix: &'static str,
+    /// 发送前对指令做的改写
+    rewrite: Option<fn(&str) -> String>,
+    response: ResponseFilter,
+}
+
+static RULES: &[CommandRule] = &[
+    CommandRule {
+        prefix: "AT^SYSCFGEX",
+        rewrite: Some(rewrite_syscfgex),
+        response: ResponseFilter::StripOk,
+    },
+];
+
+fn find_rule(cmd: &str) -> Option<&'static CommandRule> {
+    let cmd = cmd.trim();
+    RULES.iter().find(|rule| cmd.starts_with(rule.prefix))
+}
+
+/// 按规则表改写前端下发的指令，无匹配规则时原样返回
+pub fn rewrite_request(cmd: String) -> String {
+    match find_rule(&cmd).and_then(|rule| rule.rewrite) {
+        Some(rewrite) => rewrite(&cmd),
+        None => cmd,
+    }
+}
+
+/// 按连接设置与规则表清洗模组响应，`cmd` 为实际发送的指令
+///
+/// 依次执行：去掉空行；开启回显清洗时去掉与指令相同的第一行；StripOk 规则去掉最后一行的 OK。
+/// 其余行（包括中间恰好与指令或 OK 相同的行）原样保留。
+pub fn filter_response(cmd: &str, data: &str, trim: ResponseTrim) -> String {
+    let filter = find_rule(cmd).map_or(ResponseFilter::Default, |rule| rule.response);
+    let mut lines: Vec<&str> = data.lines().filter(|line| !line.trim().is_empty()).collect();
+    if trim.strip_echo && lines.first().is_some_and(|first| first.trim() == cmd.trim()) {
+        lines.remove(0);
+    }
+    if filter == ResponseFilter::StripOk && lines.last().is_some_and(|last| last.trim() == "OK") {
+        lines.pop();
+    }
+    lines.join("\r\n")
+}
+
+/// 宽松输入：去掉结尾多余的换行（含手打的 `\r`、`\n` 字面量），
+/// 并给缺少前缀的标准指令补上 `AT`（`+CSQ`、`^HCSQ?` → `AT+CSQ`、`AT^HCSQ?`，`CSQ` → `AT+CSQ`）
+///
+/// 只处理形如 AT 指令的输入，其他内容原样返回，控制指令应在调用前已分流。
+pub fn normalize_lenient(cmd: &str) -> String {
+    let mut cmd = cmd.trim();
+    while let Some(rest) = cmd.strip_suffix("\\r").or_else(|| cmd.strip_suffix("\\n")) {
+        cmd = rest.trim_end();
+    }
+
+    if cmd.get(..2).is_some_and(|p| p.eq_ignore_ascii_case("AT")) {
+        return cmd.to_string();
+    }
+    if cmd.starts_with(['+', '^']) && cmd[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return format!("AT{}", cmd);
+    }
+    // 不带前缀的 3GPP 指令名（C 开头，只含大写字母与数字），可跟 ? 或 =参数
+    let name_end = cmd.find(['?', '=']).unwrap_or(cmd.len());
+    let name = &cmd[..name_end];
+    if name.len() >= 3 && name.starts_with('C') && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+        return format!("AT+{}", cmd);
+    }
+    cmd.to_string()
+}
+
+/// 正文提交（Ctrl-Z）与取消（ESC）是唯一允许出现在指令末尾的控制字符
+const ALLOWED_TERMINATORS: [char; 2] = ['\x1A', '\x1B'];
+
+/// 校验一条即将下发给模组的指令，返回去掉首尾空白后的指令
+///
+/// 指令中间出现 CR/LF 或其他控制字符会被模组当成多条指令执行，一律拒绝，
+/// 防止一条 WebSocket 消息或拼接进指令的用户输入夹带额外的 AT 指令。
+pub fn sanitize_command(cmd: &str) -> Result<&str, String> {
+    let cmd = cmd.trim();
+    if cmd.is_empty() {
+        return Err("Empty command".to_string());
+    }
+    let body = cmd.strip_suffix(ALLOWED_TERMINATORS).unwrap_or(cmd);
+    if let Some(c) = body.chars().find(|c| c.is_control()) {
+        return Err(format!("Command contains control character {:?}", c));
+    }
+    Ok(cmd)
+}
+
+/// 解析 `command_delimiter` 配置，`newline` 表示按换行拆分
+///
+/// `;` 是 AT 指令自身的语法：`ATD<号码>;` 以它表示语音呼叫，`AT+CSQ;+COPS?` 用它串联多条指令，
+/// 按它拆分会改变指令含义，因此不能作为分隔符。
+pub fn parse_delimiter(raw: &str) -> Result<String, String> {
+    match raw {
+        "newline" | "\\n" => Ok("\n".to_string()),
+        d if d.contains(';') => Err(format!("'{}' conflicts with AT syntax (ATD<number>; and AT+A;+B), use newline or |", d)),
+        d => Ok(d.to_string()),
+    }
+}
+
+/// 按配置的分隔符把一条输入拆成多条指令，引号内的分隔符不拆，空段丢弃
+///
+/// 拆分是有意且受控的：拆出的每条仍需单独经过 `sanitize_command` 校验。
+pub fn split_commands(input: &str, delimiter: &str) -> Vec<String> {
+    if delimiter.is_empty() {
+        return vec![input.trim().to_string()];
+    }
+    let mut commands = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if !in_quotes && rest.starts_with(delimiter) {
+            commands.push(std::mem::take(&mut current));
+            rest = &rest[delimiter.len()..];
+            continue;
+        }
+        if c == '"' {
+            in_quotes = !in_quotes;
+        }
+        current.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    commands.push(current);
+    commands.into_iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect()
+}
+
+/// 前端可能把上一次的响应残留（换行、OK）一并带进来，且频段留空时会生成 `,"",""` 需要重新拼装
+fn rewrite_syscfgex(cmd: &str) -> String {
+    let mut cmd_str = cmd.replace(['\n', '\r'], "").replace("OK", "");
+    if cmd_str.contains(",\"\",\"\"") {
+        let parts: Vec<&str> = cmd_str.split(',').collect();
+        if parts.len() >= 5 {
+            let bands = parts[4].trim_matches('"');
+            cmd_str = format!("{},{},{},{},\"{}\",\"\",\"\"", parts[0], parts[1], parts[2], parts[3], bands);
+        }
+    }
+    cmd_str.push('\r');
+    cmd_str
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECHO_ON: ResponseTrim = ResponseTrim { strip_echo: true };
+    const ECHO_OFF: ResponseTrim = ResponseTrim { strip_echo: false };
+
+    #[test]
+    fn strips_leading_echo_when_enabled() {
+        assert_eq!(filter_response("AT+CSQ", "AT+CSQ\r\n+CSQ: 20,99\r\n\r\nOK", ECHO_ON), "+CSQ: 20,99\r\nOK");
+        assert_eq!(filter_response("AT+CSQ", "AT+CSQ\r\n+CSQ: 20,99\r\nOK", ECHO_OFF), "AT+CSQ\r\n+CSQ: 20,99\r\nOK");
+    }
+
+    #[test]
+    fn keeps_response_line_equal_to_command() {
+        // ATE0 下第一行就是响应本身，恰好与指令文本相同也必须保留
+        assert_eq!(filter_response("AT^VERSION", "AT^VERSION\r\nOK", ECHO_OFF), "AT^VERSION\r\nOK");
+        // 回显开启时只去掉第一行，后面与指令相同的行保留
+        assert_eq!(filter_response("ATI", "ATI\r\nATI\r\nOK", ECHO_ON), "ATI\r\nOK");
+    }
+
+    #[test]
+    fn splits_on_delimiter_outside_quotes() {
+        assert_eq!(split_commands("AT+CSQ| AT+COPS?|", "|"), vec!["AT+CSQ", "AT+COPS?"]);
+        assert_eq!(split_commands("AT+CMGS=\"a|b\"|ATI", "|"), vec!["AT+CMGS=\"a|b\"", "ATI"]);
+        assert_eq!(split_commands("AT+CSQ\r\nATI\r\n", "\n"), vec!["AT+CSQ", "ATI"]);
+        // 未配置分隔符时不拆，换行仍交给校验拒绝
+        assert_eq!(split_commands("AT+CSQ\r\nATI", ""), vec!["AT+CSQ\r\nATI"]);
+    }
+
+    #[test]
+    fn rejects_semicolon_delimiter() {
+        assert_eq!(parse_delimiter("newline").unwrap(), "\n");
+        assert_eq!(parse_delimiter("|").unwrap(), "|");
+        assert!(parse_delimiter(";").is_err());
+    }
+
+    #[test]
+    fn strip_ok_only_removes_final_ok() {
+        assert_eq!(
+            filter_response("AT^SYSCFGEX?", "^SYSCFGEX: \"03\",3FFFFFFF,1,2,7FFFFFFFFFFFFFFF\r\nOK", ECHO_OFF),
+            "^SYSCFGEX: \"03\",3FFFFFFF,1,2,7FFFFFFFFFFFFFFF"
+        );
+        assert_eq!(filter_response("AT^SYSCFGEX?", "OK\r\nX\r\nOK", ECHO_OFF), "OK\r\nX");
+    }
+}
@@ -1,9 +1,29 @@
 use crate::models::ConnectionType;
+use crate::connection::Keepalive;
 use std::process::Command;
 use log::{debug, info, error};
 use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use serde::{Serialize, Serializer};
+
+/// 序列化时隐藏密钥类字段，只暴露是否已配置
+fn redact_secret<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_str("******"),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 含密钥的配置不派生 Debug，统一走脱敏后的序列化输出，避免日志（会广播给所有 WebSocket 客户端）泄露密钥
+fn fmt_redacted<T: Serialize>(name: &str, value: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match serde_json::to_string(value) {
+        Ok(json) => write!(f, "{} {}", name, json),
+        Err(_) => write!(f, "{} {{ .. }}", name),
+    }
+}
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Config {
     pub at_config: AtConfig,
     pub notification_config: NotificationConfig,
@@ -13,48 +33,135 @@ pub struct Config {
     pub sys_log_config: SysLogConfig,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SysLogConfig {
     pub enable: bool,
     pub persist: bool,
     pub level: String,
+    /// WebSocket 指令审计日志路径，留空不记录
+    pub audit_log: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AtConfig {
     pub connection_type: ConnectionType,
     pub network: NetworkConfig,
     pub serial: SerialConfig,
+    /// 查询类指令遇到 ERROR/超时时的总尝试次数（含首次）
+    pub retry_attempts: u32,
+    /// 重试间隔（毫秒）
+    pub retry_delay_ms: u64,
+    /// 支持读取模组内部日志的型号前缀（与 AT+CGMM 比对，不区分大小写）
+    pub modem_log_models: Vec<String>,
+    /// 读取模组日志的厂商指令，随固件不同，留空即视为不支持
+    pub modem_log_read_cmd: String,
+    /// 清空模组日志的厂商指令
+    pub modem_log_clear_cmd: String,
+    /// 查询指示灯状态的厂商指令，留空即视为不支持
+    pub led_query_cmd: String,
+    /// 设置指示灯的厂商指令模板，`{mode}` 替换为 SET_LED 的参数
+    pub led_set_cmd: String,
+    /// 查询服务小区详情的指令：auto 按型号选择，monsc 为 AT^MONSC，qeng 为 AT+QENG="servingcell"
+    pub signal_query_cmd: String,
+    /// 宽松输入：自动补全 `AT` 前缀并去掉多余的换行，严格模式下关闭
+    pub lenient_commands: bool,
+    /// 响应回显清洗：auto 网络连接开启、串口关闭（串口通常已 ATE0），1/0 强制开关
+    pub strip_echo: String,
+    /// 覆盖或补充内置运营商名称表：PLMN → 名称
+    pub operator_names: HashMap<String, String>,
+    /// 射频操作（定时锁频）进行中提交的终端指令最多推迟多少秒，0 表示不推迟、CFUN 直接返回 busy
+    pub radio_defer_timeout: u64,
+    /// 等待 `>` 提示符（AT+CMGS= 等）的超时秒数，短于整体指令超时
+    pub prompt_timeout: u64,
+    /// AT 连接建立多少秒后在空闲时主动断开重连并重新下发 URC 配置，0 表示不重连
+    pub session_max_age: u64,
+    /// 允许 INJECT_URC 注入模拟的主动上报，仅供前端调试，默认关闭
+    pub allow_inject_urc: bool,
+    /// 多指令分隔符：一条 WebSocket 消息按它拆成多条指令依次执行，留空关闭
+    pub command_delimiter: String,
+    /// 连续多少条指令超时视为模组卡死并断开重连，0 表示不检测
+    pub wedge_timeout_count: u32,
+    /// 判定卡死后断开前尝试下发的复位指令（如 AT^RESET），留空不发送
+    pub wedge_reset_cmd: String,
+    /// 判定卡死后执行的恢复脚本（如通过 GPIO 给模组断电），留空不执行
+    pub wedge_recovery_script: String,
 }
 
-#[derive(Debug, Clone)]
+impl AtConfig {
+    /// 新 WebSocket 连接默认是否清洗回显，连接内可用 SET_ECHO_STRIP 调整
+    ///
+    /// `connection_type` 为实际使用的连接方式（SET_CONNECTION 切换后与配置不同）。
+    pub fn strip_echo_default(&self, connection_type: ConnectionType) -> bool {
+        match self.strip_echo.as_str() {
+            "1" => true,
+            "0" => false,
+            _ => connection_type == ConnectionType::Network,
+        }
+    }
+
+    /// 是否允许注入模拟上报与运行时切到模拟连接：需显式开启 allow_inject_urc，或在 UCI 中就配置为模拟连接
+    pub fn allows_simulation(&self) -> bool {
+        self.allow_inject_urc || self.connection_type == ConnectionType::Simulation
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct NetworkConfig {
     pub host: String,
     pub port: u16,
     pub timeout: u64,
+    /// TCP keepalive 空闲时间（秒），0 表示不开启
+    pub keepalive_idle: u64,
+    /// keepalive 探测间隔（秒）
+    pub keepalive_interval: u64,
+    /// keepalive 探测次数
+    pub keepalive_probes: u32,
+}
+
+impl NetworkConfig {
+    pub fn keepalive(&self) -> Option<Keepalive> {
+        if self.keepalive_idle == 0 {
+            return None;
+        }
+        Some(Keepalive {
+            idle: Duration::from_secs(self.keepalive_idle),
+            interval: Duration::from_secs(self.keepalive_interval.max(1)),
+            probes: self.keepalive_probes.max(1),
+        })
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SerialConfig {
     pub port: String,
     pub baudrate: u32,
     pub timeout: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone, Serialize)]
 pub struct NotificationConfig {
     pub enabled_push_services: Vec<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub wechat_webhook: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub pushplus_token: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub serverchan_key: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub pushdeer_key: Option<String>,
     pub pushdeer_url: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub feishu_webhook: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub dingtalk_webhook: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub dingtalk_secret: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub bark_url: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub tg_bot_token: Option<String>,
     pub tg_chat_id: Option<String>,
+    #[serde(serialize_with = "redact_secret")]
     pub generic_webhook_url: Option<String>,
     pub custom_script_path: Option<String>,
     // pub log_file: Option<String>, // Removed, using standard paths
@@ -62,28 +169,65 @@ pub struct NotificationConfig {
     pub notify_log_persist: bool,
     pub notify_sms: bool,
     pub notify_call: bool,
+    /// SIM 卡拔出、插入或 ICCID 变化时通知
+    pub notify_sim: bool,
+    /// IMS（VoLTE）注册状态变化时通知
+    pub notify_ims: bool,
+    /// 拨号因 APN/PDP 配置错误停止重试时通知
+    pub notify_dial: bool,
+    /// 与模组的 AT 连接意外断开及恢复时通知，恢复时附带中断时长
+    pub notify_connection: bool,
+    /// 各通知类型的冷却秒数（来自 `<类型>_cooldown`，键为 sms/call/signal 等），上次发出后冷却期内同类通知不再发送
+    pub notify_cooldowns: HashMap<String, u64>,
+    /// 推送请求绑定的本地源地址，留空由系统选择；仅决定源 IP，出口仍由路由表（含策略路由）决定
+    pub notify_bind_address: String,
+    /// 来电记录是否写入文件，重启后保留
+    pub call_history_persist: bool,
+    /// 已接收短信记录是否写入文件，重启后保留
+    pub sms_history_persist: bool,
     /// 短信存储使用率超过此百分比时通知（0=禁用，1-100=阈值）
     pub notify_memory_full_threshold: u8,
+    /// 定时查询 AT+CPMS? 的间隔（秒，0=关闭），弥补部分模组存储满时不上报 URC
+    pub sms_storage_check_interval: u64,
     /// 信号强度（RSRP dBm 绝对值）低于此值时通知（0=禁用）
     pub notify_signal_threshold: i32,
+    /// 短信成功转发到第三方推送后是否删除（默认保留，保留时存储将满会记录警告）
     pub sms_delete_after_forward: bool,
     pub delete_mms_notification: bool,
+    /// 短信时间的时区：local 换算为本地时区，original 保留短信中心时间戳自带的时区
+    pub sms_timezone: String,
+    /// 短信通知正文附带的接收时间：none 不附带，local / original / utc 为对应时区
+    pub sms_notify_time: String,
+    /// 长短信通知方式：wait 收齐后通知，immediate 每段通知，both 两者都通知
+    pub multipart_mode: String,
+    /// 未收齐的长短信分段最长等待秒数，超时后按不完整短信记录
+    pub partial_sms_max_age: u64,
+    /// 同时等待拼装的长短信最多条数，超出时丢弃最早的
+    pub partial_sms_max_entries: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Clone, Serialize)]
 pub struct WebSocketConfig {
     pub ipv4: IpConfig,
     pub ipv6: IpConfig,
+    #[serde(serialize_with = "redact_secret")]
     pub auth_key: Option<String>,
+    /// 认证方式：plain 首条消息直接发送密钥，hmac 由服务端下发随机数、客户端回复 HMAC-SHA256
+    pub auth_mode: String,
+    /// `GET /metrics` 的 Bearer 令牌，留空则无需认证
+    #[serde(serialize_with = "redact_secret")]
+    pub metrics_token: Option<String>,
+    /// 广播与日志推送通道的容量，客户端落后超过该条数时丢弃最旧的消息
+    pub broadcast_capacity: usize,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct IpConfig {
     pub host: String,
     pub port: u16,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct ScheduleConfig {
     pub enabled: bool,
     pub check_interval: u64,
@@ -91,6 +235,15 @@ pub struct ScheduleConfig {
     pub unlock_lte: bool,
     pub unlock_nr: bool,
     pub toggle_airplane: bool,
+    /// 解锁/恢复时依次下发的指令，默认华为 ^LTEFREQLOCK/^NRFREQLOCK，
+    /// 其他芯片可改为自己的解锁指令（如 Quectel 的 AT+QNWLOCK）
+    pub unlock_cmds: Vec<String>,
+    /// 启动时先执行一次解锁（不受 enabled 影响），避免崩溃前残留的锁频一直生效
+    pub unlock_on_startup: bool,
+    /// 切换模式后至少停留的秒数，期间不再切换，防止在边界时刻来回切换（0=不限制）
+    pub min_dwell: u64,
+    /// 有服务的判定：registration 为已注册网络，data 还要求已分配数据 IP
+    pub service_check: String,
     
     // Night Mode
     pub night_enabled: bool,
@@ -117,20 +270,50 @@ pub struct ScheduleConfig {
     pub day_nr_arfcns: String,
     pub day_nr_scs_types: String,
     pub day_nr_pcis: String,
+
+    /// 手动切换的锁频配置（LIST_PROFILES / APPLY_PROFILE），与定时时段无关，共用 toggle_airplane
+    pub lock_profiles: Vec<crate::freq_lock::LockProfile>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct AdvancedNetworkConfig {
     pub pdp_type: String,
     pub ifname: String,
     pub ra_master: bool,
     pub extend_prefix: bool,
+    /// 不使用运营商下发的 DNS（IPv4/IPv6 均生效）
+    pub do_not_add_dns: bool,
+    /// 自定义 DNS，按地址族分别写入 wan_modem / wan_modem6
     pub dns_list: Vec<String>,
     pub init_at_cmds: Vec<String>,
     /// 短信存储位置，对应 AT+CPMS 的 mem1/mem2/mem3
     /// 模组掉电不保存，由后端在每次启动时重新下发
     /// 可选值："SM"（SIM卡）、"ME"（Flash）
     pub sms_storage: String,
+    /// `ifup wan_modem` 后网卡未 up 时的最大尝试次数
+    pub ifup_attempts: u32,
+    /// 首次重试间隔（秒），之后每次翻倍
+    pub ifup_retry_delay: u32,
+    /// PDCP 吞吐量指数滑动平均的平滑系数（0-1，越大越贴近瞬时值）
+    pub throughput_smoothing: f64,
+    /// 启动后首次拨号前的等待（秒），给模组留出初始化时间
+    pub dial_startup_delay: u64,
+    /// 首次拨号前等待 SIM 就绪并注册网络的最长时间（秒，0=不等待）
+    pub dial_ready_timeout: u64,
+    /// 自动探测网卡时额外认可的 USB 厂商 ID（4 位十六进制），与内置列表合并
+    pub modem_vids: Vec<String>,
+}
+
+impl fmt::Debug for NotificationConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("NotificationConfig", self, f)
+    }
+}
+
+impl fmt::Debug for WebSocketConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_redacted("WebSocketConfig", self, f)
+    }
 }
 
 impl Default for Config {
@@ -142,12 +325,34 @@ impl Default for Config {
                     host: "192.168.8.1".to_string(),
                     port: 20249,
                     timeout: 10,
+                    keepalive_idle: 30,
+                    keepalive_interval: 10,
+                    keepalive_probes: 3,
                 },
                 serial: SerialConfig {
                     port: "/dev/ttyUSB0".to_string(),
                     baudrate: 115200,
                     timeout: 10,
                 },
+                retry_attempts: 3,
+                retry_delay_ms: 500,
+                modem_log_models: vec!["MT5700".to_string()],
+                modem_log_read_cmd: String::new(),
+                modem_log_clear_cmd: String::new(),
+                led_query_cmd: String::new(),
+                led_set_cmd: String::new(),
+                signal_query_cmd: "auto".to_string(),
+                lenient_commands: true,
+                strip_echo: "auto".to_string(),
+                operator_names: HashMap::new(),
+                radio_defer_timeout: 60,
+                prompt_timeout: 5,
+                session_max_age: 0,
+                allow_inject_urc: false,
+                command_delimiter: String::new(),
+                wedge_timeout_count: 5,
+                wedge_reset_cmd: String::new(),
+                wedge_recovery_script: String::new(),
             },
             notification_config: NotificationConfig {
                 enabled_push_services: Vec::new(),
@@ -169,10 +374,24 @@ impl Default for Config {
                 notify_log_persist: false,
                 notify_sms: true,
                 notify_call: true,
+                notify_sim: true,
+                notify_ims: false,
+                notify_dial: true,
+                notify_connection: false,
+                notify_cooldowns: HashMap::new(),
+                notify_bind_address: String::new(),
+                call_history_persist: false,
+                sms_history_persist: false,
                 notify_memory_full_threshold: 90,
+                sms_storage_check_interval: 300,
                 notify_signal_threshold: 0,
                 sms_delete_after_forward: false,
                 delete_mms_notification: false,
+                sms_timezone: "local".to_string(),
+                sms_notify_time: "none".to_string(),
+                multipart_mode: "wait".to_string(),
+                partial_sms_max_age: 3600,
+                partial_sms_max_entries: 100,
             },
             websocket_config: WebSocketConfig {
                 ipv4: IpConfig {
@@ -184,6 +403,9 @@ impl Default for Config {
                     port: 8765,
                 },
                 auth_key: None,
+                auth_mode: "plain".to_string(),
+                metrics_token: None,
+                broadcast_capacity: 100,
             },
             schedule_config: ScheduleConfig {
                 enabled: false,
@@ -192,6 +414,10 @@ impl Default for Config {
                 unlock_lte: true,
                 unlock_nr: true,
                 toggle_airplane: true,
+                unlock_cmds: vec!["AT^LTEFREQLOCK=0".to_string(), "AT^NRFREQLOCK=0".to_string()],
+                unlock_on_startup: false,
+                min_dwell: 300,
+                service_check: "registration".to_string(),
                 night_enabled: true,
                 night_start: "22:00".to_string(),
                 night_end: "06:00".to_string(),
@@ -214,20 +440,29 @@ impl Default for Config {
                 day_nr_arfcns: "".to_string(),
                 day_nr_scs_types: "".to_string(),
                 day_nr_pcis: "".to_string(),
+                lock_profiles: Vec::new(),
             },
             advanced_network_config: AdvancedNetworkConfig {
                 pdp_type: "ipv4v6".to_string(),
                 ifname: "auto".to_string(),
                 ra_master: true,
                 extend_prefix: true,
+                do_not_add_dns: false,
                 dns_list: vec![],
                 init_at_cmds: vec![],
                 sms_storage: "SM".to_string(),
+                ifup_attempts: 4,
+                ifup_retry_delay: 1,
+                throughput_smoothing: 0.3,
+                dial_startup_delay: 10,
+                dial_ready_timeout: 120,
+                modem_vids: vec![],
             },
             sys_log_config: SysLogConfig {
                 enable: true,
                 persist: false,
                 level: "info".to_string(),
+                audit_log: String::new(),
             },
         }
     }
@@ -295,15 +530,18 @@ impl Config {
 
         // AT Config
         let conn_type_str = get_str("connection_type", "NETWORK");
-        if conn_type_str == "SERIAL" {
-            config.at_config.connection_type = ConnectionType::Serial;
-        } else {
-            config.at_config.connection_type = ConnectionType::Network;
-        }
+        config.at_config.connection_type = match conn_type_str.as_str() {
+            "SERIAL" => ConnectionType::Serial,
+            "SIMULATION" => ConnectionType::Simulation,
+            _ => ConnectionType::Network,
+        };
 
         config.at_config.network.host = get_str("network_host", "192.168.8.1");
         config.at_config.network.port = get_u16("network_port", 20249);
         config.at_config.network.timeout = get_int("network_timeout", 10);
+        config.at_config.network.keepalive_idle = get_int("network_keepalive_idle", 30);
+        config.at_config.network.keepalive_interval = get_int("network_keepalive_interval", 10);
+        config.at_config.network.keepalive_probes = get_u32("network_keepalive_probes", 3);
 
         let mut serial_port = get_str("serial_port", "/dev/ttyUSB0");
         if serial_port == "custom" {
@@ -312,6 +550,24 @@ impl Config {
         config.at_config.serial.port = serial_port;
         config.at_config.serial.baudrate = get_u32("serial_baudrate", 115200);
         config.at_config.serial.timeout = get_int("serial_timeout", 10);
+        config.at_config.retry_attempts = get_u32("at_retry_attempts", 3).max(1);
+        config.at_config.retry_delay_ms = get_int("at_retry_delay_ms", 500);
+        config.at_config.lenient_commands = get_bool("lenient_commands", true);
+        config.at_config.strip_echo = get_str("strip_echo", "auto");
+        config.at_config.radio_defer_timeout = get_int("radio_defer_timeout", 60);
+        config.at_config.prompt_timeout = get_int("prompt_timeout", 5);
+        config.at_config.session_max_age = get_int("session_max_age", 0);
+        config.at_config.allow_inject_urc = get_bool("allow_inject_urc", false);
+        config.at_config.command_delimiter = match crate::command_rules::parse_delimiter(&get_str("command_delimiter", "")) {
+            Ok(delimiter) => delimiter,
+            Err(e) => {
+                error!("Invalid command_delimiter {}, multi-command input disabled", e);
+                String::new()
+            }
+        };
+        config.at_config.wedge_timeout_count = get_u32("wedge_timeout_count", 5);
+        config.at_config.wedge_reset_cmd = get_str("wedge_reset_cmd", "");
+        config.at_config.wedge_recovery_script = get_str("wedge_recovery_script", "");
 
         // Notification Config
         let mut enabled_services = Vec::new();
@@ -374,18 +630,45 @@ impl Config {
 
         config.notification_config.notify_sms = get_bool("notify_sms", true);
         config.notification_config.notify_call = get_bool("notify_call", true);
+        config.notification_config.notify_sim = get_bool("notify_sim", true);
+        config.notification_config.notify_ims = get_bool("notify_ims", false);
+        config.notification_config.notify_dial = get_bool("notify_dial", true);
+        config.notification_config.notify_connection = get_bool("notify_connection", false);
+        for kind in crate::notifications::NotificationType::ALL {
+            let secs = get_int(&format!("{}_cooldown", kind.key()), 0);
+            if secs > 0 {
+                config.notification_config.notify_cooldowns.insert(kind.key().to_string(), secs);
+            }
+        }
+        config.notification_config.notify_bind_address = get_str("notify_bind_address", "");
+        config.notification_config.call_history_persist = get_bool("call_history_persist", false);
+        config.notification_config.sms_history_persist = get_bool("sms_history_persist", false);
         config.notification_config.notify_memory_full_threshold = get_u8("notify_memory_full_threshold", 90);
+        config.notification_config.sms_storage_check_interval = get_int("sms_storage_check_interval", 300);
         config.notification_config.notify_signal_threshold = uci_data.get("notify_signal_threshold").and_then(|s| s.parse().ok()).unwrap_or(0);
         config.notification_config.sms_delete_after_forward = get_bool("sms_delete_after_forward", false);
         config.notification_config.delete_mms_notification = get_bool("delete_mms_notification", false);
+        config.notification_config.sms_timezone = get_str("sms_timezone", "local");
+        config.notification_config.sms_notify_time = get_str("sms_notify_time", "none");
+        config.notification_config.multipart_mode = get_str("multipart_mode", "wait");
+        config.notification_config.partial_sms_max_age = get_int("partial_sms_max_age", 3600).max(60);
+        config.notification_config.partial_sms_max_entries = (get_int("partial_sms_max_entries", 100) as usize).max(1);
 
         // WebSocket Config
         let ws_port = get_u16("websocket_port", 8765);
         config.websocket_config.ipv4.port = ws_port;
         config.websocket_config.ipv6.port = ws_port;
+        // 监听地址：留空表示不监听该协议族；IPv6 为 :: 时即双栈监听，IPv4 地址被忽略
+        // 链路本地地址需带作用域，如 fe80::1%br-lan
+        config.websocket_config.ipv4.host = get_str("websocket_ipv4_host", "0.0.0.0");
+        config.websocket_config.ipv6.host = get_str("websocket_ipv6_host", "::");
         
         let auth_key = get_str("websocket_auth_key", "");
         config.websocket_config.auth_key = if auth_key.is_empty() { None } else { Some(auth_key) };
+        config.websocket_config.auth_mode = get_str("websocket_auth_mode", "plain");
+        let metrics_token = get_str("metrics_token", "");
+        config.websocket_config.metrics_token = if metrics_token.is_empty() { None } else { Some(metrics_token) };
+        config.websocket_config.broadcast_capacity = get_u32("broadcast_capacity", 100).clamp(16, 10000) as usize;
 
         // Schedule Config
         config.schedule_config.enabled = get_bool("schedule_enabled", false);
@@ -394,6 +677,9 @@ impl Config {
         config.schedule_config.unlock_lte = get_bool("schedule_unlock_lte", true);
         config.schedule_config.unlock_nr = get_bool("schedule_unlock_nr", true);
         config.schedule_config.toggle_airplane = get_bool("schedule_toggle_airplane", true);
+        config.schedule_config.unlock_on_startup = get_bool("schedule_unlock_on_startup", false);
+        config.schedule_config.min_dwell = get_int("schedule_min_dwell", 300);
+        config.schedule_config.service_check = get_str("schedule_service_check", "registration");
 
         config.schedule_config.night_enabled = get_bool("schedule_night_enabled", true);
         config.schedule_config.night_start = get_str("schedule_night_start", "22:00");
@@ -424,6 +710,7 @@ impl Config {
         config.advanced_network_config.ifname = get_str("ifname", "auto");
         config.advanced_network_config.ra_master = get_bool("ra_master", true);
         config.advanced_network_config.extend_prefix = get_bool("extend_prefix", true);
+        config.advanced_network_config.do_not_add_dns = get_bool("do_not_add_dns", false);
         // 短信存储位置：模组掉电不保存，由后端每次启动时通过 AT+CPMS 重新下发
         // UCI key: at-webserver.config.sms_storage，可选值 SM / ME
         let raw_sms = get_str("sms_storage", "SM").to_uppercase();
@@ -449,6 +736,34 @@ impl Config {
         }
 
         config.advanced_network_config.init_at_cmds = get_list("init_at_cmds");
+        config.advanced_network_config.modem_vids = get_list("modem_vids");
+
+        let log_models = get_list("modem_log_models");
+        if !log_models.is_empty() {
+            config.at_config.modem_log_models = log_models;
+        }
+        config.at_config.modem_log_read_cmd = get_str("modem_log_read_cmd", "");
+        config.at_config.modem_log_clear_cmd = get_str("modem_log_clear_cmd", "");
+        config.at_config.led_query_cmd = get_str("led_query_cmd", "");
+        config.at_config.led_set_cmd = get_str("led_set_cmd", "");
+        config.at_config.signal_query_cmd = get_str("signal_query_cmd", "auto");
+        config.at_config.operator_names = crate::operator::parse_overrides(&get_list("operator_name"));
+
+        // 未配置时保留默认的华为解锁指令
+        let unlock_cmds = get_list("schedule_unlock_cmds");
+        if !unlock_cmds.is_empty() {
+            config.schedule_config.unlock_cmds = unlock_cmds;
+        }
+        config.schedule_config.lock_profiles = crate::freq_lock::parse_profiles(&get_list("lock_profile"));
+        config.advanced_network_config.ifup_attempts = get_u32("ifup_attempts", 4).max(1);
+        config.advanced_network_config.ifup_retry_delay = get_u32("ifup_retry_delay", 1);
+        config.advanced_network_config.dial_startup_delay = get_int("dial_startup_delay", 10);
+        config.advanced_network_config.dial_ready_timeout = get_int("dial_ready_timeout", 120);
+        config.advanced_network_config.throughput_smoothing = uci_data
+            .get("throughput_smoothing")
+            .and_then(|s| s.parse::<f64>().ok())
+            .filter(|a| *a > 0.0 && *a <= 1.0)
+            .unwrap_or(0.3);
 
         // SysLog Config
         config.sys_log_config.enable = get_bool("sys_log_enable", true);
@@ -458,6 +773,7 @@ impl Config {
             "error" | "warn" | "info" | "debug" => raw_level,
             _ => "info".to_string(),
         };
+        config.sys_log_config.audit_log = get_str("audit_log", "");
         // config.sys_log_config.path_temp = get_str("sys_log_path_temp", "/tmp/at-webserver.log");
         // config.sys_log_config.path_persist = get_str("sys_log_path_persist", "/etc/at-webserver.log");
 
@@ -466,6 +782,7 @@ impl Config {
             match val.as_str() {
                 "SERIAL" => config.at_config.connection_type = ConnectionType::Serial,
                 "NETWORK" => config.at_config.connection_type = ConnectionType::Network,
+                "SIMULATION" => config.at_config.connection_type = ConnectionType::Simulation,
                 _ => {}
             }
         }
@@ -1,11 +1,55 @@
 use async_trait::async_trait;
-use anyhow::{Result, Context};
-use log::info;
+use log::{info, warn};
+use std::fmt;
+use std::collections::VecDeque;
+use std::io;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::time::{timeout, Duration};
 use tokio_serial::SerialPortBuilderExt;
 
+/// 连接层错误，Actor 按变体决定是否重连，不再匹配错误文本
+#[derive(Debug)]
+pub enum ConnectionError {
+    /// 建立连接超时
+    Timeout,
+    /// 对端关闭（读到 EOF）
+    Closed,
+    /// 尚未建立连接
+    NotConnected,
+    /// 底层 IO 错误，附带发生时的操作描述
+    Io(&'static str, io::Error),
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::Timeout => write!(f, "Connection timed out"),
+            ConnectionError::Closed => write!(f, "Connection closed"),
+            ConnectionError::NotConnected => write!(f, "Not connected"),
+            ConnectionError::Io(context, e) => write!(f, "{}: {}", context, e),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConnectionError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl ConnectionError {
+    /// 连接已失效，需要丢弃并重连
+    pub fn is_disconnect(&self) -> bool {
+        matches!(self, ConnectionError::Closed | ConnectionError::NotConnected)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, ConnectionError>;
+
 #[async_trait]
 pub trait ATConnection: Send {
     async fn connect(&mut self) -> Result<()>;
@@ -15,19 +59,32 @@ pub trait ATConnection: Send {
     fn is_connected(&self) -> bool;
 }
 
+/// TCP keepalive 参数，用于尽快发现中间链路已断开的对端
+#[derive(Debug, Clone, Copy)]
+pub struct Keepalive {
+    /// 连接空闲多久后开始探测
+    pub idle: Duration,
+    /// 探测间隔
+    pub interval: Duration,
+    /// 连续多少次探测无响应后断开
+    pub probes: u32,
+}
+
 pub struct NetworkATConnection {
     host: String,
     port: u16,
     timeout_secs: u64,
+    keepalive: Option<Keepalive>,
     stream: Option<TcpStream>,
 }
 
 impl NetworkATConnection {
-    pub fn new(host: String, port: u16, timeout_secs: u64) -> Self {
+    pub fn new(host: String, port: u16, timeout_secs: u64, keepalive: Option<Keepalive>) -> Self {
         Self {
             host,
             port,
             timeout_secs,
+            keepalive,
             stream: None,
         }
     }
@@ -40,13 +97,22 @@ impl ATConnection for NetworkATConnection {
         info!("Connecting to network AT server at {}", addr);
         match timeout(Duration::from_secs(self.timeout_secs), TcpStream::connect(&addr)).await {
             Ok(result) => {
-                self.stream = Some(result.context("Failed to connect to network AT server")?);
+                let stream = result.map_err(|e| ConnectionError::Io("Failed to connect to network AT server", e))?;
+                if let Some(keepalive) = self.keepalive {
+                    let params = socket2::TcpKeepalive::new()
+                        .with_time(keepalive.idle)
+                        .with_interval(keepalive.interval)
+                        .with_retries(keepalive.probes);
+                    // 设置失败不影响使用，只是断线要等读超时才能发现
+                    if let Err(e) = socket2::SockRef::from(&stream).set_tcp_keepalive(&params) {
+                        warn!("Failed to enable TCP keepalive: {}", e);
+                    }
+                }
+                self.stream = Some(stream);
                 info!("Connected to network AT server");
                 Ok(())
             }
-            Err(_) => {
-                anyhow::bail!("Connection timed out");
-            }
+            Err(_) => Err(ConnectionError::Timeout),
         }
     }
 
@@ -59,20 +125,20 @@ impl ATConnection for NetworkATConnection {
 
     async fn send(&mut self, data: &[u8]) -> Result<()> {
         if let Some(stream) = &mut self.stream {
-            stream.write_all(data).await.context("Failed to write to stream")?;
-            stream.flush().await.context("Failed to flush stream")?;
+            stream.write_all(data).await.map_err(|e| ConnectionError::Io("Failed to write to stream", e))?;
+            stream.flush().await.map_err(|e| ConnectionError::Io("Failed to flush stream", e))?;
             Ok(())
         } else {
-            anyhow::bail!("Not connected");
+            Err(ConnectionError::NotConnected)
         }
     }
 
     async fn receive(&mut self, buffer: &mut [u8]) -> Result<usize> {
         if let Some(stream) = &mut self.stream {
             // We just await data. Cancellation via timeout is handled by caller (client.rs: select!)
-            stream.read(buffer).await.context("Failed to read from stream")
+            stream.read(buffer).await.map_err(|e| ConnectionError::Io("Failed to read from stream", e))
         } else {
-            anyhow::bail!("Not connected");
+            Err(ConnectionError::NotConnected)
         }
     }
 
@@ -103,7 +169,7 @@ impl ATConnection for SerialATConnection {
         info!("Opening serial port {} at {}", self.port, self.baudrate);
         let port = tokio_serial::new(&self.port, self.baudrate)
             .open_native_async()
-            .context("Failed to open serial port")?;
+            .map_err(|e| ConnectionError::Io("Failed to open serial port", e.into()))?;
         self.stream = Some(port);
         Ok(())
     }
@@ -115,11 +181,11 @@ impl ATConnection for SerialATConnection {
 
     async fn send(&mut self, data: &[u8]) -> Result<()> {
         if let Some(stream) = &mut self.stream {
-            stream.write_all(data).await.context("Failed to write to serial")?;
-            stream.flush().await.context("Failed to flush serial")?;
+            stream.write_all(data).await.map_err(|e| ConnectionError::Io("Failed to write to serial", e))?;
+            stream.flush().await.map_err(|e| ConnectionError::Io("Failed to flush serial", e))?;
             Ok(())
         } else {
-            anyhow::bail!("Not connected");
+            Err(ConnectionError::NotConnected)
         }
     }
 
@@ -127,9 +193,9 @@ impl ATConnection for SerialATConnection {
         if let Some(stream) = &mut self.stream {
              // Serial reading doesn't inherently timeout in the same way, but we can wrap it.
              // Usually we just read.
-             stream.read(buffer).await.context("Failed to read from serial")
+             stream.read(buffer).await.map_err(|e| ConnectionError::Io("Failed to read from serial", e))
         } else {
-            anyhow::bail!("Not connected");
+            Err(ConnectionError::NotConnected)
         }
     }
 
@@ -137,3 +203,206 @@ impl ATConnection for SerialATConnection {
         self.stream.is_some()
     }
 }
+
+/// 从待读队列取出一段数据，放不下的部分留到下次；队列为空时一直挂起，模拟模组沉默
+async fn read_queued(readable: &mut VecDeque<Vec<u8>>, buffer: &mut [u8]) -> Result<usize> {
+    match readable.pop_front() {
+        Some(chunk) => {
+            let n = chunk.len().min(buffer.len());
+            buffer[..n].copy_from_slice(&chunk[..n]);
+            if n < chunk.len() {
+                readable.push_front(chunk[n..].to_vec());
+            }
+            Ok(n)
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// 模拟连接（`connection_type 'SIMULATION'`）：不接模组，按指令返回固定回复
+///
+/// 用于演示、前端开发与 CI，整套服务可以在没有模组的机器上跑起来。常用查询（CSQ、COPS?、CGPADDR 等）
+/// 返回一个已注册、已拨通的 LTE 状态，设置类指令一律 OK，其余查询返回 ERROR；URC 通过 `INJECT_URC` 注入。
+#[derive(Default)]
+pub struct SimulatedATConnection {
+    connected: bool,
+    /// 尚未遇到 `\r` 的指令片段
+    pending_cmd: Vec<u8>,
+    readable: VecDeque<Vec<u8>>,
+}
+
+impl SimulatedATConnection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// 模拟连接对一条指令的回复（不含首尾换行）
+pub fn simulated_reply(cmd: &str) -> String {
+    let cmd = cmd.trim();
+    // 短信正文以 Ctrl-Z 提交
+    if cmd.ends_with('\x1A') {
+        return "+CMGS: 1\r\n\r\nOK".to_string();
+    }
+    if cmd.ends_with('\x1B') {
+        return "OK".to_string();
+    }
+    let upper = cmd.to_ascii_uppercase();
+    let body = match upper.as_str() {
+        "AT" | "ATE0" | "ATE1" => "",
+        "AT+CSQ" => "+CSQ: 24,99",
+        "AT+COPS?" => "+COPS: 0,2,\"46000\",7",
+        "AT+CGPADDR" | "AT+CGPADDR=1" => "+CGPADDR: 1,\"10.64.23.5\"",
+        "AT+CPIN?" => "+CPIN: READY",
+        "AT+CREG?" => "+CREG: 0,1",
+        "AT+CEREG?" => "+CEREG: 0,1",
+        "AT+C5GREG?" => "+C5GREG: 0,0",
+        "AT^HCSQ?" => "^HCSQ: \"LTE\",50,120,30",
+        "AT+CGMI" => "Simulated",
+        "AT+CGMM" => "MT5700M-CN",
+        "AT+CGMR" => "SIMULATION",
+        "AT+CGSN" => "860000000000000",
+        "AT+CIMI" => "460001234567890",
+        "AT+CPMS?" => "+CPMS: \"ME\",0,100,\"ME\",0,100,\"ME\",0,100",
+        "AT+CMGF?" => "+CMGF: 0",
+        "AT+CSCA?" => "+CSCA: \"+8613800100500\",145",
+        _ if upper.starts_with("AT+CMGS=") => return "> ".to_string(),
+        _ if upper.starts_with("AT+CMGL") => "",
+        _ if upper.contains('=') && !upper.ends_with("=?") => "",
+        _ => return "ERROR".to_string(),
+    };
+    if body.is_empty() {
+        "OK".to_string()
+    } else {
+        format!("{}\r\n\r\nOK", body)
+    }
+}
+
+#[async_trait]
+impl ATConnection for SimulatedATConnection {
+    async fn connect(&mut self) -> Result<()> {
+        info!("Using simulated AT connection, no modem attached");
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn close(&mut self) -> Result<()> {
+        self.connected = false;
+        self.pending_cmd.clear();
+        self.readable.clear();
+        Ok(())
+    }
+
+    async fn send(&mut self, data: &[u8]) -> Result<()> {
+        if !self.connected {
+            return Err(ConnectionError::NotConnected);
+        }
+        for &b in data {
+            if b != b'\r' {
+                self.pending_cmd.push(b);
+                continue;
+            }
+            let cmd = String::from_utf8_lossy(&std::mem::take(&mut self.pending_cmd)).into_owned();
+            if !cmd.trim().is_empty() {
+                self.readable.push_back(format!("\r\n{}\r\n", simulated_reply(&cmd)).into_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    async fn receive(&mut self, buffer: &mut [u8]) -> Result<usize> {
+        if !self.connected {
+            return Err(ConnectionError::NotConnected);
+        }
+        read_queued(&mut self.readable, buffer).await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+}
+
+/// 测试用的脚本化连接：每发出一条以 `\r` 结尾的指令，放出下一段预设回复；
+/// 发送的字节全部记录，供断言使用。没有可读数据时 `receive` 一直挂起，模拟模组沉默。
+#[cfg(test)]
+pub mod mock {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    pub struct MockATConnection {
+        /// 连接建立后立即可读的数据（指令发出前就滞留在串口里的内容）
+        readable: VecDeque<Vec<u8>>,
+        /// 按指令顺序排列的回复，每段回复可拆成多次 `receive`
+        replies: VecDeque<Vec<Vec<u8>>>,
+        sent: Arc<Mutex<Vec<u8>>>,
+    }
+
+    impl MockATConnection {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// 追加指令发出前就已到达的数据
+        pub fn pending(mut self, data: &str) -> Self {
+            self.readable.push_back(data.as_bytes().to_vec());
+            self
+        }
+
+        /// 追加一条指令的回复，`chunks` 按顺序逐次返回，空串表示对端关闭
+        pub fn reply(mut self, chunks: &[&str]) -> Self {
+            self.replies.push_back(chunks.iter().map(|c| c.as_bytes().to_vec()).collect());
+            self
+        }
+
+        /// 已发送字节的共享句柄，连接被移走后仍可读取
+        pub fn sent(&self) -> Arc<Mutex<Vec<u8>>> {
+            self.sent.clone()
+        }
+    }
+
+    #[async_trait]
+    impl ATConnection for MockATConnection {
+        async fn connect(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn close(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        async fn send(&mut self, data: &[u8]) -> Result<()> {
+            self.sent.lock().unwrap().extend_from_slice(data);
+            if data.ends_with(b"\r") {
+                if let Some(chunks) = self.replies.pop_front() {
+                    self.readable.extend(chunks);
+                }
+            }
+            Ok(())
+        }
+
+        async fn receive(&mut self, buffer: &mut [u8]) -> Result<usize> {
+            read_queued(&mut self.readable, buffer).await
+        }
+
+        fn is_connected(&self) -> bool {
+            true
+        }
+    }
+
+    /// 不经过连接与 actor 的假模组：按 `respond` 逐条回复指令，返回发送端与已收到指令的记录
+    pub fn scripted_sender(
+        respond: impl Fn(&str) -> crate::models::ATResponse + Send + 'static,
+    ) -> (crate::models::CommandSender, Arc<Mutex<Vec<String>>>) {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<(crate::models::AtCommand, tokio::sync::oneshot::Sender<_>)>(8);
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let log = sent.clone();
+        tokio::spawn(async move {
+            while let Some((cmd, reply)) = rx.recv().await {
+                log.lock().unwrap().push(cmd.cmd.clone());
+                let _ = reply.send(respond(&cmd.cmd));
+            }
+        });
+        (tx, sent)
+    }
+}
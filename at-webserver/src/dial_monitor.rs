@@ -1,10 +1,12 @@
 use crate::client::ATClient;
 use crate::config::Config;
-use crate::models::get_ndis_disconnect_tx;
+use crate::models::{get_ndis_disconnect_tx, CommandSender, ConnectionType};
 use crate::network;
+use crate::notifications::{NotificationManager, NotificationType};
 use log::{info, warn, error, debug};
+use std::sync::OnceLock;
 use std::time::Duration;
-use tokio::time::{sleep, interval};
+use tokio::time::{sleep, interval, Instant};
 use tokio::process::Command;
 use anyhow::Result;
 
@@ -36,17 +38,79 @@ enum ConnectionState {
     FullStackConfigured,
 }
 
-pub async fn start_monitor(config: Config, at_client: ATClient) {
+/// 拨号失败的原因，决定后续是等待注册、停止重试还是下一轮照常重试
+#[derive(Debug, Clone, PartialEq)]
+enum DialError {
+    /// +CME ERROR: 30，当前没有网络服务
+    NoService(String),
+    /// APN/PDP 配置或签约问题，重试也不会成功
+    Config(String),
+    Other(String),
+}
+
+impl DialError {
+    /// 按 `+CME ERROR: <n>`（数字或文本）分类
+    fn classify(error: &str) -> Self {
+        let error = error.trim().to_string();
+        let detail = error.strip_prefix("+CME ERROR:").map(str::trim).unwrap_or("");
+        match detail.parse::<u32>() {
+            Ok(30) => DialError::NoService(error),
+            // 50 参数错误（APN）、132/133 业务未支持/未签约、149 PDP 鉴权失败；
+            // 3 操作不允许、4 不支持是通用错误，拨号过程中多为暂时状态，按普通失败重试
+            Ok(50 | 132 | 133 | 149) => DialError::Config(error),
+            Ok(_) => DialError::Other(error),
+            Err(_) if detail.eq_ignore_ascii_case("no network service") => DialError::NoService(error),
+            Err(_) if detail.to_lowercase().contains("authentication") || detail.eq_ignore_ascii_case("incorrect parameters") => {
+                DialError::Config(error)
+            }
+            Err(_) => DialError::Other(error),
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        match self {
+            DialError::NoService(_) => "no_service",
+            DialError::Config(_) => "config_error",
+            DialError::Other(_) => "failed",
+        }
+    }
+
+    fn reason(&self) -> &str {
+        match self {
+            DialError::NoService(r) | DialError::Config(r) | DialError::Other(r) => r,
+        }
+    }
+}
+
+impl std::fmt::Display for DialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.status(), self.reason())
+    }
+}
+
+/// 配置错误后暂停自动拨号的时长，期间拿到 IP（用户已在前端修正）会立即解除
+const CONFIG_ERROR_BACKOFF: Duration = Duration::from_secs(1800);
+
+/// 无网络服务时等待重新注册的最长时间
+const REGISTRATION_WAIT: Duration = Duration::from_secs(120);
+
+pub async fn start_monitor(config: Config, at_client: ATClient, notifications: NotificationManager) {
     info!("Starting dial monitor with Disaster Recovery...");
     
     let mut state = ConnectionState::Disconnected;
     let mut ping_fail_count = 0u32;
     let mut unexpected_response_count = 0u32;
+    // 最近一次完成网络配置所用的网卡，模组复位后可能重新枚举成别的 usbN
+    let mut current_ifname: Option<String> = None;
+    // 拨号因配置错误失败的时间，暂停期间不再自动重拨
+    let mut dial_blocked: Option<Instant> = None;
 
     // 订阅 ^NDISSTAT 断开事件，断线时无需等待轮询立即响应
     let ndis_tx = get_ndis_disconnect_tx();
     let mut ndis_rx = ndis_tx.subscribe();
 
+    wait_for_modem_ready(&config, &at_client).await;
+
     // 10 秒轮询定时器
     let mut poll_timer = interval(Duration::from_secs(10));
     poll_timer.tick().await; // 消耗第一个立即触发的 tick
@@ -72,6 +136,11 @@ pub async fn start_monitor(config: Config, at_client: ATClient) {
             }
         };
 
+        // 模拟连接下没有真实网卡，不做拨号与网络配置
+        if crate::client::active_connection_type(&config.at_config) == ConnectionType::Simulation {
+            continue;
+        }
+
         // NDIS 断开事件直接触发恢复，跳过 IP 检查
         if ndis_disconnected {
             // 检查用户是否手动关闭了自动拨号，若关闭则不进行灾难恢复
@@ -85,7 +154,7 @@ pub async fn start_monitor(config: Config, at_client: ATClient) {
             if !matches!(state, ConnectionState::Disconnected) {
                 state = ConnectionState::Disconnected;
             }
-            trigger_disaster_recovery(&config, &at_client).await;
+            trigger_disaster_recovery(&config, &at_client, &notifications, &mut dial_blocked).await;
             ping_fail_count = 0;
             unexpected_response_count = 0;
             continue;
@@ -100,7 +169,7 @@ pub async fn start_monitor(config: Config, at_client: ATClient) {
                         warn!("AT+CGPADDR returned unexpected response. Count: {}/3", unexpected_response_count);
                         if unexpected_response_count >= 3 {
                             warn!("3 consecutive unexpected AT responses. Triggering disaster recovery.");
-                            trigger_disaster_recovery(&config, &at_client).await;
+                            trigger_disaster_recovery(&config, &at_client, &notifications, &mut dial_blocked).await;
                             unexpected_response_count = 0;
                             ping_fail_count = 0;
                             state = ConnectionState::Disconnected;
@@ -121,53 +190,33 @@ pub async fn start_monitor(config: Config, at_client: ATClient) {
                         } else {
                             warn!("No IP address detected. Triggering disaster recovery.");
                         }
-                        trigger_disaster_recovery(&config, &at_client).await;
+                        trigger_disaster_recovery(&config, &at_client, &notifications, &mut dial_blocked).await;
                         ping_fail_count = 0;
                         state = ConnectionState::Disconnected;
                     }
 
                     ref status if status.has_ip() => {
                         unexpected_response_count = 0;
+                        if dial_blocked.take().is_some() {
+                            info!("[dial] IP obtained, resuming automatic dialing");
+                        }
                         log_ip_status(status);
 
                         match state {
                             ConnectionState::Disconnected => {
                                 info!("IP address detected. Starting network setup...");
 
-                                debug!("Initializing modem URC reporting configs...");
-                                let _ = at_client.send_command("AT+CNMI=2,1,0,2,0".to_string()).await;
-                                let _ = at_client.send_command("AT+CMGF=0".to_string()).await;
-                                let _ = at_client.send_command("AT+CLIP=1".to_string()).await;
-                                // 手册：AT+CPMS 的 mem3（接收存储）掉电不保存，重启后重置
-                                // mem1/mem2 上电后与上次 mem3 保持一致，因此三者都需重新下发
-                                let sms_mem = &config.advanced_network_config.sms_storage;
-                                let cpms_cmd = format!("AT+CPMS=\"{}\",\"{}\",\"{}\"", sms_mem, sms_mem, sms_mem);
-                                debug!("Setting SMS storage to {} (AT+CPMS)...", sms_mem);
-                                let _ = at_client.send_command(cpms_cmd).await;
+                                configure_urc_reporting(&config, &at_client.get_sender()).await;
 
                                 let actual_ifname = detect_modem_ifname(&config.advanced_network_config.ifname).await;
                                 debug!("Auto-detected 5G interface: {}", actual_ifname);
-
-                                if let Err(e) = network::setup_ipv4_only(&config, &actual_ifname).await {
-                                    error!("Failed to setup IPv4 network: {}", e);
-                                } else {
-                                    debug!("IPv4 setup done.");
+                                if current_ifname.as_deref().is_some_and(|old| old != actual_ifname) {
+                                    info!("Modem interface changed: {} -> {}", current_ifname.as_deref().unwrap_or(""), actual_ifname);
                                 }
+                                broadcast_ifname(&actual_ifname);
 
-                                let pdp_type = config.advanced_network_config.pdp_type.to_lowercase();
-                                // ipv6_needed：配置了 v6 协议类型（ipv4v6 / ipv6）
-                                // 注意：不依赖 ipv6_present（AT+CGPADDR 可能只返回数据 PDP 的 IPv4，
-                                // IMS/IPv6 地址不一定出现在响应中），只要配置了就尝试注入
-                                let ipv6_needed = pdp_type.contains("v6") || pdp_type.contains("ipv6");
-
-                                if ipv6_needed {
-                                    info!("IPv6 configured (pdp_type={}). Injecting IPv6 interface...", pdp_type);
-                                    if let Err(e) = network::inject_ipv6_interface(&config, &actual_ifname).await {
-                                        error!("Failed to inject IPv6 interface: {}", e);
-                                    } else {
-                                        debug!("IPv6 Injection Completed.");
-                                    }
-                                }
+                                network::setup_modem_network(&config, &actual_ifname, false).await;
+                                current_ifname = Some(actual_ifname);
 
                                 state = ConnectionState::FullStackConfigured;
                                 ping_fail_count = 0;
@@ -175,12 +224,27 @@ pub async fn start_monitor(config: Config, at_client: ATClient) {
                             }
 
                             ConnectionState::FullStackConfigured => {
+                                // 已配置的网卡消失（模组复位后重新枚举），重新探测并在新网卡上重做网络配置
+                                if let Some(old_ifname) = current_ifname.clone() {
+                                    if !interface_exists(&old_ifname).await {
+                                        let new_ifname = detect_modem_ifname(&config.advanced_network_config.ifname).await;
+                                        if new_ifname != old_ifname && interface_exists(&new_ifname).await {
+                                            warn!("Modem interface {} disappeared, re-running network setup on {}", old_ifname, new_ifname);
+                                            broadcast_ifname(&new_ifname);
+                                            network::setup_modem_network(&config, &new_ifname, true).await;
+                                            current_ifname = Some(new_ifname);
+                                            ping_fail_count = 0;
+                                            continue;
+                                        }
+                                    }
+                                }
+
                                 if !check_router_network_status(&config).await {
                                     ping_fail_count += 1;
                                     warn!("Router-side network check failed. Count: {}/3", ping_fail_count);
                                     if ping_fail_count >= 3 {
                                         warn!("Continuous 3 router-side failures detected! Triggering disaster recovery.");
-                                        trigger_disaster_recovery(&config, &at_client).await;
+                                        trigger_disaster_recovery(&config, &at_client, &notifications, &mut dial_blocked).await;
                                         ping_fail_count = 0;
                                         state = ConnectionState::Disconnected;
                                         continue;
@@ -203,6 +267,159 @@ pub async fn start_monitor(config: Config, at_client: ATClient) {
     }
 }
 
+/// 首次拨号前就绪检查的轮询间隔
+const READY_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// 下发上报配置前等待模组与 SIM 就绪的最长时间，超时后仍尝试下发
+const INIT_READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// 上报配置指令的最多下发轮数，每轮只重发上一轮失败的指令
+const INIT_ROUNDS: u32 = 3;
+
+/// 两轮上报配置之间的等待
+const INIT_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// 下发短信/来电/IMS 注册上报与短信存储配置；拨号成功后与 AT 会话定期重连（`session_max_age`）后调用
+///
+/// SIM 子系统未就绪时 CNMI/CPMS 等会返回 +CME ERROR，先等 `AT` 应答 OK 且 `AT+CPIN?` 为 READY，
+/// 失败的指令隔几秒重发，直到全部成功或用完 `INIT_ROUNDS` 轮。
+pub async fn configure_urc_reporting(config: &Config, cmd_tx: &CommandSender) {
+    debug!("Initializing modem URC reporting configs...");
+    if let Err(reason) = wait_for_init_ready(cmd_tx).await {
+        warn!("Modem not ready after {}s ({}), configuring URC reporting anyway", INIT_READY_TIMEOUT.as_secs(), reason);
+    }
+
+    // 手册：AT+CPMS 的 mem3（接收存储）掉电不保存，重启后重置
+    // mem1/mem2 上电后与上次 mem3 保持一致，因此三者都需重新下发
+    let sms_mem = &config.advanced_network_config.sms_storage;
+    debug!("Setting SMS storage to {} (AT+CPMS)...", sms_mem);
+    let cpms_cmd = format!("AT+CPMS=\"{}\",\"{}\",\"{}\"", sms_mem, sms_mem, sms_mem);
+    let mut pending = vec!["AT+CNMI=2,1,0,2,0", "AT+CMGF=0", "AT+CLIP=1", cpms_cmd.as_str()];
+
+    // IMS 注册状态上报（+CIREGU），ImsHandler 据此广播变化；部分固件不支持，失败不参与重试
+    match crate::retry::send_with_retry(cmd_tx, "AT+CIREG=2", false).await {
+        Ok(resp) if resp.success => {}
+        Ok(resp) => debug!("AT+CIREG=2 not supported: {:?}", resp.error),
+        Err(e) => debug!("AT+CIREG=2 failed: {}", e),
+    }
+    for round in 1..=INIT_ROUNDS {
+        let mut failed = Vec::new();
+        for cmd in pending {
+            match crate::retry::send_with_retry(cmd_tx, cmd, false).await {
+                Ok(resp) if resp.success => {}
+                Ok(resp) => {
+                    debug!("{} failed: {:?}", cmd, resp.error);
+                    failed.push(cmd);
+                }
+                Err(e) => {
+                    debug!("{} failed: {}", cmd, e);
+                    failed.push(cmd);
+                }
+            }
+        }
+        if failed.is_empty() {
+            debug!("URC reporting configured");
+            return;
+        }
+        pending = failed;
+        if round < INIT_ROUNDS {
+            sleep(INIT_RETRY_DELAY).await;
+        }
+    }
+    warn!("URC reporting not fully configured after {} rounds, failed: {}", INIT_ROUNDS, pending.join(", "));
+}
+
+/// 等待模组应答 `AT` 且 SIM 就绪，超时返回最后一次未就绪的原因
+async fn wait_for_init_ready(cmd_tx: &CommandSender) -> Result<(), String> {
+    let deadline = Instant::now() + INIT_READY_TIMEOUT;
+    loop {
+        let reason = match crate::retry::send_with_retry(cmd_tx, "AT", false).await {
+            Ok(resp) if resp.success => match crate::retry::send_with_retry(cmd_tx, "AT+CPIN?", false).await {
+                Ok(resp) if resp.data.as_deref().is_some_and(|d| d.contains("READY")) => return Ok(()),
+                Ok(resp) => format!("SIM not ready ({})", resp.error.or(resp.data).unwrap_or_default().trim()),
+                Err(e) => format!("AT+CPIN? failed ({})", e),
+            },
+            Ok(resp) => format!("AT returned {:?}", resp.error),
+            Err(e) => format!("modem not responding ({})", e),
+        };
+        if Instant::now() >= deadline {
+            return Err(reason);
+        }
+        debug!("Waiting for modem before configuring URC reporting: {}", reason);
+        sleep(READY_POLL_INTERVAL).await;
+    }
+}
+
+/// 开机时模组往往还在初始化，立即拨号只会失败刷错误日志。
+/// 先等待 `dial_startup_delay` 秒，再等 SIM 就绪且已注册网络，最多 `dial_ready_timeout` 秒，超时后照常开始监控
+async fn wait_for_modem_ready(config: &Config, at_client: &ATClient) {
+    let delay = config.advanced_network_config.dial_startup_delay;
+    if delay > 0 {
+        info!("[monitor] Waiting {}s for the modem to initialize before the first dial", delay);
+        sleep(Duration::from_secs(delay)).await;
+    }
+
+    let ready_timeout = config.advanced_network_config.dial_ready_timeout;
+    if ready_timeout == 0 {
+        return;
+    }
+    let deadline = Instant::now() + Duration::from_secs(ready_timeout);
+    let mut last_reason = String::new();
+    loop {
+        let reason = match at_client.send_command("AT+CPIN?".to_string()).await {
+            Ok(resp) if resp.data.as_deref().is_some_and(|d| d.contains("READY")) => {
+                if is_registered(at_client).await {
+                    info!("[monitor] SIM ready and registered, starting dial monitor");
+                    return;
+                }
+                "not registered".to_string()
+            }
+            Ok(resp) => format!("SIM not ready ({})", resp.error.or(resp.data).unwrap_or_default().trim()),
+            Err(e) => format!("modem not responding ({})", e),
+        };
+        if reason != last_reason {
+            debug!("[monitor] Modem not ready for dialing: {}", reason);
+            last_reason = reason;
+        }
+        if Instant::now() >= deadline {
+            warn!("[monitor] Modem still not ready after {}s ({}), starting dial monitor anyway", ready_timeout, last_reason);
+            return;
+        }
+        sleep(READY_POLL_INTERVAL).await;
+    }
+}
+
+/// 注册状态行 `+CREG: <n>,<stat>`（CEREG/C5GREG 同）中的 stat
+pub(crate) fn registration_stat(data: &str) -> Option<&str> {
+    data.lines()
+        .filter_map(|l| l.trim().split_once(':'))
+        .filter(|(prefix, _)| matches!(*prefix, "+CREG" | "+CEREG" | "+C5GREG"))
+        .find_map(|(_, rest)| rest.split(',').nth(1))
+        .map(str::trim)
+}
+
+/// stat 1 为本地网、5 为漫游
+pub fn parse_registered(data: &str) -> bool {
+    matches!(registration_stat(data), Some("1") | Some("5"))
+}
+
+/// 任一制式（2G/3G、LTE、5G SA）已注册即可拨号
+async fn is_registered(at_client: &ATClient) -> bool {
+    for cmd in ["AT+CEREG?", "AT+C5GREG?", "AT+CREG?"] {
+        if let Ok(resp) = at_client.send_command(cmd.to_string()).await {
+            if resp.data.as_deref().is_some_and(parse_registered) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// 当前是否已分配到数据 IP（AT+CGPADDR），供其他模块判断数据业务是否可用
+pub async fn has_data_ip(at_client: &ATClient) -> Result<bool> {
+    Ok(check_ip_status(at_client).await?.has_ip())
+}
+
 /// 打印当前 IP 状态到日志
 fn log_ip_status(status: &IpStatus) {
     match status {
@@ -214,6 +431,10 @@ fn log_ip_status(status: &IpStatus) {
 }
 
 async fn check_router_network_status(_config: &Config) -> bool {
+    // 没有 netifd 工具时无法检查路由侧状态，也不会去配置它，按正常处理避免反复触发恢复
+    if !crate::capabilities::network_setup_available() {
+        return true;
+    }
     // 1) 检查 wan_modem 接口状态（路由侧）
     let status_out = Command::new("ifstatus")
         .arg("wan_modem")
@@ -289,72 +510,90 @@ async fn check_ip_status(at_client: &ATClient) -> Result<IpStatus> {
 
     debug!("IP Check Response: {}", content);
 
+    let status = parse_cgpaddr(&content);
+    match &status {
+        // 如果根本没有 +CGPADDR: 行，视为异常响应
+        IpStatus::Unexpected => {
+            warn!("AT+CGPADDR response contains no +CGPADDR line: {}", content.replace('\n', " ").replace('\r', " "));
+        }
+        IpStatus::NoIp => crate::status::record_ip(None, None),
+        IpStatus::Ipv4Only(v4) => crate::status::record_ip(Some(v4.clone()), None),
+        IpStatus::Ipv6Only(v6) => crate::status::record_ip(None, Some(v6.clone())),
+        IpStatus::DualStack(v4, v6) => crate::status::record_ip(Some(v4.clone()), Some(v6.clone())),
+    }
+    Ok(status)
+}
+
+/// 解析 `AT+CGPADDR` 响应
+///
+/// 兼容各固件的写法：上下文 id 带或不带引号、逗号前后有空格、同一字段内 IPv4 与 IPv6 以空格分隔，
+/// 地址可以是 `a.b.c.d`、冒号格式 IPv6、MT5700M-CN 等华为模组的点分十进制 IPv6（16 字节，15 个点），
+/// 以及个别固件在地址后附带掩码的写法（IPv4 8 段、IPv6 32 段）。全零地址视为未分配。
+fn parse_cgpaddr(content: &str) -> IpStatus {
     let mut found_v4: Option<String> = None;
     let mut found_v6: Option<String> = None;
     let mut has_cgpaddr_line = false;
 
     for line in content.lines() {
-        let line = line.trim();
-        if !line.starts_with("+CGPADDR:") {
+        let Some(rest) = line.trim().strip_prefix("+CGPADDR:") else {
             continue;
-        }
+        };
         has_cgpaddr_line = true;
 
-        let parts: Vec<&str> = line.splitn(2, ':').collect();
-        if parts.len() < 2 {
-            continue;
-        }
-
-        let segments: Vec<&str> = parts[1].split(',').collect();
-        // segments[0] 是 PDP 索引，从 [1] 开始是 IP
-        for segment in segments.iter().skip(1) {
-            let clean_ip = segment.trim_matches(|c| c == '"' || c == ' ' || c == '\r' || c == '\n');
-
-            if clean_ip.is_empty() || clean_ip == "0.0.0.0" || clean_ip == "::" {
-                continue;
-            }
-
-            // MT5700M-CN 的 IPv6 地址以点分十进制格式返回（16个字节，共15个点）
-            // 例如: "32.8.0.2.0.2.0.1.255.255.255.255.255.255.255.255"
-            // 标准冒号格式: "2001:db8::1" 也兼容处理
-            let dot_count = clean_ip.chars().filter(|&c| c == '.').count();
-            let colon_count = clean_ip.chars().filter(|&c| c == ':').count();
-
-            if colon_count >= 2 {
-                // 标准 IPv6 冒号格式
-                debug!("Detected IPv6 (colon fmt): {}", clean_ip);
-                found_v6 = Some(clean_ip.to_string());
-            } else if dot_count == 15 {
-                // MT5700M-CN 点分十进制 IPv6 格式（16字节，15个点）
-                // 验证所有段都是 0-255 的数字
-                let all_valid = clean_ip.split('.').all(|s| s.parse::<u8>().is_ok());
-                if all_valid {
-                    debug!("Detected IPv6 (dotted-decimal fmt): {}", clean_ip);
-                    found_v6 = Some(clean_ip.to_string());
-                } else {
-                    debug!("Detected IPv4: {}", clean_ip);
-                    found_v4 = Some(clean_ip.to_string());
+        // 第一个字段是 PDP 上下文 id，从第二个字段开始是地址
+        for field in rest.split(',').skip(1) {
+            let field = field.trim().trim_matches('"');
+            for token in field.split_whitespace() {
+                match classify_address(token) {
+                    Some(PdpAddress::V4(v4)) => {
+                        debug!("Detected IPv4: {}", v4);
+                        found_v4.get_or_insert(v4);
+                    }
+                    Some(PdpAddress::V6(v6)) => {
+                        debug!("Detected IPv6: {}", v6);
+                        found_v6.get_or_insert(v6);
+                    }
+                    None => {}
                 }
-            } else if clean_ip.contains('.') && dot_count == 3 {
-                // 标准 IPv4 格式（x.x.x.x）
-                debug!("Detected IPv4: {}", clean_ip);
-                found_v4 = Some(clean_ip.to_string());
             }
         }
     }
 
-    // 如果根本没有 +CGPADDR: 行，视为异常响应
     if !has_cgpaddr_line {
-        warn!("AT+CGPADDR response contains no +CGPADDR line: {}", content.replace('\n', " ").replace('\r', " "));
-        return Ok(IpStatus::Unexpected);
+        return IpStatus::Unexpected;
     }
-
-    Ok(match (found_v4, found_v6) {
+    match (found_v4, found_v6) {
         (Some(v4), Some(v6)) => IpStatus::DualStack(v4, v6),
         (Some(v4), None)     => IpStatus::Ipv4Only(v4),
         (None,     Some(v6)) => IpStatus::Ipv6Only(v6),
         (None,     None)     => IpStatus::NoIp,
-    })
+    }
+}
+
+enum PdpAddress {
+    V4(String),
+    V6(String),
+}
+
+/// 识别单个地址，无效或全零地址返回 None
+fn classify_address(token: &str) -> Option<PdpAddress> {
+    if token.contains(':') {
+        let addr: std::net::Ipv6Addr = token.parse().ok()?;
+        return (!addr.is_unspecified()).then(|| PdpAddress::V6(token.to_string()));
+    }
+
+    let octets: Vec<u8> = token.split('.').map(|s| s.parse().ok()).collect::<Option<_>>()?;
+    if octets.iter().all(|&b| b == 0) {
+        return None;
+    }
+    let join = |bytes: &[u8]| bytes.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(".");
+    match octets.len() {
+        4 => Some(PdpAddress::V4(token.to_string())),
+        8 => Some(PdpAddress::V4(join(&octets[..4]))),
+        16 => Some(PdpAddress::V6(token.to_string())),
+        32 => Some(PdpAddress::V6(join(&octets[..16]))),
+        _ => None,
+    }
 }
 
 /// MT5700M-CN 专用拨号函数。
@@ -363,12 +602,38 @@ async fn check_ip_status(at_client: &ATClient) -> Result<IpStatus> {
 /// PDP 配置（AT+CGDCONT）和激活（AT+CGACT）由用户在前端完成，
 /// 模组内部已保存的 PDP 数据会在 NDISDUP 时自动使用。
 /// 后端只负责建立 NDIS 数据通道（AT^NDISDUP=1,1）。
-async fn perform_dial(_config: &Config, at_client: &ATClient) -> Result<()> {
+async fn perform_dial(_config: &Config, at_client: &ATClient) -> Result<(), DialError> {
     // 手册：NDISDUP 是异步AT，OK 只代表发送成功
-    // 实际连接建立由 ^NDISSTAT: 1 URC 确认
+    // 实际连接建立由 ^NDISSTAT: 1 URC 确认；无服务、APN 错误等会直接以 +CME ERROR 返回
     info!("[dial] Establishing NDIS data channel (AT^NDISDUP=1,1)...");
-    let _ = at_client.send_command("AT^NDISDUP=1,1".to_string()).await;
-    Ok(())
+    match at_client.send_command("AT^NDISDUP=1,1".to_string()).await {
+        Ok(resp) if resp.success => Ok(()),
+        Ok(resp) => Err(DialError::classify(resp.error.as_deref().unwrap_or("ERROR"))),
+        Err(e) => Err(DialError::Other(e.to_string())),
+    }
+}
+
+/// 无网络服务时等待任一制式重新注册，超时返回 false
+async fn wait_for_registration(at_client: &ATClient) -> bool {
+    let deadline = Instant::now() + REGISTRATION_WAIT;
+    while Instant::now() < deadline {
+        if is_registered(at_client).await {
+            return true;
+        }
+        sleep(READY_POLL_INTERVAL).await;
+    }
+    false
+}
+
+/// 广播拨号状态，失败时带上具体原因
+fn broadcast_dial_status(status: &str, reason: Option<&str>) {
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = serde_json::json!({
+            "type": "dial_status",
+            "data": { "status": status, "reason": reason }
+        }).to_string();
+        let _ = tx.send(msg);
+    }
 }
 
 /// 检查模组是否已被用户手动关闭自动拨号（AT^SETAUTODIAL=0）
@@ -398,32 +663,76 @@ async fn is_auto_dial_disabled(at_client: &ATClient) -> bool {
 /// 2) 重启路由侧网卡（ip link down/up）
 ///
 /// 不再执行 HVSST/CFUN/CGATT/模组复位等慢恢复流程。
-async fn trigger_disaster_recovery(config: &Config, at_client: &ATClient) {
+///
+/// 拨号因配置错误失败后暂停自动重拨 `CONFIG_ERROR_BACKOFF`，并按 `notify_dial` 通知用户处理。
+async fn trigger_disaster_recovery(
+    config: &Config,
+    at_client: &ATClient,
+    notifications: &NotificationManager,
+    dial_blocked: &mut Option<Instant>,
+) {
+    if let Some(since) = *dial_blocked {
+        if since.elapsed() < CONFIG_ERROR_BACKOFF {
+            debug!("[FAST-RECOVERY] Skipped: dialing paused after a configuration error");
+            return;
+        }
+        info!("[FAST-RECOVERY] Configuration error backoff elapsed, trying to dial again");
+        *dial_blocked = None;
+    }
     warn!("[FAST-RECOVERY] Rebuilding NDIS channel and restarting interface...");
 
-    if try_dial_and_bind(config, at_client).await {
-        info!("[FAST-RECOVERY] Recovery succeeded.");
-    } else {
-        warn!("[FAST-RECOVERY] Recovery failed this round; will retry on next monitor cycle.");
+    match try_dial_and_bind(config, at_client).await {
+        Ok(()) => info!("[FAST-RECOVERY] Recovery succeeded."),
+        Err(DialError::Config(reason)) => {
+            error!("[FAST-RECOVERY] Dial rejected due to configuration ({}), pausing automatic dialing for {}s", reason, CONFIG_ERROR_BACKOFF.as_secs());
+            *dial_blocked = Some(Instant::now());
+            let content = format!("拨号失败：{}，请检查 APN/PDP 配置。自动拨号已暂停 {} 分钟", reason, CONFIG_ERROR_BACKOFF.as_secs() / 60);
+            let data = serde_json::json!({ "status": "config_error", "reason": reason });
+            notifications.notify_with_data("Dial", &content, NotificationType::Dial, Some(data)).await;
+        }
+        Err(e) => warn!("[FAST-RECOVERY] Recovery failed this round ({}); will retry on next monitor cycle.", e),
     }
 }
 
-/// 执行拨号并绑定数据通道，返回 true 表示成功
+/// 执行拨号并绑定数据通道，失败时返回原因并广播 `dial_status`
 /// 注意：调用此函数前已确认 SETAUTODIAL != 0
-async fn try_dial_and_bind(config: &Config, at_client: &ATClient) -> bool {
+async fn try_dial_and_bind(config: &Config, at_client: &ATClient) -> Result<(), DialError> {
+    let result = dial_and_bind(config, at_client).await;
+    match &result {
+        Ok(()) => broadcast_dial_status("connected", None),
+        Err(e) => broadcast_dial_status(e.status(), Some(e.reason())),
+    }
+    result
+}
+
+async fn dial_and_bind(config: &Config, at_client: &ATClient) -> Result<(), DialError> {
+    broadcast_dial_status("dialing", None);
     // 断开旧 NDIS 连接
     // 手册：NDISDUP 是异步AT，断开后需等待 ^NDISSTAT: 0，此处用 sleep 兜底
     let _ = at_client.send_command("AT^NDISDUP=1,0".to_string()).await;
     sleep(Duration::from_secs(2)).await;
 
     // perform_dial 只建立 NDIS 通道，PDP 由模组内部数据驱动
-    if let Err(e) = perform_dial(config, at_client).await {
-        warn!("[dial] perform_dial failed: {}", e);
+    match perform_dial(config, at_client).await {
+        Ok(()) => {}
+        Err(DialError::NoService(reason)) => {
+            warn!("[dial] No network service ({}), waiting up to {}s for registration", reason, REGISTRATION_WAIT.as_secs());
+            broadcast_dial_status("no_service", Some(&reason));
+            if !wait_for_registration(at_client).await {
+                return Err(DialError::NoService(reason));
+            }
+            info!("[dial] Registered again, retrying dial");
+            perform_dial(config, at_client).await?;
+        }
+        Err(e) => {
+            warn!("[dial] perform_dial failed: {}", e);
+            return Err(e);
+        }
     }
 
     if !wait_for_ip(at_client).await {
         warn!("[dial] Timed out waiting for IP.");
-        return false;
+        return Err(DialError::Other("Timed out waiting for IP".to_string()));
     }
     info!("[dial] IP obtained. Binding NDIS channel...");
     // 手册：NDISDUP 是异步AT，sleep 5s 等待 ^NDISSTAT: 1 及 DHCP 就绪
@@ -434,7 +743,7 @@ async fn try_dial_and_bind(config: &Config, at_client: &ATClient) -> bool {
     sleep(Duration::from_secs(1)).await;
     let _ = Command::new("ip").args(&["link", "set", "dev", &actual_ifname, "up"]).status().await;
     info!("[dial] Interface {} restarted. Recovery complete.", actual_ifname);
-    true
+    Ok(())
 }
 
 /// 等待有效 IP，参考 QModem 增加 120 秒超时熔断
@@ -469,7 +778,22 @@ async fn wait_for_ip(at_client: &ATClient) -> bool {
     }
 }
 
-async fn detect_modem_ifname(configured: &str) -> String {
+async fn interface_exists(ifname: &str) -> bool {
+    fs::metadata(format!("/sys/class/net/{}", ifname)).await.is_ok()
+}
+
+/// 广播当前绑定的模组网卡名，便于前端排查
+fn broadcast_ifname(ifname: &str) {
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = serde_json::json!({
+            "type": "modem_ifname",
+            "data": { "ifname": ifname }
+        }).to_string();
+        let _ = tx.send(msg);
+    }
+}
+
+pub async fn detect_modem_ifname(configured: &str) -> String {
     if !configured.is_empty() && configured != "auto" {
         return configured.to_string();
     }
@@ -479,14 +803,45 @@ async fn detect_modem_ifname(configured: &str) -> String {
     "usb0".to_string()
 }
 
+/// 内置认可的 USB 厂商 ID：MT5700M-CN 为鼎桥 3466
+const BUILTIN_VIDS: [&str; 1] = ["3466"];
+
+/// 内置列表与配置 `modem_vids` 合并后的结果，启动时设置一次
+static MODEM_VIDS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// 规范化配置中的厂商 ID：去掉 `0x` 前缀并转小写，必须是 4 位十六进制
+fn normalize_vid(vid: &str) -> Option<String> {
+    let vid = vid.trim().to_lowercase();
+    let vid = vid.strip_prefix("0x").unwrap_or(&vid);
+    (vid.len() == 4 && vid.chars().all(|c| c.is_ascii_hexdigit())).then(|| vid.to_string())
+}
+
+/// 合并配置的厂商 ID 与内置列表，并记录实际生效的列表
+pub fn init_vendor_ids(extra: &[String]) {
+    let mut vids: Vec<String> = BUILTIN_VIDS.iter().map(|v| v.to_string()).collect();
+    for raw in extra {
+        match normalize_vid(raw) {
+            Some(vid) if !vids.contains(&vid) => vids.push(vid),
+            Some(_) => {}
+            None => warn!("Ignoring invalid modem vendor ID {:?} in modem_vids", raw),
+        }
+    }
+    info!("Modem vendor IDs for interface detection: {}", vids.join(", "));
+    let _ = MODEM_VIDS.set(vids);
+}
+
+fn is_modem_vid(vid: &str) -> bool {
+    match MODEM_VIDS.get() {
+        Some(vids) => vids.iter().any(|v| v == vid),
+        None => BUILTIN_VIDS.contains(&vid),
+    }
+}
+
 /// 基于 QModem 原理的绝对精准探测法：直接读取 USB 设备的 Vendor ID (厂商代码)
 async fn detect_modem_interface() -> Option<String> {
     let net_dir = "/sys/class/net";
     let Ok(mut entries) = fs::read_dir(net_dir).await else { return None; };
 
-    // MT5700M-CN 专用：只匹配鼎桥 VID 3466
-    let valid_vids = ["3466"];
-
     while let Ok(Some(entry)) = entries.next_entry().await {
         let iface = entry.file_name().into_string().unwrap_or_default();
         if iface == "lo" || iface.starts_with("br-") || iface.starts_with("wl") || iface.starts_with("ra") {
@@ -507,7 +862,7 @@ async fn detect_modem_interface() -> Option<String> {
         }
 
         let vid = vid.trim().to_lowercase();
-        if !vid.is_empty() && valid_vids.contains(&vid.as_str()) {
+        if !vid.is_empty() && is_modem_vid(&vid) {
             info!("Hardware probing success! Found 5G modem: {} (Vendor ID: {})", iface, vid);
             return Some(iface);
         }
@@ -515,4 +870,76 @@ async fn detect_modem_interface() -> Option<String> {
 
     warn!("No valid 5G/4G USB modem interface found based on Vendor ID.");
     None
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOTTED_V6: &str = "36.9.128.16.0.1.0.2.0.0.0.0.0.0.0.1";
+
+    #[test]
+    fn normalizes_configured_vendor_ids() {
+        assert_eq!(normalize_vid("12D1"), Some("12d1".to_string()));
+        assert_eq!(normalize_vid(" 0x2c7c "), Some("2c7c".to_string()));
+        assert_eq!(normalize_vid("huawei"), None);
+        assert_eq!(normalize_vid("12d"), None);
+    }
+
+    #[test]
+    fn classifies_dial_errors() {
+        assert_eq!(DialError::classify("+CME ERROR: 30").status(), "no_service");
+        assert_eq!(DialError::classify("+CME ERROR: no network service").status(), "no_service");
+        assert_eq!(DialError::classify("+CME ERROR: 50").status(), "config_error");
+        assert_eq!(DialError::classify("+CME ERROR: 149").status(), "config_error");
+        assert_eq!(DialError::classify("+CME ERROR: 3").status(), "failed");
+        assert_eq!(DialError::classify("+CME ERROR: 4").status(), "failed");
+        assert_eq!(DialError::classify("ERROR").status(), "failed");
+    }
+
+    #[test]
+    fn cgpaddr_unquoted_cid_with_ipv4() {
+        assert_eq!(parse_cgpaddr("+CGPADDR: 1,\"10.12.34.56\"\r\nOK"), IpStatus::Ipv4Only("10.12.34.56".to_string()));
+        assert_eq!(parse_cgpaddr("+CGPADDR:1, 10.12.34.56"), IpStatus::Ipv4Only("10.12.34.56".to_string()));
+    }
+
+    #[test]
+    fn cgpaddr_quoted_cid_dual_stack() {
+        let data = format!("+CGPADDR: \"1\",\"10.12.34.56\",\"{}\"", DOTTED_V6);
+        assert_eq!(parse_cgpaddr(&data), IpStatus::DualStack("10.12.34.56".to_string(), DOTTED_V6.to_string()));
+    }
+
+    #[test]
+    fn cgpaddr_colon_ipv6_and_space_separated_field() {
+        assert_eq!(
+            parse_cgpaddr("+CGPADDR: 1,\"0.0.0.0\",\"2409:8a00::1\""),
+            IpStatus::Ipv6Only("2409:8a00::1".to_string())
+        );
+        assert_eq!(
+            parse_cgpaddr("+CGPADDR: 1,\"10.0.0.2 2409:8a00::1\""),
+            IpStatus::DualStack("10.0.0.2".to_string(), "2409:8a00::1".to_string())
+        );
+    }
+
+    #[test]
+    fn cgpaddr_address_with_mask() {
+        assert_eq!(
+            parse_cgpaddr("+CGPADDR: 1,\"10.0.0.2.255.255.255.0\""),
+            IpStatus::Ipv4Only("10.0.0.2".to_string())
+        );
+    }
+
+    #[test]
+    fn cgpaddr_without_address() {
+        assert_eq!(parse_cgpaddr("+CGPADDR: 1"), IpStatus::NoIp);
+        assert_eq!(parse_cgpaddr("+CGPADDR: 1,\"0.0.0.0\",\"0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0\""), IpStatus::NoIp);
+        assert_eq!(parse_cgpaddr("+CGPADDR: 1,\"::\""), IpStatus::NoIp);
+        assert_eq!(parse_cgpaddr("OK"), IpStatus::Unexpected);
+    }
+
+    #[test]
+    fn cgpaddr_multiple_contexts() {
+        let data = format!("+CGPADDR: 1,\"10.0.0.2\"\r\n+CGPADDR: 2,\"{}\"", DOTTED_V6);
+        assert_eq!(parse_cgpaddr(&data), IpStatus::DualStack("10.0.0.2".to_string(), DOTTED_V6.to_string()));
+    }
+}
@@ -0,0 +1,392 @@
+//! 模组当前的锁频/锁小区状态（`AT^LTEFREQLOCK?`、`AT^NRFREQLOCK?`）
+//!
+//! 查询响应与设置指令的字段顺序一致：
+//! `^LTEFREQLOCK: <type>,<mode>,<count>,"<bands>"[,"<arfcns>"[,"<pcis>"]]`，
+//! NR 在 ARFCN 与 PCI 之间多一个 `"<scs_types>"`。type 0 为未锁定，1 锁频点，2 锁小区，3 锁频段。
+//! 服务重启后前端据此显示实际生效的锁定，定时锁频也可用来回读校验。
+//!
+//! 设置指令的构造（定时锁频与手动切换的锁频配置共用）也在这里。锁频配置为 UCI 列表 `lock_profile`，
+//! 每项形如 `<名称>:lte=<type>/<bands>[/<arfcns>[/<pcis>]];nr=<type>/<bands>[/<arfcns>[/<scs>[/<pcis>]]]`，
+//! 列表内部用逗号分隔；省略的制式或 type 0 在应用时解锁。
+
+use crate::at_parse::split_fields;
+use crate::models::CommandSender;
+use crate::retry;
+use anyhow::{anyhow, Result};
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::sleep;
+
+/// 最近一次通过 APPLY_PROFILE 应用的配置名，CLEAR_PROFILE 后清空
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FreqLock {
+    pub lock_type: u8,
+    /// none / frequency / cell / band
+    pub kind: &'static str,
+    pub bands: Vec<String>,
+    pub arfcns: Vec<String>,
+    /// 仅 NR 锁频点/锁小区时有值
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scs_types: Vec<String>,
+    pub pcis: Vec<String>,
+}
+
+fn lock_kind(lock_type: u8) -> &'static str {
+    match lock_type {
+        0 => "none",
+        1 => "frequency",
+        2 => "cell",
+        3 => "band",
+        _ => "unknown",
+    }
+}
+
+fn split_list(field: Option<&String>) -> Vec<String> {
+    field
+        .map(|f| f.split(',').map(str::trim).filter(|s| !s.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn parse_lock(data: &str, prefix: &str, nr: bool) -> Option<FreqLock> {
+    let rest = data.lines().find_map(|l| l.trim().strip_prefix(prefix))?;
+    let fields = split_fields(rest);
+    let lock_type: u8 = fields.first()?.parse().ok()?;
+    // 字段 1 为保留的 mode，字段 2 为频段个数，列表从字段 3 开始
+    let list = |i: usize| split_list(fields.get(i));
+    let (arfcns, scs_types, pcis) = if nr { (list(4), list(5), list(6)) } else { (list(4), Vec::new(), list(5)) };
+    Some(FreqLock { lock_type, kind: lock_kind(lock_type), bands: list(3), arfcns, scs_types, pcis })
+}
+
+/// 解析 `^LTEFREQLOCK:` 查询响应
+pub fn parse_lte(data: &str) -> Option<FreqLock> {
+    parse_lock(data, "^LTEFREQLOCK:", false)
+}
+
+/// 解析 `^NRFREQLOCK:` 查询响应
+pub fn parse_nr(data: &str) -> Option<FreqLock> {
+    parse_lock(data, "^NRFREQLOCK:", true)
+}
+
+async fn query_one(cmd_tx: &CommandSender, cmd: &str, parse: fn(&str) -> Option<FreqLock>) -> Result<FreqLock> {
+    let resp = retry::send_with_retry(cmd_tx, cmd, false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    resp.data
+        .as_deref()
+        .and_then(parse)
+        .ok_or_else(|| anyhow!("Unexpected {} response", cmd))
+}
+
+/// 构造 `AT^LTEFREQLOCK` 设置指令，频点/PCI 个数与频段数不一致时退回解锁
+pub fn build_lte_command(lock_type: u8, bands: &[&str], arfcns: &str, pcis: &str) -> String {
+    // Type 1: Frequency point lock (Band + ARFCN)
+    // Type 2: Cell lock (Band + ARFCN + PCI)
+    // Type 3: Band lock (Band only)
+    
+    if lock_type == 3 {
+        // AT^LTEFREQLOCK=3,0,<count>,"<band1>,<band2>,..."
+        return format!("AT^LTEFREQLOCK=3,0,{},\"{}\"\r\n", bands.len(), bands.join(","));
+    } else if lock_type == 1 {
+        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        if bands.len() != arfcn_list.len() {
+            warn!("LTE Frequency Lock: Band count ({}) != ARFCN count ({}), unlocking", bands.len(), arfcn_list.len());
+            return "AT^LTEFREQLOCK=0\r\n".to_string();
+        }
+        // AT^LTEFREQLOCK=1,0,<count>,"<band1>,...","<arfcn1>,..."
+        return format!("AT^LTEFREQLOCK=1,0,{},\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","));
+    } else if lock_type == 2 {
+        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        let pci_list: Vec<&str> = pcis.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        
+        if bands.len() != arfcn_list.len() || arfcn_list.len() != pci_list.len() {
+            warn!("LTE Cell Lock: Count mismatch (Band:{}, ARFCN:{}, PCI:{}), unlocking", bands.len(), arfcn_list.len(), pci_list.len());
+            return "AT^LTEFREQLOCK=0\r\n".to_string();
+        }
+        // AT^LTEFREQLOCK=2,0,<count>,"<band1>,...","<arfcn1>,...","<pci1>,..."
+        return format!("AT^LTEFREQLOCK=2,0,{},\"{}\",\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","), pci_list.join(","));
+    }
+
+    "AT^LTEFREQLOCK=0\r\n".to_string()
+}
+
+/// 构造 `AT^NRFREQLOCK` 设置指令，频点/SCS/PCI 个数与频段数不一致时退回解锁
+pub fn build_nr_command(lock_type: u8, bands: &[&str], arfcns: &str, scs_types: &str, pcis: &str) -> String {
+    // Type 1: Frequency point lock (Band + ARFCN)
+    // Type 2: Cell lock (Band + ARFCN + SCS + PCI)
+    // Type 3: Band lock (Band only)
+
+    if lock_type == 3 {
+        // AT^NRFREQLOCK=3,0,<count>,"<band1>,..."
+        return format!("AT^NRFREQLOCK=3,0,{},\"{}\"\r\n", bands.len(), bands.join(","));
+    } else if lock_type == 1 {
+        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        if bands.len() != arfcn_list.len() {
+            warn!("NR Frequency Lock: Band count ({}) != ARFCN count ({}), unlocking", bands.len(), arfcn_list.len());
+            return "AT^NRFREQLOCK=0\r\n".to_string();
+        }
+        // AT^NRFREQLOCK=1,0,<count>,"<band1>,...","<arfcn1>,..."
+        return format!("AT^NRFREQLOCK=1,0,{},\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","));
+    } else if lock_type == 2 {
+        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        let scs_list: Vec<&str> = scs_types.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+        let pci_list: Vec<&str> = pcis.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
+
+        if bands.len() != arfcn_list.len() || arfcn_list.len() != scs_list.len() || scs_list.len() != pci_list.len() {
+            warn!("NR Cell Lock: Count mismatch (Band:{}, ARFCN:{}, SCS:{}, PCI:{}), unlocking", bands.len(), arfcn_list.len(), scs_list.len(), pci_list.len());
+            return "AT^NRFREQLOCK=0\r\n".to_string();
+        }
+        // AT^NRFREQLOCK=2,0,<count>,"<band1>,...","<arfcn1>,...","<scs1>,...","<pci1>,..."
+        return format!("AT^NRFREQLOCK=2,0,{},\"{}\",\"{}\",\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","), scs_list.join(","), pci_list.join(","));
+    }
+
+    "AT^NRFREQLOCK=0\r\n".to_string()
+}
+
+/// 一种制式的锁定参数，各列表为逗号分隔的原始字符串，与定时锁频的配置项一致
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockSpec {
+    pub lock_type: u8,
+    pub bands: String,
+    pub arfcns: String,
+    /// 仅 NR 使用
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub scs_types: String,
+    pub pcis: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LockProfile {
+    pub name: String,
+    pub lte: LockSpec,
+    pub nr: LockSpec,
+}
+
+impl LockSpec {
+    fn band_list(&self) -> Vec<&str> {
+        self.bands.split(',').map(str::trim).filter(|s| !s.is_empty()).collect()
+    }
+
+    /// 应用后是否需要锁定（否则下发解锁）
+    fn is_locked(&self) -> bool {
+        self.lock_type > 0 && !self.band_list().is_empty()
+    }
+
+    pub fn lte_command(&self) -> String {
+        if !self.is_locked() {
+            return "AT^LTEFREQLOCK=0\r\n".to_string();
+        }
+        build_lte_command(self.lock_type, &self.band_list(), &self.arfcns, &self.pcis)
+    }
+
+    pub fn nr_command(&self) -> String {
+        if !self.is_locked() {
+            return "AT^NRFREQLOCK=0\r\n".to_string();
+        }
+        build_nr_command(self.lock_type, &self.band_list(), &self.arfcns, &self.scs_types, &self.pcis)
+    }
+
+    /// 回读的锁定状态与期望是否一致（比较类型与频段集合）
+    fn matches(&self, lock: &FreqLock) -> bool {
+        if !self.is_locked() {
+            return lock.lock_type == 0;
+        }
+        let mut expected = self.band_list();
+        let mut actual: Vec<&str> = lock.bands.iter().map(String::as_str).collect();
+        expected.sort_unstable();
+        actual.sort_unstable();
+        lock.lock_type == self.lock_type && expected == actual
+    }
+}
+
+/// 解析一项 `lock_profile` 配置
+pub fn parse_profile(entry: &str) -> Result<LockProfile> {
+    let (name, rest) = entry.split_once(':').ok_or_else(|| anyhow!("Missing profile name in '{}'", entry))?;
+    let name = name.trim();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return Err(anyhow!("Invalid profile name '{}'", name));
+    }
+    let mut profile = LockProfile { name: name.to_string(), lte: LockSpec::default(), nr: LockSpec::default() };
+    for part in rest.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (rat, value) = part.split_once('=').ok_or_else(|| anyhow!("Invalid lock '{}' in profile {}", part, name))?;
+        let fields: Vec<String> = value.split('/').map(|f| f.trim().to_string()).collect();
+        let nr = match rat.trim().to_lowercase().as_str() {
+            "lte" => false,
+            "nr" => true,
+            other => return Err(anyhow!("Unknown RAT '{}' in profile {}", other, name)),
+        };
+        if fields.len() > if nr { 5 } else { 4 } {
+            return Err(anyhow!("Too many fields for {} in profile {}", rat.trim(), name));
+        }
+        let lock_type: u8 = fields[0].parse().ok().filter(|t| *t <= 3).ok_or_else(|| anyhow!("Invalid lock type '{}' in profile {}", fields[0], name))?;
+        let field = |i: usize| fields.get(i).cloned().unwrap_or_default();
+        let spec = if nr {
+            LockSpec { lock_type, bands: field(1), arfcns: field(2), scs_types: field(3), pcis: field(4) }
+        } else {
+            LockSpec { lock_type, bands: field(1), arfcns: field(2), scs_types: String::new(), pcis: field(3) }
+        };
+        if spec.lock_type > 0 && spec.band_list().is_empty() {
+            return Err(anyhow!("Lock type {} needs bands for {} in profile {}", lock_type, rat.trim(), name));
+        }
+        if nr {
+            profile.nr = spec;
+        } else {
+            profile.lte = spec;
+        }
+    }
+    Ok(profile)
+}
+
+/// 解析全部 `lock_profile`，无效或重名的项记录警告后跳过
+pub fn parse_profiles(entries: &[String]) -> Vec<LockProfile> {
+    let mut profiles: Vec<LockProfile> = Vec::new();
+    for entry in entries {
+        match parse_profile(entry) {
+            Ok(profile) if profiles.iter().any(|p| p.name == profile.name) => {
+                warn!("Duplicate lock profile '{}', ignoring", profile.name);
+            }
+            Ok(profile) => profiles.push(profile),
+            Err(e) => warn!("Ignoring lock profile: {}", e),
+        }
+    }
+    profiles
+}
+
+pub fn active_profile() -> Option<String> {
+    ACTIVE_PROFILE.lock().unwrap().clone()
+}
+
+/// 锁定被其他途径（定时锁频的锁定与解锁）改写后调用，LIST_PROFILES 不再报告过期的配置名
+pub fn clear_active_profile() {
+    ACTIVE_PROFILE.lock().unwrap().take();
+}
+
+/// 按需应用一组锁定（APPLY_PROFILE / CLEAR_PROFILE），不经过定时锁频
+///
+/// 与定时锁频相同的指令序列：可选进出飞行模式，依次下发 LTE、NR 设置，完成后回读并校验。
+/// 返回 `{commands:[{command, success, error}], readback:{lte, nr}, verified}`。
+pub async fn apply(cmd_tx: &CommandSender, lte: &LockSpec, nr: &LockSpec, toggle_airplane: bool) -> serde_json::Value {
+    let mut commands = Vec::new();
+    {
+        // 整段序列期间独占射频操作，与定时锁频互斥
+        let _radio_guard = crate::radio::lock_radio().await;
+        let mut sequence = vec![lte.lte_command(), nr.nr_command()];
+        if toggle_airplane {
+            sequence.insert(0, "AT+CFUN=0\r\n".to_string());
+            sequence.push("AT+CFUN=1\r\n".to_string());
+        }
+        for cmd in sequence {
+            let (success, error) = match retry::send_with_retry(cmd_tx, &cmd, false).await {
+                Ok(resp) => (resp.success, resp.error),
+                Err(e) => (false, Some(e.to_string())),
+            };
+            if !success {
+                warn!("Lock profile step {} failed: {:?}", cmd.trim(), error);
+            }
+            commands.push(json!({ "command": cmd.trim(), "success": success, "error": error }));
+            sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    let (lte_lock, nr_lock) = query_both(cmd_tx).await;
+    let verified = matches!(&lte_lock, Ok(lock) if lte.matches(lock)) && matches!(&nr_lock, Ok(lock) if nr.matches(lock));
+    if !verified {
+        warn!("Frequency lock readback does not match the requested lock");
+    }
+    json!({ "commands": commands, "readback": to_json(lte_lock, nr_lock), "verified": verified })
+}
+
+/// 应用指定名称的锁频配置
+pub async fn apply_profile(cmd_tx: &CommandSender, profile: &LockProfile, toggle_airplane: bool) -> serde_json::Value {
+    info!("Applying lock profile '{}'", profile.name);
+    let mut result = apply(cmd_tx, &profile.lte, &profile.nr, toggle_airplane).await;
+    // 回读不一致时模组里的锁定状态不确定，不再标记任何配置为生效
+    *ACTIVE_PROFILE.lock().unwrap() = (result["verified"] == json!(true)).then(|| profile.name.clone());
+    result["profile"] = json!(profile.name);
+    result
+}
+
+/// 解除 LTE 与 NR 的锁定
+pub async fn clear_profile(cmd_tx: &CommandSender, toggle_airplane: bool) -> serde_json::Value {
+    info!("Clearing frequency lock profile");
+    let result = apply(cmd_tx, &LockSpec::default(), &LockSpec::default(), toggle_airplane).await;
+    clear_active_profile();
+    result
+}
+
+async fn query_both(cmd_tx: &CommandSender) -> (Result<FreqLock>, Result<FreqLock>) {
+    (query_one(cmd_tx, "AT^LTEFREQLOCK?", parse_lte).await, query_one(cmd_tx, "AT^NRFREQLOCK?", parse_nr).await)
+}
+
+fn to_json(lte: Result<FreqLock>, nr: Result<FreqLock>) -> serde_json::Value {
+    let mut result = json!({});
+    for (key, lock) in [("lte", lte), ("nr", nr)] {
+        match lock {
+            Ok(lock) => result[key] = json!(lock),
+            Err(e) => {
+                result[key] = serde_json::Value::Null;
+                result[format!("{}Error", key)] = json!(e.to_string());
+            }
+        }
+    }
+    result
+}
+
+/// 查询 LTE 与 NR 的锁定状态，某一制式查询失败时对应字段为 null 并给出 error
+pub async fn query(cmd_tx: &CommandSender) -> serde_json::Value {
+    let (lte, nr) = query_both(cmd_tx).await;
+    to_json(lte, nr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lte_and_nr_locks() {
+        let lte = parse_lte("^LTEFREQLOCK: 2,0,1,\"3\",\"1850\",\"123\"\r\nOK").unwrap();
+        assert_eq!((lte.lock_type, lte.kind), (2, "cell"));
+        assert_eq!((lte.bands, lte.arfcns, lte.pcis), (vec!["3".to_string()], vec!["1850".to_string()], vec!["123".to_string()]));
+
+        let nr = parse_nr("^NRFREQLOCK: 3,0,2,\"41,78\"\r\nOK").unwrap();
+        assert_eq!(nr.kind, "band");
+        assert_eq!(nr.bands, vec!["41", "78"]);
+        assert!(nr.arfcns.is_empty() && nr.scs_types.is_empty());
+
+        let nr = parse_nr("^NRFREQLOCK: 1,0,1,\"78\",\"627264\",\"1\"").unwrap();
+        assert_eq!(nr.scs_types, vec!["1"]);
+
+        assert_eq!(parse_lte("^LTEFREQLOCK: 0").unwrap().kind, "none");
+        assert_eq!(parse_lte("OK"), None);
+    }
+
+    #[test]
+    fn parses_profiles_and_builds_commands() {
+        let profile = parse_profile("tower_a:lte=2/3/1850/256;nr=2/78/627264/1/100").unwrap();
+        assert_eq!(profile.lte.lte_command(), "AT^LTEFREQLOCK=2,0,1,\"3\",\"1850\",\"256\"\r\n");
+        assert_eq!(profile.nr.nr_command(), "AT^NRFREQLOCK=2,0,1,\"78\",\"627264\",\"1\",\"100\"\r\n");
+
+        // 省略的制式解锁，频段用逗号分隔
+        let profile = parse_profile("bands:lte=3/3,8").unwrap();
+        assert_eq!(profile.lte.lte_command(), "AT^LTEFREQLOCK=3,0,2,\"3,8\"\r\n");
+        assert_eq!(profile.nr.nr_command(), "AT^NRFREQLOCK=0\r\n");
+        assert!(profile.lte.matches(&parse_lte("^LTEFREQLOCK: 3,0,2,\"8,3\"").unwrap()));
+        assert!(profile.nr.matches(&parse_nr("^NRFREQLOCK: 0").unwrap()));
+        assert!(!profile.lte.matches(&parse_lte("^LTEFREQLOCK: 3,0,1,\"3\"").unwrap()));
+
+        assert!(parse_profile("no_name").is_err());
+        assert!(parse_profile("x:lte=3").is_err());
+        assert!(parse_profile("x:lte=5/3").is_err());
+        assert!(parse_profile("x:gsm=3/3").is_err());
+        let profiles = parse_profiles(&["a:lte=3/1".to_string(), "a:lte=3/3".to_string(), "bad".to_string()]);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].lte.bands, "1");
+    }
+}
@@ -1,7 +1,14 @@
+use crate::calls::CliValidity;
 use crate::models::CommandSender;
 use crate::notifications::{NotificationManager, NotificationType};
-use crate::pdu::{read_incoming_sms, IncomingMessage, SmsData};
+use crate::pdcp::{parse_pdcp_line, record_throughput};
+use crate::pdu::{read_incoming_sms, IncomingMessage, MmsNotification, PidKind, SmsData};
+use crate::sms::CmgrPayload;
+use crate::retry;
+use crate::signal::{self, ServingCell, SignalCommand};
+use crate::urc::{self, UrcStream};
 use anyhow::Result;
+use chrono::{Local, TimeZone};
 use async_trait::async_trait;
 use log::{debug, error, info, warn};
 use regex::Regex;
@@ -12,12 +19,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::oneshot;
 
 // Global regex instances
-static RE_CLIP: OnceLock<Regex> = OnceLock::new();
-static RE_CMTI: OnceLock<Regex> = OnceLock::new();
 static RE_CMGR: OnceLock<Regex> = OnceLock::new();
-static RE_PDCP: OnceLock<Regex> = OnceLock::new();
-static RE_MONSC_NR: OnceLock<Regex> = OnceLock::new();
-static RE_MONSC_LTE: OnceLock<Regex> = OnceLock::new();
 
 #[async_trait]
 pub trait MessageHandler: Send + Sync {
@@ -43,6 +45,7 @@ impl MessageHandler for CallHandler {
         _cmd_tx: &CommandSender,
     ) -> Result<()> {
         if line.contains("RING") {
+            crate::calls::record(None, "RING");
             notifications
                 .notify("System", "Incoming Call (Ring)", NotificationType::Call)
                 .await;
@@ -58,30 +61,165 @@ impl MessageHandler for CallHandler {
                 let _ = tx.send(msg);
             }
         } else if line.contains("+CLIP:") {
-            let re = RE_CLIP.get_or_init(|| Regex::new(r#"\+CLIP: "([^"]+)""#).unwrap());
-            if let Some(caps) = re.captures(line) {
-                if let Some(number) = caps.get(1) {
-                    notifications
-                        .notify(number.as_str(), "Incoming Call", NotificationType::Call)
-                        .await;
-                    
-                    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
-                        let msg = serde_json::json!({
-                            "type": "incoming_call",
-                            "data": {
-                                "number": number.as_str(),
-                                "status": "CLIP"
-                            }
-                        }).to_string();
-                        let _ = tx.send(msg);
-                    }
-                }
+            let Some(clip) = line.lines().find_map(crate::calls::parse_clip) else {
+                warn!("Unparsable +CLIP: {}", line);
+                return Ok(());
+            };
+            let number = clip.display_number();
+            crate::calls::record(Some(&number), "CLIP");
+            let content = match clip.validity {
+                CliValidity::Valid => "Incoming Call".to_string(),
+                CliValidity::Withheld => "Incoming Call (number withheld)".to_string(),
+                CliValidity::Unavailable => "Incoming Call (number unavailable)".to_string(),
+            };
+            let data = json!({
+                "number": number,
+                "status": "CLIP",
+                "numberType": clip.number_type,
+                "validity": clip.validity,
+            });
+            notifications
+                .notify_with_data(&number, &content, NotificationType::Call, Some(data.clone()))
+                .await;
+
+            if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+                let _ = tx.send(json!({ "type": "incoming_call", "data": data }).to_string());
             }
         }
         Ok(())
     }
 }
 
+/// SIM 卡拔出/插入/更换检测
+///
+/// `+CPIN: NOT INSERTED` 或 `^SIMST: 255` 视为拔出，`+CPIN: READY` 或 `^SIMST: 1` 视为在位，
+/// 在位时查询 ICCID 与上次记录比较，区分重新插入与换卡。首次得到的 ICCID 只作为基准。
+pub struct SimHandler {
+    state: Mutex<SimState>,
+}
+
+#[derive(Default)]
+struct SimState {
+    present: Option<bool>,
+    iccid: Option<String>,
+}
+
+impl SimHandler {
+    pub fn new() -> Self {
+        Self { state: Mutex::new(SimState::default()) }
+    }
+}
+
+/// 广播 `{"type":"sim"}` 并发送通知
+async fn report_sim_event(notifications: &NotificationManager, event: &str, iccid: Option<&str>, message: &str) {
+    warn!("{}", message);
+    let data = json!({ "event": event, "iccid": iccid });
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let _ = tx.send(json!({ "type": "sim", "data": data }).to_string());
+    }
+    notifications.notify_with_data("SIM", message, NotificationType::Sim, Some(data)).await;
+}
+
+#[async_trait]
+impl MessageHandler for SimHandler {
+    fn can_handle(&self, line: &str) -> bool {
+        line.starts_with("+CPIN:") || line.starts_with("^SIMST:")
+    }
+    async fn handle(
+        &self,
+        line: &str,
+        notifications: &NotificationManager,
+        cmd_tx: &CommandSender,
+    ) -> Result<()> {
+        let line = line.trim();
+        let present = if line.contains("NOT INSERTED") || line == "^SIMST: 255" || line.starts_with("^SIMST: 255,") {
+            false
+        } else if line == "+CPIN: READY" || line == "^SIMST: 1" || line.starts_with("^SIMST: 1,") {
+            true
+        } else {
+            // PIN 锁等中间状态不代表插拔
+            return Ok(());
+        };
+
+        if !present {
+            let was_present = self.state.lock().unwrap().present.replace(false);
+            if was_present != Some(false) {
+                let iccid = self.state.lock().unwrap().iccid.clone();
+                report_sim_event(notifications, "removed", iccid.as_deref(), "SIM 卡已被拔出").await;
+            }
+            return Ok(());
+        }
+
+        let was_present = self.state.lock().unwrap().present.replace(true);
+        if was_present == Some(true) {
+            return Ok(());
+        }
+        let iccid = crate::sim::query_iccid(cmd_tx).await;
+        let previous = {
+            let mut state = self.state.lock().unwrap();
+            match &iccid {
+                Some(iccid) => state.iccid.replace(iccid.clone()),
+                None => state.iccid.clone(),
+            }
+        };
+        match (&previous, &iccid) {
+            (Some(old), Some(new)) if old != new => {
+                let message = format!("SIM 卡已更换：ICCID {} -> {}", old, new);
+                report_sim_event(notifications, "changed", Some(new), &message).await;
+            }
+            _ if was_present == Some(false) => {
+                report_sim_event(notifications, "inserted", iccid.as_deref(), "SIM 卡已插入").await;
+            }
+            _ => info!("SIM present, ICCID {}", iccid.as_deref().unwrap_or("unknown")),
+        }
+        Ok(())
+    }
+}
+
+/// IMS（VoLTE）注册状态上报：`+CIREGU:` / `^IMSSTATE:`
+///
+/// 状态变化时广播 `{"type":"ims"}`，注册状态翻转时按 `notify_ims` 发送通知。
+pub struct ImsHandler {
+    last: Mutex<Option<crate::ims::ImsStatus>>,
+}
+
+impl ImsHandler {
+    pub fn new() -> Self {
+        Self { last: Mutex::new(None) }
+    }
+}
+
+#[async_trait]
+impl MessageHandler for ImsHandler {
+    fn can_handle(&self, line: &str) -> bool {
+        line.starts_with("+CIREGU:") || line.starts_with("^IMSSTATE:")
+    }
+    async fn handle(
+        &self,
+        line: &str,
+        notifications: &NotificationManager,
+        _cmd_tx: &CommandSender,
+    ) -> Result<()> {
+        let Some(status) = crate::ims::parse(line) else {
+            return Ok(());
+        };
+        let previous = self.last.lock().unwrap().replace(status.clone());
+        if previous.as_ref() == Some(&status) {
+            return Ok(());
+        }
+        info!("IMS registration: {:?}", status);
+        let data = serde_json::to_value(&status)?;
+        if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+            let _ = tx.send(json!({ "type": "ims", "data": data }).to_string());
+        }
+        if previous.map(|p| p.registered) != Some(status.registered) {
+            let message = if status.registered { "IMS (VoLTE) 已注册" } else { "IMS (VoLTE) 注册已断开" };
+            notifications.notify_with_data("IMS", message, NotificationType::Ims, Some(data)).await;
+        }
+        Ok(())
+    }
+}
+
 pub struct MemoryFullHandler;
 #[async_trait]
 impl MessageHandler for MemoryFullHandler {
@@ -113,6 +251,9 @@ fn get_partial_cache() -> PartialSmsCache {
         .clone()
 }
 
+/// 保留短信时存储使用率达到此百分比即记录警告
+const RETENTION_WARN_PERCENT: u8 = 80;
+
 pub struct NewSMSHandler {
     delete_after_forward: bool,
     delete_mms_notification: bool,
@@ -136,188 +277,219 @@ impl MessageHandler for NewSMSHandler {
         cmd_tx: &CommandSender,
     ) -> Result<()> {
         // +CMTI: "SM", 5
-        let re = RE_CMTI.get_or_init(|| Regex::new(r#"\+CMTI: "(\w+)",\s*(\d+)"#).unwrap());
-        if let Some(caps) = re.captures(line) {
-            let index = caps.get(2).map_or("0", |m| m.as_str());
-            info!("New SMS at index {}", index);
-
-            let cmd = format!("AT+CMGR={}", index);
-            let (tx, rx) = oneshot::channel();
-            if let Err(_) = cmd_tx.send((cmd, tx)).await {
-                error!("Failed to send CMGR command");
-                return Ok(());
+        if let Some((mem, index)) = line.lines().find_map(crate::sms::parse_cmti) {
+            info!("New SMS at {} index {}", mem, index);
+
+            if let Err(e) = crate::sms::ensure_pdu_mode(cmd_tx).await {
+                warn!("Failed to verify SMS PDU mode: {}", e);
             }
+            // 存储区没切过去时仍尝试读取，但不删除，以免删掉另一个存储区同索引的短信
+            let (storage_selected, previous_storage) = match crate::sms::select_read_storage(cmd_tx, &mem).await {
+                Ok(previous) => (true, previous),
+                Err(e) => {
+                    warn!("Failed to select SMS storage {}: {}", mem, e);
+                    (false, None)
+                }
+            };
+
+            let (message, pdu_hex) = match Self::read_cmgr(cmd_tx, index).await {
+                CmgrPayload::Pdu(pdu) => (read_incoming_sms(&pdu), pdu),
+                CmgrPayload::Text(sms) => (Ok(IncomingMessage::Sms(sms)), String::new()),
+                CmgrPayload::Empty => {
+                    warn!("No PDU found in CMGR response");
+                    crate::sms::restore_read_storage(cmd_tx, previous_storage).await;
+                    return Ok(());
+                }
+            };
+            match message {
+                Ok(IncomingMessage::Sms(sms_data)) => {
+                    // Process SMS (notify & websocket broadcast)
+                    let forwarded = process_sms(sms_data, notifications).await;
+
+                    // Only delete if enabled in config AND it was actually forwarded to a 3rd party service
+                    let delete = self.delete_after_forward && forwarded && storage_selected;
 
-            match rx.await {
-                Ok(response) => {
-                    if response.success {
-                        if let Some(data) = response.data {
-                            // Try to parse PDU from response
-                            // Response might be:
-                            // +CMGR: 0,,28\r\n0891683108501305F0040D916831...
-                            // We need to find the PDU (hex string)
-                            
-                            // Find the last long hex string which is likely the PDU
-                            // Or split by newline and find the line that looks like PDU
-                            let lines: Vec<&str> = data.lines().collect();
-                            let mut pdu_hex = "";
-                            for line in lines.iter().rev() {
-                                let clean_line = line.trim();
-                                if clean_line.len() > 10 && clean_line.chars().all(|c| c.is_ascii_hexdigit()) {
-                                    pdu_hex = clean_line;
-                                    break;
-                                }
-                            }
-
-                            if !pdu_hex.is_empty() {
-                                match read_incoming_sms(pdu_hex) {
-                                    Ok(IncomingMessage::Sms(sms_data)) => {
-                                        // Process SMS (notify & websocket broadcast)
-                                        let forwarded = self.process_sms(sms_data, notifications).await;
-
-                                        // 每次新短信到达时检查存储使用率
-                                        Self::check_sms_storage(notifications, cmd_tx).await;
-                                        
-                                        // Only delete if enabled in config AND it was actually forwarded to a 3rd party service
-                                        if self.delete_after_forward && forwarded {
-                                            info!("Deleting SMS at index {} (forwarded & configured to auto-delete)", index);
-                                            let del_cmd = format!("AT+CMGD={}", index);
-                                            let (del_tx, del_rx) = oneshot::channel();
-                                            let _ = cmd_tx.send((del_cmd, del_tx)).await;
-                                            let _ = del_rx.await;
-                                        } else {
-                                            info!("Keeping SMS at index {} (auto-delete disabled or not forwarded)", index);
-                                        }
-                                    }
-                                    Ok(IncomingMessage::MmsNotification(mms)) => {
-                                        warn!("Detected MMS notification at index {} from {}", index, mms.sender);
-
-                                        if let Some(tx) = crate::server::WS_BROADCASTER.get() {
-                                            let msg = serde_json::json!({
-                                                "type": "new_mms_notification",
-                                                "data": {
-                                                    "sender": mms.sender,
-                                                    "contentLocation": mms.content_location,
-                                                    "transactionId": mms.transaction_id,
-                                                    "contentType": mms.content_type,
-                                                    "time": mms.date,
-                                                }
-                                            }).to_string();
-                                            let _ = tx.send(msg);
-                                        }
-
-                                        Self::check_sms_storage(notifications, cmd_tx).await;
-
-                                        if self.delete_mms_notification {
-                                            info!("Deleting MMS notification at index {}", index);
-                                            let del_cmd = format!("AT+CMGD={}", index);
-                                            let (del_tx, del_rx) = oneshot::channel();
-                                            let _ = cmd_tx.send((del_cmd, del_tx)).await;
-                                            let _ = del_rx.await;
-                                        } else {
-                                            info!("Keeping MMS notification at index {} (auto-delete disabled)", index);
-                                        }
-                                    }
-                                    Err(e) => {
-                                        error!("Failed to decode PDU: {}", e);
-                                        // Fallback raw notification
-                                        notifications
-                                            .notify("Unknown", &format!("Raw PDU: {}", pdu_hex), NotificationType::SMS)
-                                            .await;
-                                    }
-                                }
-                            } else {
-                                warn!("No PDU found in CMGR response");
-                            }
-                        }
+                    // 每次新短信到达时检查存储使用率
+                    Self::check_sms_storage(notifications, cmd_tx, !delete).await;
+                    
+                    if delete && Self::reselect_storage(cmd_tx, &mem).await {
+                        info!("Deleting SMS at index {} (forwarded & configured to auto-delete)", index);
+                        let del_cmd = format!("AT+CMGD={}", index);
+                        let (del_tx, del_rx) = oneshot::channel();
+                        let _ = cmd_tx.send((del_cmd.into(), del_tx)).await;
+                        let _ = del_rx.await;
+                    } else {
+                        info!("Keeping SMS at index {} (auto-delete disabled or not forwarded)", index);
+                    }
+                }
+                Ok(IncomingMessage::MmsNotification(mms)) => {
+                    warn!("Detected MMS notification at index {} from {}", index, mms.sender);
+                    broadcast_mms_notification(&mms);
+
+                    let delete = self.delete_mms_notification && storage_selected;
+                    Self::check_sms_storage(notifications, cmd_tx, !delete).await;
+
+                    if delete && Self::reselect_storage(cmd_tx, &mem).await {
+                        info!("Deleting MMS notification at index {}", index);
+                        let del_cmd = format!("AT+CMGD={}", index);
+                        let (del_tx, del_rx) = oneshot::channel();
+                        let _ = cmd_tx.send((del_cmd.into(), del_tx)).await;
+                        let _ = del_rx.await;
+                    } else {
+                        info!("Keeping MMS notification at index {} (auto-delete disabled)", index);
                     }
                 }
-                Err(e) => error!("Failed to receive CMGR response: {}", e),
+                Err(e) => {
+                    error!("Failed to decode PDU: {}", e);
+                    // Fallback raw notification
+                    notifications
+                        .notify("Unknown", &format!("Raw PDU: {}", pdu_hex), NotificationType::SMS)
+                        .await;
+                }
             }
+            crate::sms::restore_read_storage(cmd_tx, previous_storage).await;
         }
         Ok(())
     }
 }
 
-impl NewSMSHandler {
-    /// Returns true if the SMS was successfully forwarded to a third-party notification service
-    async fn process_sms(&self, sms: SmsData, notifications: &NotificationManager) -> bool {
-        let mut forwarded_to_third_party = false;
-
-        if let Some(partial) = sms.partial_info {
-            // Handle partial SMS
-            let cache = get_partial_cache();
-            let key = format!("{}_{}", sms.sender, partial.reference);
-            let mut full_content = None;
-            let current_time = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
+fn broadcast_mms_notification(mms: &MmsNotification) {
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = serde_json::json!({
+            "type": "new_mms_notification",
+            "data": {
+                "sender": mms.sender,
+                "contentLocation": mms.content_location,
+                "transactionId": mms.transaction_id,
+                "contentType": mms.content_type,
+                "time": crate::sms::format_timestamp(&mms.date),
+            }
+        }).to_string();
+        let _ = tx.send(msg);
+    }
+}
 
-            {
-                let mut map = cache.lock().unwrap();
-                
-                // Cleanup old entries (older than 1 hour)
-                map.retain(|_, (_, _, ts)| current_time - *ts < 3600);
-
-                let entry = map.entry(key.clone()).or_insert((partial.parts_count, HashMap::new(), current_time));
-                entry.1.insert(partial.part_number, sms.content.clone());
-
-                if entry.1.len() == entry.0 as usize {
-                    // All parts received
-                    let mut content = String::new();
-                    for i in 1..=entry.0 {
-                        if let Some(part) = entry.1.get(&i) {
-                            content.push_str(part);
-                        }
-                    }
-                    full_content = Some(content);
+/// 通知、广播并拼装长短信；+CMTI 读取与 +CMT 直投共用。长短信按 `multipart_mode` 决定逐段通知还是收齐后通知
+/// Returns true if the SMS was successfully forwarded to a third-party notification service
+async fn process_sms(sms: SmsData, notifications: &NotificationManager) -> bool {
+    let mut forwarded_to_third_party = false;
+
+    // Type 0 与发给终端/SIM 卡的数据下载短信不是给用户看的，只记日志和广播，不通知也不记入短信记录
+    let pid_kind = sms.pid_kind();
+    if matches!(pid_kind, PidKind::TypeZero | PidKind::DataDownload) {
+        info!("Suppressing special SMS from {} (TP-PID {:#04X})", sms.sender, sms.pid);
+        if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+            let msg = json!({
+                "type": "sms_suppressed",
+                "data": {
+                    "sender": sms.sender,
+                    "pid": sms.pid,
+                    "time": crate::sms::format_timestamp(&sms.date),
+                    "timeOriginal": crate::sms::original_timestamp(&sms.date),
+                    "timeUtc": crate::sms::utc_timestamp(&sms.date),
                 }
+            });
+            let _ = tx.send(msg.to_string());
+        }
+        return false;
+    }
+
+    if let Some(partial) = sms.partial_info {
+        let mode = crate::sms::multipart_mode();
+        if mode.notify_segments() {
+            let content = format!("({}/{}) {}", partial.part_number, partial.parts_count, sms.content);
+            let content = crate::sms::notification_text(&content, &sms.date);
+            // 冷却期内被压下的通知不算转发，避免短信没发出去就被删除
+            if notifications.notify(&sms.sender, &content, NotificationType::SMS).await && notifications.has_active_push_services() {
+                forwarded_to_third_party = true;
             }
+            if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+                let msg = serde_json::json!({
+                    "type": "new_sms",
+                    "data": {
+                        "sender": sms.sender,
+                        "content": sms.content,
+                        "time": crate::sms::format_timestamp(&sms.date),
+                        "timeOriginal": crate::sms::original_timestamp(&sms.date),
+                        "timeUtc": crate::sms::utc_timestamp(&sms.date),
+                        "isComplete": false,
+                        "partNumber": partial.part_number,
+                        "partsCount": partial.parts_count
+                    }
+                }).to_string();
+                let _ = tx.send(msg);
+            }
+        }
 
-            if let Some(content) = full_content {
-                {
-                    let mut map = cache.lock().unwrap();
-                    map.remove(&key);
-                }
-                info!("Combined partial SMS from {}", sms.sender);
-                
-                // 核心逻辑：调用 notify 并检查返回值（虽然目前 notify 总是返回 void，我们需要修改 NotificationManager 以返回状态）
-                // 暂时假设 NotificationManager::notify 总是成功触发配置的服务。
-                // 实际上我们需要知道是否 *开启了* 任何推送服务。
-                // 如果用户没有配置任何推送服务（如微信、钉钉等），那么我们不应该删除短信。
-                // 但是 notify 方法内部处理了所有逻辑。
-                // 为了简单起见，我们认为只要调用了 notify 就算 "尝试转发"。
-                // 如果要更精确，需要修改 NotificationManager::notify 返回是否有实际推送。
-                // 这里我们先调用，然后假设如果配置了服务就会推送。
-                
-                notifications.notify(&sms.sender, &content, NotificationType::SMS).await;
-                
-                // 检查是否配置了任何推送服务
-                if notifications.has_active_push_services() {
-                    forwarded_to_third_party = true;
+        // Handle partial SMS
+        let cache = get_partial_cache();
+        let key = format!("{}_{}", sms.sender, partial.reference);
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let (max_age, max_entries) = crate::sms::partial_limits();
+        let mut expired = Vec::new();
+        let mut evicted = Vec::new();
+        let full_content = {
+            let mut map = cache.lock().unwrap();
+            
+            // Cleanup old entries (older than partial_sms_max_age)，未收齐的分段记入短信记录
+            map.retain(|key, (count, parts, ts)| {
+                if current_time.saturating_sub(*ts) < max_age {
+                    return true;
                 }
-                
-                if let Some(tx) = crate::server::WS_BROADCASTER.get() {
-                    let msg = serde_json::json!({
-                        "type": "new_sms",
-                        "data": {
-                            "sender": sms.sender,
-                            "content": content,
-                            "time": sms.date,
-                            "isComplete": true
-                        }
-                    }).to_string();
-                    let _ = tx.send(msg);
+                expired.push((key.clone(), *count, std::mem::take(parts), *ts));
+                false
+            });
+
+            let entry = map.entry(key.clone()).or_insert((partial.parts_count, HashMap::new(), current_time));
+            entry.1.insert(partial.part_number, sms.content.clone());
+            // All parts received
+            let full_content = crate::sms::join_parts(entry.0, &entry.1);
+
+            // 条数超限时丢弃最早的，防止大量只发一段的长短信把缓存撑大
+            for old in crate::sms::partial_evictions(map.iter().map(|(k, v)| (k, v.2)), &key, max_entries) {
+                if let Some((count, parts, ts)) = map.remove(&old) {
+                    evicted.push((old, count, parts, ts));
                 }
-            } else {
-                info!("Received part {}/{} from {}", partial.part_number, partial.parts_count, sms.sender);
             }
-        } else {
-            // Normal SMS
-            notifications.notify(&sms.sender, &sms.content, NotificationType::SMS).await;
+            full_content
+        };
+
+        for (reason, (key, count, parts, ts)) in expired
+            .into_iter()
+            .map(|e| ("timed out", e))
+            .chain(evicted.into_iter().map(|e| ("evicted, partial_sms_max_entries reached", e)))
+        {
+            let sender = key.rsplit_once('_').map_or(key.as_str(), |(sender, _)| sender);
+            let time = Local.timestamp_opt(ts as i64, 0).single().unwrap_or_else(Local::now);
+            warn!("Giving up on partial SMS from {} ({}/{} parts received, {})", sender, parts.len(), count, reason);
+            crate::sms_history::record(sender, &crate::sms::join_available_parts(count, &parts), time, false);
+        }
+
+        if let Some(content) = full_content {
+            {
+                let mut map = cache.lock().unwrap();
+                map.remove(&key);
+            }
+            info!("Combined partial SMS from {}", sms.sender);
+            crate::sms_history::record(&sms.sender, &content, sms.date.with_timezone(&Local), true);
+            if !mode.notify_complete() {
+                return forwarded_to_third_party;
+            }
             
-            if notifications.has_active_push_services() {
+            // 核心逻辑：调用 notify 并检查返回值（虽然目前 notify 总是返回 void，我们需要修改 NotificationManager 以返回状态）
+            // 暂时假设 NotificationManager::notify 总是成功触发配置的服务。
+            // 实际上我们需要知道是否 *开启了* 任何推送服务。
+            // 如果用户没有配置任何推送服务（如微信、钉钉等），那么我们不应该删除短信。
+            // 但是 notify 方法内部处理了所有逻辑。
+            // notify 返回 false 表示未开启短信通知或处于冷却期，此时不算转发
+            let notified = notifications.notify(&sms.sender, &crate::sms::notification_text(&content, &sms.date), NotificationType::SMS).await;
+            
+            // 检查是否配置了任何推送服务
+            if notified && notifications.has_active_push_services() {
                 forwarded_to_third_party = true;
             }
             
@@ -326,30 +498,112 @@ impl NewSMSHandler {
                     "type": "new_sms",
                     "data": {
                         "sender": sms.sender,
-                        "content": sms.content,
-                        "time": sms.date,
+                        "content": content,
+                        "time": crate::sms::format_timestamp(&sms.date),
+                        "timeOriginal": crate::sms::original_timestamp(&sms.date),
+                        "timeUtc": crate::sms::utc_timestamp(&sms.date),
                         "isComplete": true
                     }
                 }).to_string();
                 let _ = tx.send(msg);
             }
+        } else {
+            info!("Received part {}/{} from {}", partial.part_number, partial.parts_count, sms.sender);
+        }
+    } else {
+        // Normal SMS
+        let replace_type = match pid_kind {
+            PidKind::Replace(kind) => Some(kind),
+            _ => None,
+        };
+        match replace_type {
+            Some(kind) => crate::sms_history::record_replacing(&sms.sender, &sms.content, sms.date.with_timezone(&Local), kind),
+            None => crate::sms_history::record(&sms.sender, &sms.content, sms.date.with_timezone(&Local), true),
+        }
+        let notified = notifications.notify(&sms.sender, &crate::sms::notification_text(&sms.content, &sms.date), NotificationType::SMS).await;
+        
+        if notified && notifications.has_active_push_services() {
+            forwarded_to_third_party = true;
         }
         
-        forwarded_to_third_party
+        if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+            let msg = serde_json::json!({
+                "type": "new_sms",
+                "data": {
+                    "sender": sms.sender,
+                    "content": sms.content,
+                    "time": crate::sms::format_timestamp(&sms.date),
+                    "timeOriginal": crate::sms::original_timestamp(&sms.date),
+                    "timeUtc": crate::sms::utc_timestamp(&sms.date),
+                    "isComplete": true,
+                    "replaceType": replace_type
+                }
+            }).to_string();
+            let _ = tx.send(msg);
+        }
+    }
+    
+    forwarded_to_third_party
+}
+
+impl NewSMSHandler {
+    /// 读取新短信。模组重连等原因回到文本模式时，先切回 PDU 模式重读一次（PDU 才有长短信分段等信息），
+    /// 仍是文本时按文本模式解析，不再退化为原始内容通知
+    async fn read_cmgr(cmd_tx: &CommandSender, index: u32) -> CmgrPayload {
+        let cmd = format!("AT+CMGR={}", index);
+        // Response might be:
+        // +CMGR: 0,,28\r\n0891683108501305F0040D916831...
+        let read = || async {
+            match retry::send_with_retry(cmd_tx, &cmd, false).await {
+                Ok(resp) if resp.success => crate::sms::parse_cmgr(resp.data.as_deref().unwrap_or("")),
+                Ok(resp) => {
+                    error!("{} failed: {:?}", cmd, resp.error);
+                    CmgrPayload::Empty
+                }
+                Err(e) => {
+                    error!("Failed to receive CMGR response: {}", e);
+                    CmgrPayload::Empty
+                }
+            }
+        };
+
+        let payload = read().await;
+        if !matches!(payload, CmgrPayload::Text(_)) {
+            return payload;
+        }
+        warn!("AT+CMGR returned a text-mode SMS at index {}, switching back to PDU mode and re-reading", index);
+        if let Err(e) = crate::sms::set_sms_mode(cmd_tx, 0).await {
+            warn!("Failed to switch to PDU mode: {}", e);
+            return payload;
+        }
+        match read().await {
+            retry @ CmgrPayload::Pdu(_) => retry,
+            _ => payload,
+        }
+    }
+
+    /// 删除前重新选定存储区：读取与删除之间其它指令（如 LIST_SMS:<mem>）可能已切换过
+    async fn reselect_storage(cmd_tx: &CommandSender, mem: &str) -> bool {
+        match crate::sms::select_read_storage(cmd_tx, mem).await {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Not deleting SMS: failed to select storage {}: {}", mem, e);
+                false
+            }
+        }
     }
 
     /// 查询短信存储使用率，超过阈值时发送通知
-    async fn check_sms_storage(notifications: &NotificationManager, cmd_tx: &CommandSender) {
+    ///
+    /// `retaining` 表示本条短信会留在存储中（未开启转发后删除）。此时即使关闭了存储通知，
+    /// 使用率超过 `RETENTION_WARN_PERCENT` 也会记一条警告：存储写满后模组不再上报 +CMTI。
+    async fn check_sms_storage(notifications: &NotificationManager, cmd_tx: &CommandSender, retaining: bool) {
         let threshold = notifications.memory_full_threshold();
-        if threshold == 0 {
+        if threshold == 0 && !retaining {
             return; // 禁用
         }
 
-        let (tx, rx) = oneshot::channel();
-        if cmd_tx.send(("AT+CPMS?".to_string(), tx)).await.is_err() {
-            return;
-        }
-        let resp = match rx.await {
+        let resp = match retry::send_with_retry(cmd_tx, "AT+CPMS?", false).await {
             Ok(r) if r.success => r,
             _ => return,
         };
@@ -360,14 +614,17 @@ impl NewSMSHandler {
 
         // +CPMS: "SM",8,10,"SM",8,10,"SM",8,10
         // 取第一组 used/total
-        let re = regex::Regex::new(r#"\+CPMS:\s*"\w+",(\d+),(\d+)"#).unwrap();
-        if let Some(caps) = re.captures(&data) {
-            let used: u32 = caps.get(1).and_then(|m| m.as_str().parse().ok()).unwrap_or(0);
-            let total: u32 = caps.get(2).and_then(|m| m.as_str().parse().ok()).unwrap_or(1);
+        if let Some(first) = crate::sms::parse_cpms(&data).into_iter().next() {
+            let (used, total, pct) = (first.used, first.total, first.percent);
             if total == 0 { return; }
-            let pct = (used * 100 / total) as u8;
             info!("SMS storage: {}/{} ({}%)", used, total, pct);
-            if pct >= threshold {
+            if retaining && pct >= RETENTION_WARN_PERCENT {
+                warn!(
+                    "SMS storage {}/{} ({}%) is filling up while messages are retained (sms_delete_after_forward=0); new SMS will be rejected once full",
+                    used, total, pct
+                );
+            }
+            if threshold > 0 && pct >= threshold {
                 let msg = format!("短信存储已使用 {}/{} ({}%)，超过阈值 {}%，请及时清理", used, total, pct, threshold);
                 notifications.notify("短信存储", &msg, crate::notifications::NotificationType::MemoryFull).await;
             }
@@ -375,6 +632,120 @@ impl NewSMSHandler {
     }
 }
 
+/// 处理 `+CDS` 短信状态报告（CNMI ds=1），PDU 在下一行，与 +CMT 一样由 client 拼成一条
+pub struct StatusReportHandler;
+
+#[async_trait]
+impl MessageHandler for StatusReportHandler {
+    fn can_handle(&self, line: &str) -> bool {
+        line.starts_with("+CDS:")
+    }
+    async fn handle(
+        &self,
+        line: &str,
+        _notifications: &NotificationManager,
+        _cmd_tx: &CommandSender,
+    ) -> Result<()> {
+        let Some((_, pdu_hex)) = line.split_once('\n') else {
+            warn!("+CDS without PDU line: {}", line);
+            return Ok(());
+        };
+        match crate::pdu::read_status_report(pdu_hex.trim()) {
+            Ok(report) => crate::sms_report::on_status_report(&report),
+            Err(e) => warn!("Failed to decode status report: {}", e),
+        }
+        Ok(())
+    }
+}
+
+/// 处理 CNMI 模式 2 下的 `+CMT` 直投短信
+///
+/// 短信不落存储，PDU 紧跟在头部下一行；client 会把两行拼成 `+CMT: ,<len>\n<pdu>` 一起分发。
+/// 解码后与 +CMTI 走同一条 `process_sms` 通知/长短信拼装流程。
+/// 当 `AT+CSMS?` 报告 service=1（Phase 2+）时，模组要求收到后用 `AT+CNMA` 确认，否则会重发。
+pub struct DirectSmsHandler {
+    /// 缓存的 CSMS service 是否为 1，None 表示尚未查询
+    needs_ack: Mutex<Option<bool>>,
+}
+
+impl DirectSmsHandler {
+    pub fn new() -> Self {
+        Self { needs_ack: Mutex::new(None) }
+    }
+
+    async fn needs_ack(&self, cmd_tx: &CommandSender) -> bool {
+        if let Some(cached) = *self.needs_ack.lock().unwrap() {
+            return cached;
+        }
+        // +CSMS: 1,1,1,1
+        let resp = match retry::send_with_retry(cmd_tx, "AT+CSMS?", false).await {
+            Ok(r) if r.success => r,
+            _ => return false,
+        };
+        let service = resp.data.as_deref().and_then(|d| {
+            d.lines()
+                .find_map(|l| l.trim().strip_prefix("+CSMS:"))
+                .and_then(|rest| rest.split(',').next())
+                .map(|s| s.trim() == "1")
+        });
+        match service {
+            Some(ack) => {
+                *self.needs_ack.lock().unwrap() = Some(ack);
+                ack
+            }
+            None => false,
+        }
+    }
+}
+
+#[async_trait]
+impl MessageHandler for DirectSmsHandler {
+    fn can_handle(&self, line: &str) -> bool {
+        line.starts_with("+CMT:")
+    }
+    async fn handle(
+        &self,
+        line: &str,
+        notifications: &NotificationManager,
+        cmd_tx: &CommandSender,
+    ) -> Result<()> {
+        let pdu_hex = match line.split_once('\n') {
+            Some((_, pdu)) => pdu.trim(),
+            None => {
+                warn!("+CMT without PDU line: {}", line);
+                return Ok(());
+            }
+        };
+        info!("New SMS delivered directly (+CMT)");
+
+        match read_incoming_sms(pdu_hex) {
+            Ok(IncomingMessage::Sms(sms_data)) => {
+                process_sms(sms_data, notifications).await;
+            }
+            Ok(IncomingMessage::MmsNotification(mms)) => {
+                warn!("Detected MMS notification (+CMT) from {}", mms.sender);
+                broadcast_mms_notification(&mms);
+            }
+            Err(e) => {
+                error!("Failed to decode +CMT PDU: {}", e);
+                notifications
+                    .notify("Unknown", &format!("Raw PDU: {}", pdu_hex), NotificationType::SMS)
+                    .await;
+            }
+        }
+
+        if self.needs_ack(cmd_tx).await {
+            let (ack_tx, ack_rx) = oneshot::channel();
+            let _ = cmd_tx.send(("AT+CNMA".into(), ack_tx)).await;
+            match ack_rx.await {
+                Ok(resp) if !resp.success => warn!("AT+CNMA failed: {:?}", resp.error),
+                _ => debug!("+CMT acknowledged with AT+CNMA"),
+            }
+        }
+        Ok(())
+    }
+}
+
 /// 处理 ^NDISSTAT URC，实时感知 NDIS 拨号连接状态变化
 /// 参考 MT5700M-CN AT命令手册 16.2 ^NDISSTAT
 /// 格式: ^NDISSTAT: [<cid>,]<stat>,[<err>],[<wx_state>],<PDP_type>
@@ -447,38 +818,26 @@ impl MessageHandler for PDCPDataHandler {
         _notifications: &NotificationManager,
         _cmd_tx: &CommandSender,
     ) -> Result<()> {
-        // ^PDCPDATAINFO: 1,1,100,20,5,30,10,5,100,50,1024,2048,0,0
-        let re = RE_PDCP.get_or_init(|| Regex::new(r"\^PDCPDATAINFO:(.*)").unwrap());
-        if let Some(caps) = re.captures(line) {
-            if let Some(data_str) = caps.get(1) {
-                let parts: Vec<&str> = data_str.as_str().split(',').map(|s| s.trim()).collect();
-                if parts.len() >= 14 {
-                    let data = json!({
-                        "type": "pdcp_data",
-                        "data": {
-                            "id": parts[0].parse::<i32>().unwrap_or(0),
-                            "pduSessionId": parts[1].parse::<i32>().unwrap_or(0),
-                            "discardTimerLen": parts[2].parse::<i32>().unwrap_or(0),
-                            "avgDelay": parts[3].parse::<f64>().unwrap_or(0.0) / 10.0,
-                            "minDelay": parts[4].parse::<f64>().unwrap_or(0.0) / 10.0,
-                            "maxDelay": parts[5].parse::<f64>().unwrap_or(0.0) / 10.0,
-                            "highPriQueMaxBuffTime": parts[6].parse::<f64>().unwrap_or(0.0) / 10.0,
-                            "lowPriQueMaxBuffTime": parts[7].parse::<f64>().unwrap_or(0.0) / 10.0,
-                            "highPriQueBuffPktNums": parts[8].parse::<i32>().unwrap_or(0),
-                            "lowPriQueBuffPktNums": parts[9].parse::<i32>().unwrap_or(0),
-                            "ulPdcpRate": parts[10].parse::<i64>().unwrap_or(0),
-                            "dlPdcpRate": parts[11].parse::<i64>().unwrap_or(0),
-                            "ulDiscardCnt": parts[12].parse::<i32>().unwrap_or(0),
-                            "dlDiscardCnt": parts[13].parse::<i32>().unwrap_or(0),
-                        }
-                    });
-                    
-                    // Broadcast via WebSocket
-                    debug!("PDCP Data: {}", data);
-                    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
-                        let _ = tx.send(data.to_string());
+        if !urc::is_enabled(UrcStream::Pdcp) {
+            return Ok(());
+        }
+        if let Some(info) = parse_pdcp_line(line) {
+            let data = json!({ "type": "pdcp_data", "data": info });
+
+            let sample = record_throughput(&info);
+
+            // Broadcast via WebSocket
+            debug!("PDCP Data: {}", data);
+            if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+                let _ = tx.send(data.to_string());
+                let throughput = json!({
+                    "type": "throughput",
+                    "data": {
+                        "ul_mbps": sample.ul_mbps,
+                        "dl_mbps": sample.dl_mbps,
                     }
-                }
+                });
+                let _ = tx.send(throughput.to_string());
             }
         }
         Ok(())
@@ -487,6 +846,10 @@ impl MessageHandler for PDCPDataHandler {
 
 pub struct NetworkSignalHandler {
     state: Mutex<SignalState>,
+    /// 配置 `signal_query_cmd` 指定的指令，None 为按型号自动选择
+    configured: Option<SignalCommand>,
+    /// 已确认模组支持的指令
+    command: Mutex<Option<SignalCommand>>,
 }
 
 struct SignalState {
@@ -495,13 +858,55 @@ struct SignalState {
 }
 
 impl NetworkSignalHandler {
-    pub fn new() -> Self {
+    pub fn new(signal_query_cmd: &str) -> Self {
         Self {
             state: Mutex::new(SignalState {
                 last_rsrp: None,
                 last_sys_mode: None,
             }),
+            configured: SignalCommand::from_config(signal_query_cmd),
+            command: Mutex::new(None),
+        }
+    }
+
+    /// 查询服务小区详情。配置指定指令时只用该指令；自动模式先按型号选择，
+    /// 模组不支持（返回错误）时依次尝试其他指令，第一个成功的指令之后固定使用
+    async fn query_serving_cell(&self, cmd_tx: &CommandSender) -> Option<ServingCell> {
+        let known = *self.command.lock().unwrap();
+        let candidates: Vec<SignalCommand> = match known.or(self.configured) {
+            Some(command) => vec![command],
+            None => {
+                let preferred = match crate::modem::model(cmd_tx).await {
+                    Ok(model) => SignalCommand::for_model(&model),
+                    Err(e) => {
+                        warn!("Failed to detect modem model, defaulting to AT^MONSC: {}", e);
+                        SignalCommand::Monsc
+                    }
+                };
+                std::iter::once(preferred)
+                    .chain(SignalCommand::ALL.into_iter().filter(|&c| c != preferred))
+                    .collect()
+            }
+        };
+
+        for command in candidates {
+            match retry::send_with_retry(cmd_tx, command.at(), false).await {
+                Ok(response) if response.success => {
+                    if known.is_none() {
+                        info!("Using {} for serving cell queries", command.at());
+                        *self.command.lock().unwrap() = Some(command);
+                    }
+                    return response.data.as_deref().and_then(|data| command.parse(data));
+                }
+                Ok(response) => debug!(
+                    "{} is not usable: {}",
+                    command.at(),
+                    response.error.unwrap_or_default()
+                ),
+                Err(e) => debug!("{} failed: {}", command.at(), e),
+            }
         }
+        None
     }
 }
 
@@ -538,14 +943,18 @@ impl MessageHandler for NetworkSignalHandler {
             let parts: Vec<&str> = replaced.split(',').map(|s| s.trim()).collect();
             if parts.len() >= 4 {
                 let mode = parts[0].trim_matches('"').to_string();
-                if let Ok(rsrp_raw) = parts[1].parse::<i32>() {
-                    // Python: rsrp = -140 + rsrp_raw
-                    current_rsrp = Some(-140 + rsrp_raw);
+                if let Some(rsrp) = parts[1].parse::<i32>().ok().and_then(signal::hcsq_rsrp_dbm) {
+                    current_rsrp = Some(rsrp);
                     current_sys_mode = Some(mode);
                 }
             }
         }
 
+        if let Some(rsrp) = current_rsrp {
+            crate::metrics::record_signal(Some(rsrp as f64), None, None);
+            crate::status::record_signal(current_sys_mode.as_deref().and_then(signal::Rat::from_mode), rsrp, None);
+        }
+
         let mut should_notify = false;
         {
             let mut state = self.state.lock().unwrap();
@@ -571,59 +980,60 @@ impl MessageHandler for NetworkSignalHandler {
         }
 
         if should_notify {
-            // Query detailed info
-            let cmd = "AT^MONSC".to_string();
-            let (tx, rx) = oneshot::channel();
-            if let Err(_) = cmd_tx.send((cmd, tx)).await {
-                return Ok(());
+            // ^CERSSI 不区分 LTE/NR，两者 RSRP 分档相同，按 LTE 计算格数
+            let rat = current_sys_mode.as_deref().and_then(signal::Rat::from_mode).unwrap_or(signal::Rat::Lte);
+            let broadcaster = crate::server::WS_BROADCASTER.get().filter(|_| urc::is_enabled(UrcStream::Signal));
+            if let (Some(rsrp), Some(tx)) = (current_rsrp, broadcaster) {
+                let msg = json!({
+                    "type": "signal_quality",
+                    "data": {
+                        "sysMode": current_sys_mode,
+                        "rsrp": rsrp,
+                        "bars": signal::bars(rat, rsrp),
+                    }
+                }).to_string();
+                let _ = tx.send(msg);
             }
 
-            if let Ok(response) = rx.await {
-                if let Some(data) = response.data {
-                    let mut message = String::new();
-                    
-                    let re_nr = RE_MONSC_NR.get_or_init(|| 
-                        Regex::new(r"\^MONSC: NR,(\d+),(\d+),(\d+),(\d+),(-?\d+),(-?\d+),(-?\d+)").unwrap()
-                    );
-                    
-                    let re_lte = RE_MONSC_LTE.get_or_init(||
-                        Regex::new(r"\^MONSC: LTE,(\d+),(\d+),(\d+),(\d+),(-?\d+),(-?\d+),(-?\d+)").unwrap()
-                    );
-
-                    if let Some(caps) = re_nr.captures(&data) {
-                        let arfcn = caps.get(2).map_or("", |m| m.as_str());
-                        let pci = caps.get(3).map_or("", |m| m.as_str());
-                        let rsrp = caps.get(5).map_or(0, |m| m.as_str().parse().unwrap_or(0));
-                        let rsrq = caps.get(6).map_or(0, |m| m.as_str().parse().unwrap_or(0));
-                        let sinr = caps.get(7).map_or(0, |m| m.as_str().parse().unwrap_or(0));
-                        
-                        let level = if rsrp >= -85 { "优秀" } else if rsrp >= -95 { "良好" } else if rsrp >= -105 { "一般" } else { "较差" };
-
-                        message = format!(
-                            "📶 5G 信号变动\n时间: {}\n信号质量: {}\nRSRP: {} dBm\nRSRQ: {} dB\nSINR: {} dB\n\n📡 小区信息:\n频点: {}\nPCI: {}",
-                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                            level, rsrp, rsrq, sinr, arfcn, pci
-                        );
-                    } else if let Some(caps) = re_lte.captures(&data) {
-                        let arfcn = caps.get(2).map_or("", |m| m.as_str());
-                        let pci = caps.get(3).map_or("", |m| m.as_str());
-                        let rsrp = caps.get(5).map_or(0, |m| m.as_str().parse().unwrap_or(0));
-                        let rsrq = caps.get(6).map_or(0, |m| m.as_str().parse().unwrap_or(0));
-                        let rssi = caps.get(7).map_or(0, |m| m.as_str().parse().unwrap_or(0));
-
-                        let level = if rsrp >= -85 { "优秀" } else if rsrp >= -95 { "良好" } else if rsrp >= -105 { "一般" } else { "较差" };
-
-                        message = format!(
-                            "📶 4G 信号变动\n时间: {}\n信号质量: {}\nRSRP: {} dBm\nRSRQ: {} dB\nRSSI: {} dBm\n\n📡 小区信息:\n频点: {}\nPCI: {}",
-                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
-                            level, rsrp, rsrq, rssi, arfcn, pci
-                        );
-                    }
+            // Query detailed info
+            if let Some(cell) = self.query_serving_cell(cmd_tx).await {
+                crate::metrics::record_signal(
+                    Some(cell.rsrp as f64),
+                    Some(cell.rsrq as f64),
+                    cell.sinr.map(f64::from),
+                );
+                crate::status::record_signal(Some(cell.rat), cell.rsrp, cell.sinr);
+                let bars = signal::bars(cell.rat, cell.rsrp);
+                let mut structured = json!(cell);
+                structured["bars"] = json!(bars);
 
-                    if !message.is_empty() {
-                        notifications.notify("信号监控", &message, NotificationType::Signal).await;
-                    }
+                // 结构化的服务小区数据供前端画图，与下面的通知文本分开；频点、PCI 能解析为数字时按数字下发
+                if let Some(tx) = crate::server::WS_BROADCASTER.get().filter(|_| urc::is_enabled(UrcStream::Signal)) {
+                    let numeric = |s: &str| s.parse::<u32>().map_or_else(|_| json!(s), |n| json!(n));
+                    let data = json!({
+                        "rat": cell.rat,
+                        "arfcn": numeric(&cell.arfcn),
+                        "pci": numeric(&cell.pci),
+                        "rsrp": cell.rsrp,
+                        "rsrq": cell.rsrq,
+                        "sinr": cell.sinr,
+                        "rssi": cell.rssi,
+                        "bars": bars,
+                    });
+                    let _ = tx.send(json!({ "type": "signal", "data": data }).to_string());
                 }
+
+                let (title, extra) = match cell.rat {
+                    signal::Rat::Nr => ("5G", format!("SINR: {} dB", cell.sinr.map_or("-".to_string(), |v| v.to_string()))),
+                    _ => ("4G", format!("RSSI: {} dBm", cell.rssi.map_or("-".to_string(), |v| v.to_string()))),
+                };
+                let message = format!(
+                    "📶 {} 信号变动\n时间: {}\n信号质量: {} ({}/4 格)\nRSRP: {} dBm\nRSRQ: {} dB\n{}\n\n📡 小区信息:\n频点: {}\nPCI: {}",
+                    title,
+                    chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                    signal::quality_label(bars), bars, cell.rsrp, cell.rsrq, extra, cell.arfcn, cell.pci
+                );
+                notifications.notify_with_data("信号监控", &message, NotificationType::Signal, Some(structured)).await;
             }
         }
         Ok(())
@@ -0,0 +1,88 @@
+//! VoLTE / IMS 注册状态
+//!
+//! 标准指令 `AT+CIREG?` 返回 `+CIREG: <n>,<reg_info>[,<ext_info>]`，开启上报（n=2）后状态变化以
+//! `+CIREGU: <reg_info>[,<ext_info>]` 上报；部分华为固件另有 `^IMSSTATE: <state>[,...]`，只取第一个字段。
+//! ext_info 按 27.007 为能力位：1 语音、2 视频、4 IMS 短信、8 文本。两种指令都不支持时报告 unsupported。
+
+use crate::models::CommandSender;
+use crate::retry;
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImsStatus {
+    pub registered: bool,
+    /// 是否可用 IMS 语音（VoLTE/VoNR），未知时为 None
+    pub voice: Option<bool>,
+    /// 是否可用 IMS 短信，未知时为 None
+    pub sms: Option<bool>,
+    /// 状态来源：CIREG 或 IMSSTATE
+    pub source: &'static str,
+}
+
+/// 解析 `+CIREG:` 查询结果、`+CIREGU:` 上报或 `^IMSSTATE:` 上报
+pub fn parse(data: &str) -> Option<ImsStatus> {
+    data.lines().find_map(|line| {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("+CIREG:") {
+            // 查询结果第一个字段是上报开关 n
+            status_from_fields(rest.split(',').skip(1), "CIREG")
+        } else if let Some(rest) = line.strip_prefix("+CIREGU:") {
+            status_from_fields(rest.split(','), "CIREG")
+        } else if let Some(rest) = line.strip_prefix("^IMSSTATE:") {
+            let state: u8 = rest.split(',').next()?.trim().parse().ok()?;
+            Some(ImsStatus { registered: state == 1, voice: None, sms: None, source: "IMSSTATE" })
+        } else {
+            None
+        }
+    })
+}
+
+fn status_from_fields<'a>(mut fields: impl Iterator<Item = &'a str>, source: &'static str) -> Option<ImsStatus> {
+    let reg_info: u8 = fields.next()?.trim().parse().ok()?;
+    let ext_info: Option<u32> = fields.next().and_then(|f| f.trim().parse().ok());
+    let registered = reg_info == 1;
+    Some(ImsStatus {
+        registered,
+        voice: ext_info.map(|e| registered && e & 1 != 0),
+        sms: ext_info.map(|e| registered && e & 4 != 0),
+        source,
+    })
+}
+
+/// 查询 IMS 注册状态，模组不支持相关指令时返回 None
+///
+/// `+CIREG` 上报在启动时的上报配置中开启（见 `dial_monitor::configure_urc_reporting`），变化由 `ImsHandler` 广播。
+pub async fn query(cmd_tx: &CommandSender) -> Result<Option<ImsStatus>> {
+    let resp = retry::send_with_retry(cmd_tx, "AT+CIREG?", false).await?;
+    if let Some(status) = resp.data.as_deref().filter(|_| resp.success).and_then(parse) {
+        return Ok(Some(status));
+    }
+
+    let resp = retry::send_with_retry(cmd_tx, "AT^IMSSTATE?", false).await?;
+    Ok(resp.data.as_deref().filter(|_| resp.success).and_then(parse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cireg_query_and_urc() {
+        let status = parse("+CIREG: 2,1,5\r\nOK").unwrap();
+        assert!(status.registered);
+        assert_eq!(status.voice, Some(true));
+        assert_eq!(status.sms, Some(true));
+
+        let status = parse("+CIREGU: 0").unwrap();
+        assert!(!status.registered);
+        assert_eq!(status.voice, None);
+    }
+
+    #[test]
+    fn parses_vendor_imsstate() {
+        assert!(parse("^IMSSTATE: 1,0").unwrap().registered);
+        assert_eq!(parse("OK"), None);
+    }
+}
@@ -10,12 +10,37 @@ mod schedule;
 mod network;
 mod dial_monitor;
 mod syslog;
+mod command_rules;
+mod sms;
+mod radio;
+mod signal;
+mod pdcp;
+mod urc;
+mod retry;
+mod modem;
+mod metrics;
+mod calls;
+mod sms_history;
+mod ring_file;
+mod capabilities;
+mod sim;
+mod sms_index;
+mod sms_export;
+mod audit;
+mod ims;
+mod operator;
+mod pdp;
+mod at_parse;
+mod sms_report;
+mod status;
+mod ping;
+mod freq_lock;
 
 use config::Config;
 use notifications::NotificationManager;
 use client::ATClient;
 use server::start_server;
-use log::info;
+use log::{info, warn};
 use std::sync::Arc;
 
 #[tokio::main]
@@ -29,13 +54,30 @@ async fn main() {
     let log_rx = syslog::init(&config);
     
     info!("Starting AT Webserver (Rust Version)...");
+    capabilities::probe();
     
     // 【新增】启动时先执行一次彻底的网络环境清理
     let _ = network::clean_startup_state().await;
     
     let notifications = NotificationManager::new(config.notification_config.clone());
     
-    let at_client = ATClient::new(config.clone(), notifications);
+    retry::init(&config.at_config);
+    audit::init(&config.sys_log_config.audit_log);
+    calls::init(config.notification_config.call_history_persist);
+    sms_history::init(config.notification_config.sms_history_persist);
+    sms::set_timestamp_zone(&config.notification_config.sms_timezone);
+    sms::set_notify_time(&config.notification_config.sms_notify_time);
+    sms::set_multipart_mode(&config.notification_config.multipart_mode);
+    sms::set_partial_limits(
+        config.notification_config.partial_sms_max_age,
+        config.notification_config.partial_sms_max_entries,
+    );
+    pdcp::init_throughput(config.advanced_network_config.throughput_smoothing);
+    dial_monitor::init_vendor_ids(&config.advanced_network_config.modem_vids);
+    if config.at_config.allow_inject_urc {
+        warn!("allow_inject_urc is enabled: WebSocket clients can inject fake modem URCs. Do not use in production!");
+    }
+    let at_client = ATClient::new(config.clone(), notifications.clone());
     let at_client_arc = Arc::new(at_client.clone());
     
     // Spawn schedule monitor
@@ -45,20 +87,41 @@ async fn main() {
         schedule::monitor_loop(monitor_client, schedule_config).await;
     });
 
+    // SIGHUP（kill -HUP）重新读取定时锁频配置并立即重新判断，等同 SCHEDULE_REEVALUATE
+    tokio::spawn(async {
+        let Ok(mut hangup) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) else {
+            warn!("Failed to install SIGHUP handler");
+            return;
+        };
+        while hangup.recv().await.is_some() {
+            info!("SIGHUP received, reloading schedule config");
+            if let Err(e) = schedule::reload_and_reevaluate().await {
+                warn!("Schedule re-evaluation failed: {}", e);
+            }
+        }
+    });
+
     // Spawn dial monitor
     let monitor_config = config.clone();
     let monitor_client = at_client.clone();
+    let monitor_notifications = notifications.clone();
+    tokio::spawn(async move {
+        dial_monitor::start_monitor(monitor_config, monitor_client, monitor_notifications).await;
+    });
+
+    // Spawn SMS storage monitor
+    let storage_tx = at_client.get_sender();
+    let storage_interval = config.notification_config.sms_storage_check_interval;
     tokio::spawn(async move {
-        dial_monitor::start_monitor(monitor_config, monitor_client).await;
+        sms::storage_monitor(storage_tx, notifications, storage_interval).await;
     });
 
     // Start WebSocket server
     start_server(
-        config.websocket_config.ipv4.port, 
-        config.websocket_config.ipv6.port,
         config.websocket_config.auth_key.clone(),
         at_client,
         log_rx,
-        if config.sys_log_config.persist { "/var/log/at-webserver.log".to_string() } else { "/tmp/at-webserver.log".to_string() }
+        if config.sys_log_config.persist { "/var/log/at-webserver.log".to_string() } else { "/tmp/at-webserver.log".to_string() },
+        config,
     ).await;
 }
@@ -0,0 +1,100 @@
+//! Prometheus 指标导出（`GET /metrics`）
+//!
+//! 只读取已有的共享状态（指令计数、最近一次信号、PDCP 吞吐量与累计流量），
+//! 抓取时不下发任何 AT 指令，不会与前端或定时任务争用串口。
+
+use crate::pdcp;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+static COMMANDS: AtomicU64 = AtomicU64::new(0);
+static COMMAND_TIMEOUTS: AtomicU64 = AtomicU64::new(0);
+static RECONNECTS: AtomicU64 = AtomicU64::new(0);
+static CONNECTS: AtomicU64 = AtomicU64::new(0);
+static CONNECTED_SINCE: Mutex<Option<Instant>> = Mutex::new(None);
+static SIGNAL: Mutex<SignalSnapshot> = Mutex::new(SignalSnapshot { rsrp: None, rsrq: None, sinr: None });
+
+/// 最近一次已知的信号值，字段缺失表示当前制式未上报
+#[derive(Debug, Clone, Copy)]
+struct SignalSnapshot {
+    rsrp: Option<f64>,
+    rsrq: Option<f64>,
+    sinr: Option<f64>,
+}
+
+/// Actor 每执行一条指令计一次
+pub fn record_command() {
+    COMMANDS.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_timeout() {
+    COMMAND_TIMEOUTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// 连接建立，首次之后的每次建立都计为一次重连
+pub fn record_connected() {
+    if CONNECTS.fetch_add(1, Ordering::Relaxed) > 0 {
+        RECONNECTS.fetch_add(1, Ordering::Relaxed);
+    }
+    *CONNECTED_SINCE.lock().unwrap() = Some(Instant::now());
+}
+
+pub fn record_disconnected() {
+    *CONNECTED_SINCE.lock().unwrap() = None;
+}
+
+/// 当前连接已建立的时长，未连接时为 None
+pub fn connection_uptime() -> Option<std::time::Duration> {
+    CONNECTED_SINCE.lock().unwrap().map(|since| since.elapsed())
+}
+
+/// 更新信号值，传 None 的字段保留上一次的值
+pub fn record_signal(rsrp: Option<f64>, rsrq: Option<f64>, sinr: Option<f64>) {
+    let mut signal = SIGNAL.lock().unwrap();
+    signal.rsrp = rsrp.or(signal.rsrp);
+    signal.rsrq = rsrq.or(signal.rsrq);
+    signal.sinr = sinr.or(signal.sinr);
+}
+
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
+
+/// 生成 Prometheus 文本格式
+pub fn render() -> String {
+    let mut out = String::new();
+
+    let signal = *SIGNAL.lock().unwrap();
+    for (name, help, value) in [
+        ("at_signal_rsrp_dbm", "Last known RSRP in dBm", signal.rsrp),
+        ("at_signal_rsrq_db", "Last known RSRQ in dB", signal.rsrq),
+        ("at_signal_sinr_db", "Last known SINR in dB", signal.sinr),
+    ] {
+        if let Some(value) = value {
+            metric(&mut out, name, "gauge", help, value);
+        }
+    }
+
+    if let Some(sample) = pdcp::throughput_history().last() {
+        metric(&mut out, "at_throughput_ul_mbps", "gauge", "Smoothed PDCP uplink throughput in Mbit/s", sample.ul_mbps);
+        metric(&mut out, "at_throughput_dl_mbps", "gauge", "Smoothed PDCP downlink throughput in Mbit/s", sample.dl_mbps);
+    }
+    let (ul_bytes, dl_bytes) = pdcp::data_usage();
+    metric(&mut out, "at_data_ul_bytes_total", "counter", "Uplink bytes estimated from PDCP rates", ul_bytes);
+    metric(&mut out, "at_data_dl_bytes_total", "counter", "Downlink bytes estimated from PDCP rates", dl_bytes);
+
+    let since = *CONNECTED_SINCE.lock().unwrap();
+    let uptime = since.map_or(0, |since| since.elapsed().as_secs());
+    metric(&mut out, "at_connection_up", "gauge", "Whether the modem connection is up", u8::from(since.is_some()));
+    metric(&mut out, "at_connection_uptime_seconds", "gauge", "Seconds since the modem connection was established", uptime);
+
+    metric(&mut out, "at_commands_total", "counter", "AT commands executed", COMMANDS.load(Ordering::Relaxed));
+    metric(&mut out, "at_command_timeouts_total", "counter", "AT commands that timed out", COMMAND_TIMEOUTS.load(Ordering::Relaxed));
+    metric(&mut out, "at_reconnects_total", "counter", "Modem reconnections", RECONNECTS.load(Ordering::Relaxed));
+
+    out
+}
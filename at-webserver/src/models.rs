@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::sync::OnceLock;
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc, oneshot};
 
 /// 全局 NDIS 断开事件广播器
@@ -38,18 +39,31 @@ impl ATResponse {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct SMS {
-    pub index: String,
-    pub sender: String,
-    pub content: String,
-    pub timestamp: String,
+/// 发给 Actor 的一条指令，`timeout` 为 None 时使用默认的响应超时
+#[derive(Debug, Clone)]
+pub struct AtCommand {
+    pub cmd: String,
+    pub timeout: Option<Duration>,
 }
 
-pub type CommandSender = mpsc::Sender<(String, oneshot::Sender<ATResponse>)>;
+impl From<String> for AtCommand {
+    fn from(cmd: String) -> Self {
+        Self { cmd, timeout: None }
+    }
+}
 
-#[derive(Debug, Clone)]
+impl From<&str> for AtCommand {
+    fn from(cmd: &str) -> Self {
+        cmd.to_string().into()
+    }
+}
+
+pub type CommandSender = mpsc::Sender<(AtCommand, oneshot::Sender<ATResponse>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ConnectionType {
     Network,
     Serial,
+    /// 不接模组，返回固定回复，供演示与前端开发使用
+    Simulation,
 }
@@ -0,0 +1,125 @@
+//! 模组型号识别与厂商内部日志、指示灯
+//!
+//! 型号通过 `AT+CGMM` 查询一次后缓存，供按型号区分的功能判断是否支持。
+//! 模组内部日志的读取/清空指令随固件不同，由配置提供，型号不匹配或未配置时返回 unsupported。
+//! 指示灯指令同样由配置提供，未配置或模组对该指令报错时返回 unsupported。
+
+use crate::client::ATClient;
+use crate::config::AtConfig;
+use crate::models::CommandSender;
+use crate::retry;
+use anyhow::{anyhow, Result};
+use log::info;
+use std::sync::Mutex;
+
+static MODEL: Mutex<Option<String>> = Mutex::new(None);
+
+/// 解析 `AT+CGMM` 响应，兼容裸型号与 `+CGMM: <model>` 两种格式
+pub fn parse_model(data: &str) -> Option<String> {
+    data.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != "OK" && !l.starts_with("AT"))
+        .map(|l| l.strip_prefix("+CGMM:").unwrap_or(l).trim().trim_matches('"').to_string())
+        .find(|m| !m.is_empty())
+}
+
+/// 返回模组型号，首次调用时查询并缓存
+pub async fn model(cmd_tx: &CommandSender) -> Result<String> {
+    if let Some(model) = MODEL.lock().unwrap().clone() {
+        return Ok(model);
+    }
+    let resp = retry::send_with_retry(cmd_tx, "AT+CGMM", false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT+CGMM failed".to_string())));
+    }
+    let model = resp
+        .data
+        .as_deref()
+        .and_then(parse_model)
+        .ok_or_else(|| anyhow!("Unexpected AT+CGMM response"))?;
+    info!("Detected modem model: {}", model);
+    *MODEL.lock().unwrap() = Some(model.clone());
+    Ok(model)
+}
+
+fn model_matches(model: &str, prefixes: &[String]) -> bool {
+    let model = model.to_uppercase();
+    prefixes.iter().any(|p| model.starts_with(&p.to_uppercase()))
+}
+
+/// 当前型号支持时返回对应的日志指令，否则返回 unsupported 错误
+async fn log_command<'a>(client: &ATClient, config: &AtConfig, cmd: &'a str) -> Result<&'a str> {
+    let model = model(&client.get_sender()).await?;
+    if cmd.trim().is_empty() || !model_matches(&model, &config.modem_log_models) {
+        return Err(anyhow!("Modem log is unsupported on model {}", model));
+    }
+    Ok(cmd.trim())
+}
+
+/// 读取模组内部日志，返回去掉 OK 与响应前缀后的条目
+pub async fn read_log(client: &ATClient, config: &AtConfig) -> Result<Vec<String>> {
+    let cmd = log_command(client, config, &config.modem_log_read_cmd).await?;
+    let resp = client.send_command(cmd.to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    Ok(response_values(cmd, &resp.data.unwrap_or_default()))
+}
+
+/// 去掉 OK、回显与响应前缀后的各行（`AT^XXX?` 的响应行 `^XXX: ...` 取冒号后的部分）
+fn response_values(cmd: &str, data: &str) -> Vec<String> {
+    // AT^XXX? -> ^XXX:
+    let prefix = cmd
+        .strip_prefix("AT")
+        .map(|core| format!("{}:", &core[..core.find(['?', '=']).unwrap_or(core.len())]));
+    data.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != "OK" && *l != cmd)
+        .map(|l| match &prefix {
+            Some(p) => l.strip_prefix(p.as_str()).unwrap_or(l).trim().to_string(),
+            None => l.to_string(),
+        })
+        .collect()
+}
+
+/// 未配置或模组不认该指令时统一返回 unsupported
+async fn led_command(client: &ATClient, cmd: &str) -> Result<Vec<String>> {
+    let model = model(&client.get_sender()).await.unwrap_or_else(|_| "unknown".to_string());
+    if cmd.is_empty() {
+        return Err(anyhow!("LED control is unsupported on model {} (led commands not configured)", model));
+    }
+    let resp = client.send_command(cmd.to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(
+            "LED control is unsupported on model {}: {}",
+            model,
+            resp.error.unwrap_or_else(|| format!("{} failed", cmd))
+        ));
+    }
+    Ok(response_values(cmd, &resp.data.unwrap_or_default()))
+}
+
+/// 查询指示灯状态，返回响应中的取值（通常是模式编号）
+pub async fn query_led(client: &ATClient, config: &AtConfig) -> Result<Vec<String>> {
+    led_command(client, config.led_query_cmd.trim()).await
+}
+
+/// 设置指示灯，`mode` 只允许字母数字，避免拼出额外参数
+pub async fn set_led(client: &ATClient, config: &AtConfig, mode: &str) -> Result<()> {
+    if mode.is_empty() || !mode.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(anyhow!("Invalid LED mode: {}", mode));
+    }
+    let template = config.led_set_cmd.trim();
+    let cmd = if template.contains("{mode}") { template.replace("{mode}", mode) } else { String::new() };
+    led_command(client, &cmd).await.map(|_| ())
+}
+
+/// 清空模组内部日志
+pub async fn clear_log(client: &ATClient, config: &AtConfig) -> Result<()> {
+    let cmd = log_command(client, config, &config.modem_log_clear_cmd).await?;
+    let resp = client.send_command(cmd.to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    Ok(())
+}
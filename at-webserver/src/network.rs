@@ -1,11 +1,22 @@
 use crate::config::Config;
 use anyhow::Result;
-use log::{error, info, debug};
+use log::{error, info, debug, warn};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
 use tokio::process::Command;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
 
 
 // 【新增】启动时清理环境，确保无残留配置
 pub async fn clean_startup_state() -> Result<()> {
+    if !crate::capabilities::network_setup_available() {
+        return Ok(());
+    }
     debug!("Performing startup cleanup...");
     let cleanup_script = r#"
         uci -q delete network.wan_modem
@@ -26,10 +37,166 @@ pub async fn clean_startup_state() -> Result<()> {
         exit 0
     "#;
     let _ = run_command("sh", &["-c", cleanup_script]).await;
+    // 接口已被删除，之前记录的校验和随之失效
+    write_applied_checksum(None).await;
     debug!("Startup cleanup completed.");
     Ok(())
 }
 
+/// 网络配置互斥锁：拨号监控、`APPLY_NETWORK` 与 `teardown` 都会 `uci set`/`commit`，
+/// 同一时间只允许一次网络配置，后到的调用排队等待前一次结束再执行
+static NETWORK_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+fn network_lock() -> &'static Mutex<()> {
+    NETWORK_LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// 持有网络配置锁执行 `f`
+async fn with_network_lock<F: std::future::Future>(f: F) -> F::Output {
+    let _guard = network_lock().lock().await;
+    f.await
+}
+
+/// 网络配置单个步骤的执行结果
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupStep {
+    pub step: &'static str,
+    pub success: bool,
+    pub message: String,
+}
+
+impl SetupStep {
+    fn from_result(step: &'static str, result: Result<()>) -> Self {
+        match result {
+            Ok(()) => Self { step, success: true, message: "done".to_string() },
+            Err(e) => Self { step, success: false, message: e.to_string() },
+        }
+    }
+}
+
+/// 最近一次成功应用的网络配置校验和，位于 tmpfs，重启后自然失效
+const APPLIED_CHECKSUM_PATH: &str = "/var/run/at-webserver/network.sum";
+
+/// 有效网络配置 + 网卡名的校验和，任何一项变化都需要重新应用
+fn network_checksum(config: &Config, ifname: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(&config.advanced_network_config).unwrap_or_default().hash(&mut hasher);
+    ifname.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+async fn read_applied_checksum() -> Option<String> {
+    tokio::fs::read_to_string(APPLIED_CHECKSUM_PATH).await.ok().map(|s| s.trim().to_string())
+}
+
+async fn write_applied_checksum(checksum: Option<&str>) {
+    let result = match checksum {
+        Some(sum) => {
+            if let Some(dir) = std::path::Path::new(APPLIED_CHECKSUM_PATH).parent() {
+                let _ = tokio::fs::create_dir_all(dir).await;
+            }
+            tokio::fs::write(APPLIED_CHECKSUM_PATH, sum).await
+        }
+        None => match tokio::fs::remove_file(APPLIED_CHECKSUM_PATH).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        },
+    };
+    if let Err(e) = result {
+        warn!("Failed to update applied network checksum: {}", e);
+    }
+}
+
+/// 在给定网卡上配置完整的 modem 网络：IPv4 接口，按 pdp_type 决定是否注入 IPv6。
+/// 拨号成功后与前端 `APPLY_NETWORK` 共用，返回逐步执行结果。
+///
+/// 配置与网卡都没变时跳过，避免每次重连都 ifup 造成短暂断网；`force` 为 true 时总是重新应用。
+/// 并发调用按到达顺序串行执行（见 `NETWORK_LOCK`），不会交错写入 UCI。
+pub async fn setup_modem_network(config: &Config, ifname: &str, force: bool) -> Vec<SetupStep> {
+    with_network_lock(setup_modem_network_locked(config, ifname, force)).await
+}
+
+async fn setup_modem_network_locked(config: &Config, ifname: &str, force: bool) -> Vec<SetupStep> {
+    if !crate::capabilities::network_setup_available() {
+        return vec![SetupStep {
+            step: "capabilities",
+            success: false,
+            message: "uci/ifup not found, network setup is disabled".to_string(),
+        }];
+    }
+
+    let checksum = network_checksum(config, ifname);
+    if !force && read_applied_checksum().await.as_deref() == Some(checksum.as_str()) {
+        info!("Network config unchanged for {} (checksum {}), skipping setup.", ifname, checksum);
+        return vec![SetupStep {
+            step: "checksum",
+            success: true,
+            message: format!("unchanged ({}), skipped", checksum),
+        }];
+    }
+
+    let mut steps = Vec::new();
+
+    let ipv4 = setup_ipv4_only(config, ifname).await;
+    match &ipv4 {
+        Ok(()) => debug!("IPv4 setup done."),
+        Err(e) => error!("Failed to setup IPv4 network: {}", e),
+    }
+    steps.push(SetupStep::from_result("ipv4", ipv4));
+
+    let pdp_type = config.advanced_network_config.pdp_type.to_lowercase();
+    // ipv6_needed：配置了 v6 协议类型（ipv4v6 / ipv6）
+    // 注意：不依赖 ipv6_present（AT+CGPADDR 可能只返回数据 PDP 的 IPv4，
+    // IMS/IPv6 地址不一定出现在响应中），只要配置了就尝试注入
+    let ipv6_needed = pdp_type.contains("v6") || pdp_type.contains("ipv6");
+
+    if ipv6_needed {
+        info!("IPv6 configured (pdp_type={}). Injecting IPv6 interface...", pdp_type);
+        let ipv6 = inject_ipv6_interface(config, ifname).await;
+        match &ipv6 {
+            Ok(()) => debug!("IPv6 Injection Completed."),
+            Err(e) => error!("Failed to inject IPv6 interface: {}", e),
+        }
+        steps.push(SetupStep::from_result("ipv6", ipv6));
+    } else {
+        steps.push(SetupStep {
+            step: "ipv6",
+            success: true,
+            message: format!("skipped (pdp_type={})", pdp_type),
+        });
+    }
+
+    // 只有全部成功才记录，失败时清掉旧记录，下次重连会重新应用
+    let all_ok = steps.iter().all(|s| s.success);
+    write_applied_checksum(all_ok.then_some(checksum.as_str())).await;
+
+    steps
+}
+
+/// 按地址族拆分自定义 DNS，无法解析的条目记录警告后忽略
+fn split_dns_by_family(dns_list: &[String]) -> (Vec<String>, Vec<String>) {
+    let mut v4 = Vec::new();
+    let mut v6 = Vec::new();
+    for dns in dns_list {
+        match dns.trim().parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => v4.push(ip.to_string()),
+            Ok(IpAddr::V6(ip)) => v6.push(ip.to_string()),
+            Err(_) => warn!("Ignoring invalid DNS server {:?}", dns),
+        }
+    }
+    (v4, v6)
+}
+
+/// 生成接口的 peerdns/dns 设置：有自定义 DNS 或禁用运营商 DNS 时关闭 peerdns
+fn dns_uci(iface: &str, servers: &[String], do_not_add_dns: bool) -> String {
+    let peerdns = if do_not_add_dns || !servers.is_empty() { 0 } else { 1 };
+    let mut batch = format!("set network.{}.peerdns='{}'\n", iface, peerdns);
+    for dns in servers {
+        batch.push_str(&format!("add_list network.{}.dns='{}'\n", iface, dns));
+    }
+    batch
+}
+
 pub async fn setup_ipv4_only(config: &Config, ifname: &str) -> Result<()> {
     debug!("Setting up IPv4 ONLY for interface: {}", ifname);
     let net_config = &config.advanced_network_config;
@@ -55,14 +222,8 @@ pub async fn setup_ipv4_only(config: &Config, ifname: &str) -> Result<()> {
     uci_batch.push_str("set network.wan_modem.delegate='0'\n");
     uci_batch.push_str("set network.wan_modem.auto='1'\n");
     
-    if !net_config.dns_list.is_empty() {
-        uci_batch.push_str("set network.wan_modem.peerdns='0'\n");
-        for dns in &net_config.dns_list {
-            uci_batch.push_str(&format!("add_list network.wan_modem.dns='{}'\n", dns));
-        }
-    } else {
-        uci_batch.push_str("set network.wan_modem.peerdns='1'\n");
-    }
+    let (dns_v4, _) = split_dns_by_family(&net_config.dns_list);
+    uci_batch.push_str(&dns_uci("wan_modem", &dns_v4, net_config.do_not_add_dns));
     
     uci_batch.push_str("commit network\n");
     
@@ -85,25 +246,74 @@ pub async fn setup_ipv4_only(config: &Config, ifname: &str) -> Result<()> {
     "#;
     let _ = run_command("sh", &["-c", fw_script]).await;
     
-    // 3. 拉起接口
+    // 3. 拉起接口（netifd 刚 commit 完可能还没就绪，首次 ifup 会被忽略，需要重试）
     debug!("Bringing up IPv4 interface...");
-    let _ = run_command("ifup", &["wan_modem"]).await;
+    let ifup_result = ifup_with_retry("wan_modem", ifname, config).await;
     
     // 4. 重载防火墙
     if run_command("fw4", &["reload"]).await.is_err() {
         let _ = run_command("/etc/init.d/firewall", &["reload"]).await;
     }
     
+    ifup_result?;
     debug!("IPv4 network setup completed.");
     Ok(())
 }
 
+/// 反复 `ifup` 直到网卡就绪（见 `is_link_up`），间隔按 ifup_retry_delay 起步指数退避
+async fn ifup_with_retry(iface: &str, ifname: &str, config: &Config) -> Result<()> {
+    let net_config = &config.advanced_network_config;
+    let attempts = net_config.ifup_attempts.max(1);
+    let mut delay = Duration::from_secs(net_config.ifup_retry_delay as u64);
+
+    for attempt in 1..=attempts {
+        let _ = run_command("ifup", &[iface]).await;
+        sleep(delay).await;
+
+        let state = operstate(ifname).await;
+        if is_link_up(ifname, state.as_deref()).await {
+            info!("Interface {} ({}) is up after {} attempt(s).", iface, ifname, attempt);
+            return Ok(());
+        }
+        warn!(
+            "Interface {} ({}) not up yet (operstate={}), attempt {}/{}",
+            iface, ifname, state.as_deref().unwrap_or("missing"), attempt, attempts
+        );
+        delay *= 2;
+    }
+
+    error!("Interface {} ({}) failed to come up after {} attempts.", iface, ifname, attempts);
+    Err(anyhow::anyhow!("{} ({}) did not come up after {} ifup attempts", iface, ifname, attempts))
+}
+
+/// operstate 为 up，或为 unknown 但网卡已被置为 UP（IFF_UP）
+///
+/// WWAN/QMI/NCM 等没有载波检测的网卡正常工作时 operstate 也常是 unknown，只看 up 会误判失败并多次重试。
+async fn is_link_up(ifname: &str, state: Option<&str>) -> bool {
+    match state {
+        Some("up") => true,
+        Some("unknown") => tokio::fs::read_to_string(format!("/sys/class/net/{}/flags", ifname))
+            .await
+            .ok()
+            .and_then(|flags| u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok())
+            .is_some_and(|flags| flags & 0x1 != 0),
+        _ => false,
+    }
+}
+
+async fn operstate(ifname: &str) -> Option<String> {
+    tokio::fs::read_to_string(format!("/sys/class/net/{}/operstate", ifname))
+        .await
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
 /// 为 MT5700M-CN 配置 IPv6。
 ///
 /// 设计原则：尊重用户配置，只在接口不存在时写入默认值。
 /// 每次重拨只更新 device（绑定到正确网卡），其余用户自定义配置保留不变。
 /// 用户可在 LuCI 或通过 UCI 自由调整 proto/ra/dhcpv6/ndp 等参数，重拨后不会丢失。
-pub async fn inject_ipv6_interface(_config: &Config, ifname: &str) -> Result<()> {
+pub async fn inject_ipv6_interface(config: &Config, ifname: &str) -> Result<()> {
     debug!("Injecting IPv6 interface for: {}", ifname);
 
     // 1. 检查 wan_modem6 是否已存在
@@ -128,7 +338,6 @@ pub async fn inject_ipv6_interface(_config: &Config, ifname: &str) -> Result<()>
              set network.wan_modem6.norelease='1'\n\
              set network.wan_modem6.auto='1'\n\
              set network.wan_modem6.defaultroute='1'\n\
-             set network.wan_modem6.peerdns='1'\n\
              commit network\n",
             ifname = ifname
         );
@@ -147,19 +356,33 @@ pub async fn inject_ipv6_interface(_config: &Config, ifname: &str) -> Result<()>
         }
     }
 
-    // 2. 检查 odhcpd 的 dhcp.wan_modem6 是否已存在，不存在才写入默认 relay 配置
-    let dhcp_check = tokio::process::Command::new("uci")
-        .args(&["get", "dhcp.wan_modem6"])
-        .output()
-        .await
-        .map(|o| o.status.success())
-        .unwrap_or(false);
+    // DNS 由本服务的 dns_list / do_not_add_dns 管理，已存在的接口同样覆盖
+    let (_, dns_v6) = split_dns_by_family(&config.advanced_network_config.dns_list);
+    let dns_batch = format!(
+        "delete network.wan_modem6.dns\n{}commit network\n",
+        dns_uci("wan_modem6", &dns_v6, config.advanced_network_config.do_not_add_dns)
+    );
+    let script = format!("uci -q batch <<EOF\n{}EOF", dns_batch);
+    if let Err(e) = run_command("sh", &["-c", &script]).await {
+        warn!("Failed to apply wan_modem6 DNS: {}", e);
+    }
+
+    // 2. RA/NDP relay：ra_master 开启时按需写入默认配置，关闭时清理之前写入的 relay，
+    //    否则残留的 master 配置会与新的设置冲突，开关形同虚设
+    if config.advanced_network_config.ra_master {
+        // 检查 odhcpd 的 dhcp.wan_modem6 是否已存在，不存在才写入默认 relay 配置
+        let dhcp_check = tokio::process::Command::new("uci")
+            .args(&["get", "dhcp.wan_modem6"])
+            .output()
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false);
 
-    if !dhcp_check {
-        debug!("dhcp.wan_modem6 not found, creating with default relay config...");
-        // 默认：RA Relay master 模式，用户可在 LuCI 自行调整
-        let relay_script = r#"
-        uci batch <<EOF
+        if !dhcp_check {
+            debug!("dhcp.wan_modem6 not found, creating with default relay config...");
+            // 默认：RA Relay master 模式，用户可在 LuCI 自行调整
+            let relay_script = r#"
+            uci batch <<EOF
 set dhcp.wan_modem6=dhcp
 set dhcp.wan_modem6.interface='wan_modem6'
 set dhcp.wan_modem6.ignore='1'
@@ -168,39 +391,42 @@ set dhcp.wan_modem6.ndp='relay'
 set dhcp.wan_modem6.master='1'
 commit dhcp
 EOF
-        "#;
-        if let Err(e) = run_command("sh", &["-c", relay_script]).await {
-            error!("Failed to setup dhcp.wan_modem6: {}", e);
-            return Err(e);
+            "#;
+            if let Err(e) = run_command("sh", &["-c", relay_script]).await {
+                error!("Failed to setup dhcp.wan_modem6: {}", e);
+                return Err(e);
+            }
+        } else {
+            debug!("dhcp.wan_modem6 exists, preserving user config.");
         }
-    } else {
-        debug!("dhcp.wan_modem6 exists, preserving user config.");
-    }
 
-    // 3. 检查 dhcp.lan 的 relay 配置，不存在才写入默认值（保留用户修改）
-    let lan_ra = tokio::process::Command::new("uci")
-        .args(&["get", "dhcp.lan.ra"])
-        .output()
-        .await
-        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
-        .unwrap_or_default();
+        // 检查 dhcp.lan 的 relay 配置，不存在才写入默认值（保留用户修改）
+        let lan_ra = tokio::process::Command::new("uci")
+            .args(&["get", "dhcp.lan.ra"])
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default();
 
-    if lan_ra.is_empty() {
-        debug!("dhcp.lan.ra not set, applying default relay config for LAN...");
-        let lan_script = r#"
-        uci batch <<EOF
+        if lan_ra.is_empty() {
+            debug!("dhcp.lan.ra not set, applying default relay config for LAN...");
+            let lan_script = r#"
+            uci batch <<EOF
 set dhcp.lan.ra='relay'
 set dhcp.lan.ndp='relay'
 set dhcp.lan.dhcpv6='relay'
 commit dhcp
 EOF
-        "#;
-        if let Err(e) = run_command("sh", &["-c", lan_script]).await {
-            error!("Failed to setup dhcp.lan relay: {}", e);
-            return Err(e);
+            "#;
+            if let Err(e) = run_command("sh", &["-c", lan_script]).await {
+                error!("Failed to setup dhcp.lan relay: {}", e);
+                return Err(e);
+            }
+        } else {
+            debug!("dhcp.lan.ra='{}', preserving user LAN config.", lan_ra);
         }
     } else {
-        debug!("dhcp.lan.ra='{}', preserving user LAN config.", lan_ra);
+        remove_ipv6_relay().await?;
     }
 
     // 3. 绑定防火墙 wan zone
@@ -230,6 +456,43 @@ EOF
     Ok(())
 }
 
+/// ra_master 关闭时撤销 relay：删除 dhcp.wan_modem6，并把 LAN 侧的 relay 还原为 odhcpd 默认值
+async fn remove_ipv6_relay() -> Result<()> {
+    if uci_get("dhcp.wan_modem6").await.is_some() {
+        info!("ra_master disabled, removing dhcp.wan_modem6 relay master...");
+        if let Err(e) = run_command("sh", &["-c", "uci -q delete dhcp.wan_modem6 && uci commit dhcp"]).await {
+            error!("Failed to remove dhcp.wan_modem6: {}", e);
+            return Err(e);
+        }
+    }
+
+    // 只撤销 relay 取值，用户改成 server 等其他模式的保持不动
+    let mut lan_batch = String::new();
+    for opt in ["ra", "ndp", "dhcpv6"] {
+        if uci_get(&format!("dhcp.lan.{}", opt)).await.as_deref() == Some("relay") {
+            lan_batch.push_str(&format!("delete dhcp.lan.{}\n", opt));
+        }
+    }
+    if !lan_batch.is_empty() {
+        debug!("Reverting dhcp.lan relay settings...");
+        lan_batch.push_str("commit dhcp\n");
+        let script = format!("uci batch <<EOF\n{}EOF", lan_batch);
+        if let Err(e) = run_command("sh", &["-c", &script]).await {
+            error!("Failed to revert dhcp.lan relay: {}", e);
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// 读取单个 UCI 选项，不存在时返回 None
+async fn uci_get(key: &str) -> Option<String> {
+    let output = Command::new("uci").args(["-q", "get", key]).output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
 
 async fn run_command(program: &str, args: &[&str]) -> Result<()> {
     let output = Command::new(program)
@@ -250,6 +513,10 @@ async fn run_command(program: &str, args: &[&str]) -> Result<()> {
 }
 
 pub async fn teardown_modem_network() -> Result<()> {
+    if !crate::capabilities::network_setup_available() {
+        return Err(anyhow::anyhow!("uci/ifup not found, network setup is disabled"));
+    }
+    let _guard = network_lock().lock().await;
     info!("Tearing down modem network by frontend request...");
     // 1. 断开网口
     let _ = run_command("ifdown", &["wan_modem"]).await;
@@ -270,7 +537,35 @@ pub async fn teardown_modem_network() -> Result<()> {
         exit 0
     "#;
     let _ = run_command("sh", &["-c", teardown_script]).await;
+    write_applied_checksum(None).await;
     info!("Network interfaces and firewall rules cleared.");
     Ok(())
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn concurrent_setups_are_serialized() {
+        let running = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(AtomicUsize::new(0));
+        let run = |running: Arc<AtomicBool>, finished: Arc<AtomicUsize>| async move {
+            with_network_lock(async {
+                assert!(!running.swap(true, Ordering::SeqCst), "network setups overlapped");
+                sleep(Duration::from_millis(20)).await;
+                running.store(false, Ordering::SeqCst);
+                finished.fetch_add(1, Ordering::SeqCst);
+            })
+            .await
+        };
+        let a = tokio::spawn(run(running.clone(), finished.clone()));
+        let b = tokio::spawn(run(running.clone(), finished.clone()));
+        a.await.unwrap();
+        b.await.unwrap();
+        assert_eq!(finished.load(Ordering::SeqCst), 2);
+    }
+}
@@ -3,8 +3,10 @@ use anyhow::Result;
 use async_trait::async_trait;
 use log::{error, info, warn, debug};
 use reqwest::Client;
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
@@ -17,14 +19,51 @@ pub struct NotificationMessage {
     pub sender: String,
     pub content: String,
     pub notification_type: NotificationType,
+    /// 可选的结构化数据，支持 JSON 的通道（通用 Webhook、自定义脚本）一并发送，文本通道只用 content
+    pub data: Option<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NotificationType {
     SMS,
     Call,
     MemoryFull,
     Signal,
+    /// SIM 卡拔出、插入或更换
+    Sim,
+    /// IMS（VoLTE）注册状态变化
+    Ims,
+    /// 拨号因配置错误失败，需要人工处理
+    Dial,
+    /// 与模组的 AT 连接断开或恢复
+    Connection,
+}
+
+impl NotificationType {
+    pub const ALL: [NotificationType; 8] = [
+        NotificationType::SMS,
+        NotificationType::Call,
+        NotificationType::MemoryFull,
+        NotificationType::Signal,
+        NotificationType::Sim,
+        NotificationType::Ims,
+        NotificationType::Dial,
+        NotificationType::Connection,
+    ];
+
+    /// 冷却配置项 `<key>_cooldown` 中使用的类型名
+    pub fn key(self) -> &'static str {
+        match self {
+            NotificationType::SMS => "sms",
+            NotificationType::Call => "call",
+            NotificationType::MemoryFull => "memory_full",
+            NotificationType::Signal => "signal",
+            NotificationType::Sim => "sim",
+            NotificationType::Ims => "ims",
+            NotificationType::Dial => "dial",
+            NotificationType::Connection => "connection",
+        }
+    }
 }
 
 #[async_trait]
@@ -266,10 +305,14 @@ struct GenericWebhook { url: String, client: Client }
 #[async_trait]
 impl NotificationChannel for GenericWebhook {
     async fn send(&self, msg: &NotificationMessage) -> Result<()> {
-        let payload = serde_json::json!({
+        let mut payload = serde_json::json!({
             "title": msg.sender,
             "content": msg.content
         });
+        if let Some(data) = &msg.data {
+            payload["type"] = serde_json::json!(format!("{:?}", msg.notification_type));
+            payload["data"] = data.clone();
+        }
         let client = self.client.clone();
         let url = self.url.clone();
         tokio::spawn(async move {
@@ -288,10 +331,13 @@ impl NotificationChannel for CustomScript {
         let path = self.path.clone();
         let sender = msg.sender.clone();
         let content = msg.content.clone();
+        // 结构化数据通过环境变量传给脚本，保持原有的两个位置参数不变
+        let data = msg.data.as_ref().map(|d| d.to_string()).unwrap_or_default();
         tokio::spawn(async move {
             if let Err(e) = Command::new(path)
                 .arg(sender)
                 .arg(content)
+                .env("AT_NOTIFY_DATA", data)
                 .status()
                 .await 
             {
@@ -331,12 +377,43 @@ impl NotificationChannel for WeChatWork {
 pub struct NotificationManager {
     channels: Arc<Vec<Box<dyn NotificationChannel>>>,
     config: Arc<NotificationConfig>,
+    /// 各类型上一次实际发出的时间，用于 `notify_cooldowns`
+    last_sent: Arc<Mutex<HashMap<NotificationType, Instant>>>,
+}
+
+/// 构造推送共用的 HTTP 客户端，`bind_address` 非空时所有请求以该地址为源地址
+///
+/// 绑定源地址并不直接指定出口网卡：内核仍按路由表选路，只是源地址固定。多 WAN 场景下要让推送
+/// 固定走主 WAN（避免经模组自身回环），需同时配置按源地址选表的策略路由（`ip rule add from <地址> table <主 WAN 表>`，
+/// 或 mwan3 中按源地址匹配的规则）。地址必须已配置在本机某个接口上，否则发送时报 "Cannot assign requested address"。
+fn build_client(bind_address: &str) -> Client {
+    let bind_address = bind_address.trim();
+    if bind_address.is_empty() {
+        return Client::new();
+    }
+    let addr = match bind_address.parse::<std::net::IpAddr>() {
+        Ok(addr) => addr,
+        Err(_) => {
+            warn!("Invalid notify_bind_address '{}', using the default source address", bind_address);
+            return Client::new();
+        }
+    };
+    match Client::builder().local_address(addr).build() {
+        Ok(client) => {
+            info!("Notification requests will use source address {}", addr);
+            client
+        }
+        Err(e) => {
+            warn!("Failed to bind notification client to {}: {}, using the default source address", addr, e);
+            Client::new()
+        }
+    }
 }
 
 impl NotificationManager {
     pub fn new(config: NotificationConfig) -> Self {
         let mut channels: Vec<Box<dyn NotificationChannel>> = Vec::new();
-        let client = Client::new();
+        let client = build_client(&config.notify_bind_address);
         
         // Initialize Log Notification
         if config.notify_log_enable {
@@ -420,14 +497,31 @@ impl NotificationManager {
         Self {
             channels: Arc::new(channels),
             config: Arc::new(config),
+            last_sent: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    pub async fn notify(&self, sender: &str, content: &str, notification_type: NotificationType) {
+    /// 发送通知，返回是否实际交给了各通道（类型未开启或处于冷却期时为 false）
+    pub async fn notify(&self, sender: &str, content: &str, notification_type: NotificationType) -> bool {
+        self.notify_with_data(sender, content, notification_type, None).await
+    }
+
+    /// 同 `notify`，额外附带结构化数据
+    pub async fn notify_with_data(
+        &self,
+        sender: &str,
+        content: &str,
+        notification_type: NotificationType,
+        data: Option<serde_json::Value>,
+    ) -> bool {
         let should_notify = match notification_type {
             NotificationType::SMS => self.config.notify_sms,
             NotificationType::Call => self.config.notify_call,
             NotificationType::MemoryFull => self.config.notify_memory_full_threshold > 0,
+            NotificationType::Sim => self.config.notify_sim,
+            NotificationType::Ims => self.config.notify_ims,
+            NotificationType::Dial => self.config.notify_dial,
+            NotificationType::Connection => self.config.notify_connection,
             NotificationType::Signal => {
                 // signal_threshold == 0 表示禁用信号通知
                 // signal_threshold > 0 表示 RSRP 低于 -threshold dBm 才通知
@@ -436,9 +530,10 @@ impl NotificationManager {
                 if threshold <= 0 {
                     false
                 } else {
-                    // 从 content 中提取 RSRP 值判断是否超过阈值
+                    // 优先使用结构化数据中的 RSRP，否则从 content 中提取
                     // content 格式包含 "RSRP: -95 dBm" 等字符串
-                    let rsrp = content
+                    let structured = data.as_ref().and_then(|d| d.get("rsrp")).and_then(|v| v.as_i64()).map(|v| v as i32);
+                    let rsrp = structured.or_else(|| content
                         .lines()
                         .find(|l| l.contains("RSRP:"))
                         .and_then(|l| {
@@ -446,7 +541,7 @@ impl NotificationManager {
                                 .skip_while(|s| *s != "RSRP:")
                                 .nth(1)
                                 .and_then(|s| s.parse::<i32>().ok())
-                        });
+                        }));
                     // RSRP 是负数，低于 -threshold 才通知
                     // 例如 threshold=100：rsrp < -100 时通知
                     rsrp.map(|r| r < -threshold).unwrap_or(true)
@@ -454,19 +549,42 @@ impl NotificationManager {
             }
         };
 
-        if should_notify {
-            let msg = NotificationMessage {
-                sender: sender.to_string(),
-                content: content.to_string(),
-                notification_type,
-            };
-            
-            for channel in self.channels.iter() {
-                if let Err(e) = channel.send(&msg).await {
-                    error!("Failed to send notification: {}", e);
-                }
+        if !should_notify {
+            return false;
+        }
+        if !self.cooldown_passed(notification_type) {
+            debug!("Suppressing {:?} notification during cooldown: {}", notification_type, content);
+            return false;
+        }
+
+        let msg = NotificationMessage {
+            sender: sender.to_string(),
+            content: content.to_string(),
+            notification_type,
+            data,
+        };
+
+        for channel in self.channels.iter() {
+            if let Err(e) = channel.send(&msg).await {
+                error!("Failed to send notification: {}", e);
             }
         }
+        true
+    }
+
+    /// 该类型不在冷却期内时记下本次发送时间并返回 true
+    fn cooldown_passed(&self, notification_type: NotificationType) -> bool {
+        let cooldown = self.config.notify_cooldowns.get(notification_type.key()).copied().unwrap_or(0);
+        if cooldown == 0 {
+            return true;
+        }
+        let mut last_sent = self.last_sent.lock().unwrap();
+        let now = Instant::now();
+        if last_sent.get(&notification_type).is_some_and(|at| now.duration_since(*at) < Duration::from_secs(cooldown)) {
+            return false;
+        }
+        last_sent.insert(notification_type, now);
+        true
     }
 
     pub fn has_active_push_services(&self) -> bool {
@@ -480,3 +598,20 @@ impl NotificationManager {
         self.config.notify_memory_full_threshold
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cooldown_suppresses_repeat_and_reports_it() {
+        let mut config = crate::config::Config::default().notification_config;
+        config.notify_log_enable = false;
+        config.notify_cooldowns.insert("sms".to_string(), 300);
+        let manager = NotificationManager::new(config);
+        assert!(manager.notify("10086", "first", NotificationType::SMS).await);
+        assert!(!manager.notify("10086", "second", NotificationType::SMS).await);
+        // 其他类型不受影响
+        assert!(manager.notify("", "ring", NotificationType::Call).await);
+    }
+}
@@ -0,0 +1,189 @@
+//! 运营商与注册状态
+//!
+//! `GET_OPERATOR` 合并 `AT+COPS?` 与 `AT+C5GREG?`/`AT+CEREG?`/`AT+CREG?` 的结果。模组返回数字 PLMN 时
+//! 按内置表（可由 `operator_name` 列表配置覆盖或补充）换算为运营商名称，已返回字母名称时直接使用。
+
+use crate::models::CommandSender;
+use crate::retry;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 内置的 PLMN → 运营商名称
+const OPERATOR_NAMES: &[(&str, &str)] = &[
+    ("46000", "中国移动"),
+    ("46002", "中国移动"),
+    ("46004", "中国移动"),
+    ("46007", "中国移动"),
+    ("46008", "中国移动"),
+    ("46001", "中国联通"),
+    ("46006", "中国联通"),
+    ("46009", "中国联通"),
+    ("46003", "中国电信"),
+    ("46005", "中国电信"),
+    ("46011", "中国电信"),
+    ("46012", "中国电信"),
+    ("46015", "中国广电"),
+    ("46020", "中国铁通"),
+    ("45400", "CSL"),
+    ("45403", "3 HK"),
+    ("45406", "SmarTone"),
+    ("45412", "中国移动香港"),
+    ("45413", "中国移动香港"),
+    ("45500", "SmarTone MO"),
+    ("45501", "CTM"),
+    ("46601", "远传电信"),
+    ("46692", "中华电信"),
+    ("46697", "台湾大哥大"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct OperatorInfo {
+    /// 运营商名称，未知的数字 PLMN 时为 None
+    pub operator: Option<String>,
+    /// 数字 PLMN（MCC+MNC），模组返回字母名称时为 None
+    pub plmn: Option<String>,
+    /// 接入技术，由 COPS 的 AcT 换算
+    pub rat: Option<&'static str>,
+    /// 注册状态：registered / roaming / searching / denied / not_registered / unknown
+    pub registration: &'static str,
+}
+
+/// `+COPS: <mode>[,<format>,"<oper>"[,<AcT>]]` 中的运营商字段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cops {
+    /// format 2 为数字 PLMN，0/1 为长/短字母名称
+    pub numeric: bool,
+    pub oper: String,
+    pub act: Option<u8>,
+}
+
+pub fn parse_cops(data: &str) -> Option<Cops> {
+    let rest = data.lines().find_map(|l| l.trim().strip_prefix("+COPS:"))?;
+    let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+    let format: u8 = fields.get(1)?.parse().ok()?;
+    let oper = fields.get(2)?.trim_matches('"').to_string();
+    if oper.is_empty() {
+        return None;
+    }
+    Some(Cops {
+        numeric: format == 2,
+        oper,
+        act: fields.get(3).and_then(|a| a.parse().ok()),
+    })
+}
+
+/// 27.007 的 AcT 取值
+fn act_name(act: u8) -> Option<&'static str> {
+    Some(match act {
+        0 | 1 | 3 => "GSM",
+        2 | 4 | 5 | 6 => "WCDMA",
+        7 | 9 => "LTE",
+        10 => "LTE-5GC",
+        11 | 12 => "NR",
+        13 => "NR-NSA",
+        _ => return None,
+    })
+}
+
+/// `+CREG: <n>,<stat>`（CEREG/C5GREG 同）的注册状态
+pub fn parse_registration(data: &str) -> Option<&'static str> {
+    Some(match crate::dial_monitor::registration_stat(data)? {
+        "0" => "not_registered",
+        "1" => "registered",
+        "2" => "searching",
+        "3" => "denied",
+        "5" => "roaming",
+        _ => "unknown",
+    })
+}
+
+/// 解析 `operator_name` 列表配置，每项 `46000=中国移动`
+pub fn parse_overrides(entries: &[String]) -> HashMap<String, String> {
+    entries
+        .iter()
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(plmn, name)| (plmn.trim().to_string(), name.trim().to_string()))
+        .filter(|(plmn, name)| !plmn.is_empty() && !name.is_empty())
+        .collect()
+}
+
+pub fn lookup(plmn: &str, overrides: &HashMap<String, String>) -> Option<String> {
+    overrides
+        .get(plmn)
+        .cloned()
+        .or_else(|| OPERATOR_NAMES.iter().find(|(p, _)| *p == plmn).map(|(_, n)| n.to_string()))
+}
+
+pub async fn query(cmd_tx: &CommandSender, overrides: &HashMap<String, String>) -> Result<OperatorInfo> {
+    let resp = retry::send_with_retry(cmd_tx, "AT+COPS?", false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT+COPS? failed".to_string())));
+    }
+    let cops = resp.data.as_deref().and_then(parse_cops);
+
+    // 任一制式已注册即以其为准，否则取第一个有结果的
+    let mut registration = None;
+    for cmd in ["AT+C5GREG?", "AT+CEREG?", "AT+CREG?"] {
+        let Ok(resp) = retry::send_with_retry(cmd_tx, cmd, false).await else {
+            continue;
+        };
+        let Some(state) = resp.data.as_deref().and_then(parse_registration) else {
+            continue;
+        };
+        if matches!(state, "registered" | "roaming") {
+            registration = Some(state);
+            break;
+        }
+        registration.get_or_insert(state);
+    }
+
+    let (operator, plmn) = match &cops {
+        Some(c) if c.numeric => (lookup(&c.oper, overrides), Some(c.oper.clone())),
+        Some(c) => (Some(c.oper.clone()), None),
+        None => (None, None),
+    };
+    let info = OperatorInfo {
+        operator,
+        plmn,
+        rat: cops.and_then(|c| c.act).and_then(act_name),
+        registration: registration.unwrap_or("unknown"),
+    };
+    crate::status::record_operator(&info);
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_numeric_and_alphanumeric_cops() {
+        let cops = parse_cops("+COPS: 0,2,\"46001\",7\r\nOK").unwrap();
+        assert!(cops.numeric);
+        assert_eq!(cops.oper, "46001");
+        assert_eq!(cops.act, Some(7));
+        assert_eq!(lookup(&cops.oper, &HashMap::new()).as_deref(), Some("中国联通"));
+
+        let cops = parse_cops("+COPS: 0,0,\"CHN-UNICOM\",13").unwrap();
+        assert!(!cops.numeric);
+        assert_eq!(cops.oper, "CHN-UNICOM");
+
+        assert_eq!(parse_cops("+COPS: 0"), None);
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let overrides = parse_overrides(&["46001=MyCarrier".to_string(), " 99999 = Test".to_string(), "bogus".to_string()]);
+        assert_eq!(lookup("46001", &overrides).as_deref(), Some("MyCarrier"));
+        assert_eq!(lookup("99999", &overrides).as_deref(), Some("Test"));
+        assert_eq!(lookup("00101", &overrides), None);
+    }
+
+    #[test]
+    fn parses_registration_state() {
+        assert_eq!(parse_registration("+CEREG: 2,5,\"1A2B\",\"01234567\",7"), Some("roaming"));
+        assert_eq!(parse_registration("+C5GREG: 0,0"), Some("not_registered"));
+        assert_eq!(parse_registration("OK"), None);
+    }
+}
@@ -0,0 +1,169 @@
+//! ^PDCPDATAINFO 解析
+//!
+//! 主动上报（`PDCPDataHandler`）与前端拉取（`GET_PDCP_INFO`）共用同一个解析结果。
+
+use crate::client::ATClient;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// 吞吐量历史保留的样本数，供前端画图
+const THROUGHPUT_HISTORY_LEN: usize = 120;
+
+/// 两次上报间隔超过此秒数（上报被关闭、断线等）时不再按速率累计流量，避免凭空估出大流量
+const MAX_USAGE_GAP_SECS: f64 = 10.0;
+
+static THROUGHPUT: OnceLock<Mutex<ThroughputEstimator>> = OnceLock::new();
+
+/// 一条 ^PDCPDATAINFO 记录，时延类字段原始单位为 0.1ms，这里已换算为 ms
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdcpInfo {
+    pub id: i32,
+    pub pdu_session_id: i32,
+    pub discard_timer_len: i32,
+    pub avg_delay: f64,
+    pub min_delay: f64,
+    pub max_delay: f64,
+    pub high_pri_que_max_buff_time: f64,
+    pub low_pri_que_max_buff_time: f64,
+    pub high_pri_que_buff_pkt_nums: i32,
+    pub low_pri_que_buff_pkt_nums: i32,
+    pub ul_pdcp_rate: i64,
+    pub dl_pdcp_rate: i64,
+    pub ul_discard_cnt: i32,
+    pub dl_discard_cnt: i32,
+}
+
+/// 解析单行 `^PDCPDATAINFO: 1,1,100,20,5,30,10,5,100,50,1024,2048,0,0`
+///
+/// 不同固件上报的字段数不一，缺失的尾部字段按 0 处理；无法解析出 id 时返回 None。
+pub fn parse_pdcp_line(line: &str) -> Option<PdcpInfo> {
+    let data = line.trim().strip_prefix("^PDCPDATAINFO:")?;
+    let parts: Vec<&str> = data.split(',').map(|s| s.trim()).collect();
+
+    let int = |i: usize| parts.get(i).and_then(|s| s.parse::<i32>().ok()).unwrap_or(0);
+    let rate = |i: usize| parts.get(i).and_then(|s| s.parse::<i64>().ok()).unwrap_or(0);
+    let delay = |i: usize| parts.get(i).and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0) / 10.0;
+
+    Some(PdcpInfo {
+        id: parts.first()?.parse().ok()?,
+        pdu_session_id: int(1),
+        discard_timer_len: int(2),
+        avg_delay: delay(3),
+        min_delay: delay(4),
+        max_delay: delay(5),
+        high_pri_que_max_buff_time: delay(6),
+        low_pri_que_max_buff_time: delay(7),
+        high_pri_que_buff_pkt_nums: int(8),
+        low_pri_que_buff_pkt_nums: int(9),
+        ul_pdcp_rate: rate(10),
+        dl_pdcp_rate: rate(11),
+        ul_discard_cnt: int(12),
+        dl_discard_cnt: int(13),
+    })
+}
+
+/// 主动查询当前 PDCP 统计，每个 PDU 会话一条
+pub async fn query_pdcp_info(client: &ATClient) -> Result<Vec<PdcpInfo>> {
+    let resp = client.send_command("AT^PDCPDATAINFO?".to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT^PDCPDATAINFO? failed".to_string())));
+    }
+    Ok(resp.data.as_deref().unwrap_or("").lines().filter_map(parse_pdcp_line).collect())
+}
+
+/// PDCP 速率单位为 Byte/s，换算为 Mbit/s
+pub fn bytes_per_sec_to_mbps(rate: i64) -> f64 {
+    rate as f64 * 8.0 / 1_000_000.0
+}
+
+/// 平滑后的一个吞吐量样本
+#[derive(Debug, Clone, Serialize)]
+pub struct ThroughputSample {
+    pub time: DateTime<Local>,
+    pub ul_mbps: f64,
+    pub dl_mbps: f64,
+}
+
+/// 上下行吞吐量的指数滑动平均，alpha 越大越贴近瞬时值
+pub struct ThroughputEstimator {
+    alpha: f64,
+    current: Option<(f64, f64)>,
+    history: VecDeque<ThroughputSample>,
+    /// 按原始速率 × 上报间隔累计的上下行字节数
+    ul_bytes: u64,
+    dl_bytes: u64,
+    last_update: Option<Instant>,
+}
+
+impl ThroughputEstimator {
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0.01, 1.0),
+            current: None,
+            history: VecDeque::with_capacity(THROUGHPUT_HISTORY_LEN),
+            ul_bytes: 0,
+            dl_bytes: 0,
+            last_update: None,
+        }
+    }
+
+    pub fn update(&mut self, info: &PdcpInfo) -> ThroughputSample {
+        let now = Instant::now();
+        if let Some(last) = self.last_update {
+            let gap = now.duration_since(last).as_secs_f64();
+            if gap <= MAX_USAGE_GAP_SECS {
+                self.ul_bytes += (info.ul_pdcp_rate.max(0) as f64 * gap) as u64;
+                self.dl_bytes += (info.dl_pdcp_rate.max(0) as f64 * gap) as u64;
+            }
+        }
+        self.last_update = Some(now);
+
+        let ul = bytes_per_sec_to_mbps(info.ul_pdcp_rate);
+        let dl = bytes_per_sec_to_mbps(info.dl_pdcp_rate);
+        let (ul, dl) = match self.current {
+            Some((prev_ul, prev_dl)) => (
+                self.alpha * ul + (1.0 - self.alpha) * prev_ul,
+                self.alpha * dl + (1.0 - self.alpha) * prev_dl,
+            ),
+            None => (ul, dl),
+        };
+        self.current = Some((ul, dl));
+
+        let sample = ThroughputSample { time: Local::now(), ul_mbps: ul, dl_mbps: dl };
+        if self.history.len() == THROUGHPUT_HISTORY_LEN {
+            self.history.pop_front();
+        }
+        self.history.push_back(sample.clone());
+        sample
+    }
+}
+
+/// 启动时设置平滑系数，须在第一条 ^PDCPDATAINFO 到达前调用
+pub fn init_throughput(alpha: f64) {
+    let _ = THROUGHPUT.set(Mutex::new(ThroughputEstimator::new(alpha)));
+}
+
+fn throughput() -> &'static Mutex<ThroughputEstimator> {
+    THROUGHPUT.get_or_init(|| Mutex::new(ThroughputEstimator::new(0.3)))
+}
+
+/// 用一条 PDCP 记录更新全局吞吐量估计
+pub fn record_throughput(info: &PdcpInfo) -> ThroughputSample {
+    throughput().lock().unwrap().update(info)
+}
+
+/// 最近的吞吐量历史（时间升序）
+pub fn throughput_history() -> Vec<ThroughputSample> {
+    throughput().lock().unwrap().history.iter().cloned().collect()
+}
+
+/// 自启动以来按 PDCP 速率估算的累计上下行字节数
+pub fn data_usage() -> (u64, u64) {
+    let estimator = throughput().lock().unwrap();
+    (estimator.ul_bytes, estimator.dl_bytes)
+}
@@ -0,0 +1,72 @@
+//! PDP 上下文表（AT+CGDCONT）
+//!
+//! 后端拨号不修改上下文（见 `dial_monitor::perform_dial`），`GET_CONTEXTS` 供前端核对模组里实际保存的 APN。
+
+use crate::at_parse::split_fields;
+use crate::models::CommandSender;
+use crate::retry;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdpContext {
+    pub cid: u8,
+    /// IP / IPV6 / IPV4V6 等
+    pub pdp_type: String,
+    /// 未设置 APN 时为空字符串，由网络分配默认 APN
+    pub apn: String,
+    /// 静态地址，通常为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+/// 解析 `+CGDCONT: <cid>,"<type>","<apn>","<addr>",<d_comp>,<h_comp>,...`，只要求 cid 与类型，其余字段可缺省
+pub fn parse_cgdcont(data: &str) -> Vec<PdpContext> {
+    data.lines()
+        .filter_map(|l| l.trim().strip_prefix("+CGDCONT:"))
+        .filter_map(|rest| {
+            let fields = split_fields(rest);
+            let cid = fields.first()?.parse().ok()?;
+            let pdp_type = fields.get(1).filter(|t| !t.is_empty())?.clone();
+            Some(PdpContext {
+                cid,
+                pdp_type,
+                apn: fields.get(2).cloned().unwrap_or_default(),
+                address: fields.get(3).filter(|a| !a.is_empty() && a.as_str() != "0.0.0.0").cloned(),
+            })
+        })
+        .collect()
+}
+
+pub async fn query_contexts(cmd_tx: &CommandSender) -> Result<Vec<PdpContext>> {
+    let resp = retry::send_with_retry(cmd_tx, "AT+CGDCONT?", false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT+CGDCONT? failed".to_string())));
+    }
+    Ok(parse_cgdcont(resp.data.as_deref().unwrap_or("")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_contexts_with_optional_fields() {
+        let data = "+CGDCONT: 1,\"IPV4V6\",\"cmnet\",\"0.0.0.0\",0,0,0,0\r\n\
+                    +CGDCONT: 2,\"IP\",\"\"\r\n\
+                    +CGDCONT: 3,\"IPV6\"\r\nOK";
+        let contexts = parse_cgdcont(data);
+        assert_eq!(contexts.len(), 3);
+        assert_eq!(contexts[0], PdpContext { cid: 1, pdp_type: "IPV4V6".into(), apn: "cmnet".into(), address: None });
+        assert_eq!(contexts[1].apn, "");
+        assert_eq!(contexts[2], PdpContext { cid: 3, pdp_type: "IPV6".into(), apn: String::new(), address: None });
+    }
+
+    #[test]
+    fn keeps_commas_inside_quotes() {
+        let contexts = parse_cgdcont("+CGDCONT: 5,\"IP\",\"odd,apn\",\"10.0.0.1\"");
+        assert_eq!(contexts[0].apn, "odd,apn");
+        assert_eq!(contexts[0].address.as_deref(), Some("10.0.0.1"));
+    }
+}
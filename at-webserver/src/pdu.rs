@@ -1,5 +1,5 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, TimeZone};
 
 // GSM 7-bit default alphabet
 const GSM_7BIT_ALPHABET: [char; 128] = [
@@ -27,8 +27,41 @@ pub struct PartialInfo {
 pub struct SmsData {
     pub sender: String,
     pub content: String,
-    pub date: DateTime<Local>,
+    /// 短信中心时间戳（SCTS），保留其自带的时区
+    pub date: DateTime<FixedOffset>,
     pub partial_info: Option<PartialInfo>,
+    /// TP-PID 协议标识，见 `pid_kind`
+    pub pid: u8,
+}
+
+/// TP-PID 的用途分类（3GPP TS 23.040 9.2.3.9）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PidKind {
+    /// 普通短信（含 0x00 与各类 telematic 互通）
+    Normal,
+    /// Short Message Type 0：只需确认，不应展示给用户
+    TypeZero,
+    /// Replace Short Message Type 1-7：覆盖同一发送方同类型的旧短信
+    Replace(u8),
+    /// ME / (U)SIM 数据下载、ME 去个性化等发给终端或 SIM 卡的二进制数据，不应展示给用户
+    DataDownload,
+}
+
+impl PidKind {
+    pub fn from_pid(pid: u8) -> Self {
+        match pid {
+            0x40 => PidKind::TypeZero,
+            0x41..=0x47 => PidKind::Replace(pid - 0x40),
+            0x7C..=0x7F => PidKind::DataDownload,
+            _ => PidKind::Normal,
+        }
+    }
+}
+
+impl SmsData {
+    pub fn pid_kind(&self) -> PidKind {
+        PidKind::from_pid(self.pid)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -38,7 +71,7 @@ pub struct MmsNotification {
     pub transaction_id: Option<String>,
     pub content_type: Option<String>,
     pub raw_hex: String,
-    pub date: DateTime<Local>,
+    pub date: DateTime<FixedOffset>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,7 +80,12 @@ pub enum IncomingMessage {
     MmsNotification(MmsNotification),
 }
 
-fn decode_7bit(encoded_bytes: &[u8], length: usize) -> String {
+/// 解码 7-bit 用户数据，`length` 为 TP-UDL（含 UDH 在内的 septet 数）
+///
+/// UDH 按字节存放，正文从 UDH 之后的下一个 septet 边界开始（中间有 0-6 个填充位），
+/// 因此要从用户数据开头整体解包，再跳过 UDH 占用的 septet，而不能先按字节截掉 UDH。
+fn decode_7bit(encoded_bytes: &[u8], length: usize, udh_length: usize) -> String {
+    let udh_septets = (udh_length * 8).div_ceil(7);
     let mut result = Vec::new();
     let mut shift = 0;
     let mut tmp = 0u16;
@@ -67,11 +105,41 @@ fn decode_7bit(encoded_bytes: &[u8], length: usize) -> String {
         result.push((tmp & 0x7F) as usize);
     }
 
-    result
-        .iter()
-        .take(length)
-        .map(|&b| if b < GSM_7BIT_ALPHABET.len() { GSM_7BIT_ALPHABET[b] } else { '?' })
-        .collect()
+    let mut text = String::with_capacity(length.saturating_sub(udh_septets));
+    let mut septets = result.into_iter().take(length).skip(udh_septets);
+    while let Some(b) = septets.next() {
+        if b == 0x1B {
+            // 转义符：下一个 septet 查扩展表，查不到时按规范显示默认字母表中的字符
+            if let Some(next) = septets.next() {
+                text.push(gsm_7bit_extension(next).unwrap_or_else(|| default_7bit_char(next)));
+            }
+            continue;
+        }
+        text.push(default_7bit_char(b));
+    }
+    text
+}
+
+fn default_7bit_char(septet: usize) -> char {
+    GSM_7BIT_ALPHABET.get(septet).copied().unwrap_or('?')
+}
+
+/// GSM 7-bit 扩展表（3GPP TS 23.038 6.2.1.1），仅在 0x1B 转义符之后生效
+fn gsm_7bit_extension(septet: usize) -> Option<char> {
+    let c = match septet {
+        0x0A => '\u{000C}',
+        0x14 => '^',
+        0x28 => '{',
+        0x29 => '}',
+        0x2F => '\\',
+        0x3C => '[',
+        0x3D => '~',
+        0x3E => ']',
+        0x40 => '|',
+        0x65 => '\u{20AC}',
+        _ => return None,
+    };
+    Some(c)
 }
 
 fn decode_ucs2(encoded_bytes: &[u8]) -> String {
@@ -83,9 +151,10 @@ fn decode_ucs2(encoded_bytes: &[u8]) -> String {
     String::from_utf16(&u16_vec).unwrap_or_else(|_| "?".repeat(encoded_bytes.len() / 2))
 }
 
-fn decode_timestamp(timestamp_bytes: &[u8]) -> DateTime<Local> {
+/// 解码 7 字节 SCTS，最后一个字节为时区（单位 15 分钟，交换半字节后第 3 位为符号位）
+fn decode_timestamp(timestamp_bytes: &[u8]) -> DateTime<FixedOffset> {
     if timestamp_bytes.len() < 7 {
-        return Local::now();
+        return Local::now().fixed_offset();
     }
 
     let swap_nibbles = |b: u8| -> u8 { ((b & 0x0F) * 10) + (b >> 4) };
@@ -97,10 +166,16 @@ fn decode_timestamp(timestamp_bytes: &[u8]) -> DateTime<Local> {
     let minute = swap_nibbles(timestamp_bytes[4]) as u32;
     let second = swap_nibbles(timestamp_bytes[5]) as u32;
 
-    match NaiveDate::from_ymd_opt(year, month, day).and_then(|d| d.and_hms_opt(hour, minute, second)) {
-        Some(dt) => Local.from_local_datetime(&dt).unwrap(),
-        None => Local::now(),
-    }
+    let tz = timestamp_bytes[6];
+    let quarters = ((tz & 0x07) * 10 + (tz >> 4)) as i32;
+    let sign = if tz & 0x08 != 0 { -1 } else { 1 };
+    let offset = FixedOffset::east_opt(sign * quarters * 15 * 60);
+
+    NaiveDate::from_ymd_opt(year, month, day)
+        .and_then(|d| d.and_hms_opt(hour, minute, second))
+        .zip(offset)
+        .and_then(|(dt, offset)| offset.from_local_datetime(&dt).single())
+        .unwrap_or_else(|| Local::now().fixed_offset())
 }
 
 fn decode_number(number_bytes: &[u8], number_length: usize) -> String {
@@ -134,7 +209,7 @@ fn extract_ascii_field(bytes: &[u8], needle: &str) -> Option<String> {
 
 fn try_decode_mms_notification(
     sender: &str,
-    timestamp: DateTime<Local>,
+    timestamp: DateTime<FixedOffset>,
     pdu_hex: &str,
     data_bytes: &[u8],
 ) -> Option<MmsNotification> {
@@ -164,6 +239,50 @@ fn try_decode_mms_notification(
     })
 }
 
+/// `+CMGL:` / `+CMGR:` 响应头与紧随其后的 PDU 行
+#[derive(Debug, Clone, PartialEq)]
+pub struct PduRecord {
+    /// 去掉 `+CMGL:` / `+CMGR:` 后的头部字段
+    pub header: String,
+    pub pdu: String,
+}
+
+impl PduRecord {
+    /// `+CMGL: <index>,<stat>,[<alpha>],<length>` 的存储索引（`+CMGR` 头部无索引）
+    pub fn index(&self) -> Option<u32> {
+        self.header.split(',').next()?.trim().parse().ok()
+    }
+}
+
+fn is_pdu_line(line: &str) -> bool {
+    !line.is_empty() && hex::decode(line).is_ok()
+}
+
+/// 按顺序把每个 `+CMGL:` / `+CMGR:` 头与下一行 PDU 配对，头部后不是 PDU 的记录会被跳过
+pub fn extract_pdu_records(response: &str) -> Vec<PduRecord> {
+    let mut records = Vec::new();
+    let mut pending_header: Option<String> = None;
+
+    for line in response.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix("+CMGL:").or_else(|| line.strip_prefix("+CMGR:")) {
+            pending_header = Some(header.trim().to_string());
+            continue;
+        }
+        if let Some(header) = pending_header.take() {
+            if is_pdu_line(line) {
+                records.push(PduRecord { header, pdu: line.to_string() });
+            }
+        }
+    }
+    records
+}
+
+/// 从 `AT+CMGL` / `AT+CMGR` 响应中按顺序提取 PDU
+pub fn extract_pdus(response: &str) -> Vec<String> {
+    extract_pdu_records(response).into_iter().map(|r| r.pdu).collect()
+}
+
 pub fn read_incoming_sms(pdu_hex: &str) -> Result<IncomingMessage> {
     let pdu_bytes = hex::decode(pdu_hex).context("Invalid hex string")?;
     let mut pos = 0;
@@ -198,6 +317,7 @@ pub fn read_incoming_sms(pdu_hex: &str) -> Result<IncomingMessage> {
     if pos >= pdu_bytes.len() {
         return Err(anyhow::anyhow!("PDU too short"));
     }
+    let pid = pdu_bytes[pos];
     pos += 1;
 
     if pos >= pdu_bytes.len() {
@@ -249,16 +369,12 @@ pub fn read_incoming_sms(pdu_hex: &str) -> Result<IncomingMessage> {
         return Ok(IncomingMessage::MmsNotification(mms));
     }
 
-    let content_bytes = if data_bytes.len() >= udh_length {
-        &data_bytes[udh_length..]
-    } else {
-        &[]
-    };
-
     let content = if is_ucs2 {
-        decode_ucs2(content_bytes)
+        // UCS2 的 TP-UDL 为字节数（含 UDH），多出的尾部字节不属于正文
+        let end = data_length.min(data_bytes.len());
+        decode_ucs2(data_bytes.get(udh_length..end).unwrap_or(&[]))
     } else {
-        decode_7bit(content_bytes, data_length)
+        decode_7bit(data_bytes, data_length, udh_length)
     };
 
     Ok(IncomingMessage::Sms(SmsData {
@@ -266,5 +382,139 @@ pub fn read_incoming_sms(pdu_hex: &str) -> Result<IncomingMessage> {
         content,
         date: timestamp,
         partial_info,
+        pid,
     }))
 }
+
+/// SMS-STATUS-REPORT（短信状态报告）
+#[derive(Debug, Clone)]
+pub struct StatusReport {
+    /// 对应 `+CMGS: <mr>` 的消息参考号
+    pub reference: u8,
+    pub recipient: String,
+    /// 短信中心收到短信的时间
+    pub submitted: DateTime<FixedOffset>,
+    /// 投递完成（或最终失败）的时间
+    pub discharged: DateTime<FixedOffset>,
+    /// TP-ST：0x00-0x1F 已投递，0x20-0x3F 仍在重试，0x40 及以上投递失败
+    pub status: u8,
+}
+
+/// 解析 `+CDS` 携带的 SMS-STATUS-REPORT PDU
+pub fn read_status_report(pdu_hex: &str) -> Result<StatusReport> {
+    let bytes = hex::decode(pdu_hex).context("Invalid hex string")?;
+    let short = || anyhow::anyhow!("Status report PDU too short");
+    let mut pos = 1 + *bytes.first().ok_or_else(short)? as usize;
+    let first_octet = *bytes.get(pos).ok_or_else(short)?;
+    if first_octet & 0x03 != 0x02 {
+        return Err(anyhow::anyhow!("Not an SMS-STATUS-REPORT (first octet {:02X})", first_octet));
+    }
+    let reference = *bytes.get(pos + 1).ok_or_else(short)?;
+    let number_length = *bytes.get(pos + 2).ok_or_else(short)? as usize;
+    pos += 4;
+    let number_bytes = bytes.get(pos..pos + number_length.div_ceil(2)).ok_or_else(short)?;
+    let recipient = decode_number(number_bytes, number_length);
+    pos += number_bytes.len();
+    let times = bytes.get(pos..pos + 15).ok_or_else(short)?;
+    Ok(StatusReport {
+        reference,
+        recipient,
+        submitted: decode_timestamp(&times[..7]),
+        discharged: decode_timestamp(&times[7..14]),
+        status: times[14],
+    })
+}
+
+/// 取出 SMS-SUBMIT PDU 中的收件人号码，用于与之后的状态报告对应
+pub fn submit_recipient(pdu_hex: &str) -> Option<String> {
+    let bytes = hex::decode(pdu_hex).ok()?;
+    let pos = 1 + *bytes.first()? as usize;
+    if bytes.get(pos)? & 0x03 != 0x01 {
+        return None;
+    }
+    let number_length = *bytes.get(pos + 2)? as usize;
+    let number_bytes = bytes.get(pos + 4..pos + 4 + number_length.div_ceil(2))?;
+    Some(decode_number(number_bytes, number_length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // SMS-DELIVER，发送方 13800138000，7-bit 编码 "Price €5 [ok]"
+    const PDU_EXTENDED_CHARS: &str = "00040B913108108300F00000620161214365231050797A5C066DCA35D086F75E6F7C";
+
+    #[test]
+    fn decodes_euro_sign_and_square_brackets() {
+        match read_incoming_sms(PDU_EXTENDED_CHARS).unwrap() {
+            IncomingMessage::Sms(sms) => {
+                assert_eq!(sms.sender, "13800138000");
+                assert_eq!(sms.content, "Price \u{20AC}5 [ok]");
+            }
+            other => panic!("expected SMS, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decodes_7bit_concatenated_part_without_trailing_garbage() {
+        // 第 2/2 段，UDH 05 00 03 2A 02 02 占 7 个 septet（含 1 个填充位），正文 "Hello world, part two!"
+        let pdu = "00440B913108108300F00000620161214365231D0500032A0202906536FB0DBABFE56C320B040FCBE920FAFD1D02";
+        match read_incoming_sms(pdu).unwrap() {
+            IncomingMessage::Sms(sms) => {
+                assert_eq!(sms.content, "Hello world, part two!");
+                let partial = sms.partial_info.expect("concatenated SMS should carry partial info");
+                assert_eq!((partial.reference, partial.parts_count, partial.part_number), (0x2A, 2, 2));
+            }
+            other => panic!("expected SMS, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn scts_keeps_its_timezone() {
+        // 62 01 61 21 43 65 23: 2026-10-16 12:34:56，时区 32 个 15 分钟 = +08:00
+        assert_eq!(
+            decode_timestamp(&[0x62, 0x01, 0x61, 0x21, 0x43, 0x65, 0x23]).to_rfc3339(),
+            "2026-10-16T12:34:56+08:00"
+        );
+        // 时区字节 0x2A：符号位为负，22 个 15 分钟 = -05:30
+        assert_eq!(
+            decode_timestamp(&[0x62, 0x01, 0x61, 0x21, 0x43, 0x65, 0x2A]).to_rfc3339(),
+            "2026-10-16T12:34:56-05:30"
+        );
+    }
+
+    #[test]
+    fn unknown_escape_falls_back_to_default_alphabet() {
+        // septets: 0x1B 0x41 -> 'A'
+        assert_eq!(decode_7bit(&[0x9B, 0x20], 2, 0), "A");
+    }
+
+    #[test]
+    fn classifies_protocol_identifier() {
+        match read_incoming_sms(PDU_EXTENDED_CHARS).unwrap() {
+            IncomingMessage::Sms(sms) => assert_eq!(sms.pid_kind(), PidKind::Normal),
+            other => panic!("expected SMS, got {:?}", other),
+        }
+        // 同一条短信把 PID 改为 0x41（Replace Type 1）
+        let replace = PDU_EXTENDED_CHARS.replacen("F00000", "F04100", 1);
+        match read_incoming_sms(&replace).unwrap() {
+            IncomingMessage::Sms(sms) => assert_eq!(sms.pid_kind(), PidKind::Replace(1)),
+            other => panic!("expected SMS, got {:?}", other),
+        }
+        assert_eq!(PidKind::from_pid(0x40), PidKind::TypeZero);
+        assert_eq!(PidKind::from_pid(0x7F), PidKind::DataDownload);
+        assert_eq!(PidKind::from_pid(0x21), PidKind::Normal);
+    }
+
+    #[test]
+    fn parses_status_report_and_submit_recipient() {
+        let report = read_status_report("00060C0B913108108300F0620161214365236201612143752300").unwrap();
+        assert_eq!(report.reference, 12);
+        assert_eq!(report.recipient, "13800138000");
+        assert_eq!(report.status, 0);
+        assert!(report.discharged > report.submitted);
+
+        assert!(read_status_report(PDU_EXTENDED_CHARS).is_err());
+        assert_eq!(submit_recipient("0011000B915121551532F40000AA0AE8329BFD4697D9EC37").as_deref(), Some("15125551234"));
+    }
+}
@@ -0,0 +1,150 @@
+//! `PING:<host>[,<count>]` 链路时延测量
+//!
+//! 调用系统 `ping`（busybox 与 iputils 输出均可解析），尽量通过 `-I` 绑定到模组网卡，
+//! 返回丢包率与 min/avg/max 时延。主机名只允许域名/IP 字符，参数直接作为 argv 传入，不经过 shell。
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// 默认与最大发包数
+const DEFAULT_COUNT: u32 = 4;
+const MAX_COUNT: u32 = 10;
+
+/// 每个包等待回复的秒数（`-W`）
+const REPLY_TIMEOUT_SECS: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingRequest {
+    pub host: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PingStats {
+    pub transmitted: u32,
+    pub received: u32,
+    /// 丢包率（百分比）
+    pub loss: f64,
+    pub min: Option<f64>,
+    pub avg: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl PingRequest {
+    /// 解析 `<host>[,<count>]`，count 限制在 1-10
+    pub fn parse(arg: &str) -> Result<Self> {
+        let (host, count) = match arg.split_once(',') {
+            Some((host, count)) => {
+                let count: u32 = count.trim().parse().map_err(|_| anyhow!("Invalid ping count: {}", count.trim()))?;
+                (host, count)
+            }
+            None => (arg, DEFAULT_COUNT),
+        };
+        let host = host.trim();
+        if !is_valid_host(host) {
+            return Err(anyhow!("Invalid ping target: {}", host));
+        }
+        Ok(PingRequest { host: host.to_string(), count: count.clamp(1, MAX_COUNT) })
+    }
+}
+
+/// 只允许域名或 IP 地址，拒绝以 `-` 开头（会被当成 ping 的选项）
+fn is_valid_host(host: &str) -> bool {
+    if host.parse::<std::net::IpAddr>().is_ok() {
+        return true;
+    }
+    !host.is_empty()
+        && host.len() <= 253
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// 解析 ping 的统计行
+///
+/// busybox：`4 packets transmitted, 4 packets received, 0% packet loss` 与 `round-trip min/avg/max = 1.0/2.0/3.0 ms`；
+/// iputils：`4 packets transmitted, 4 received, 0% packet loss, time 3004ms` 与 `rtt min/avg/max/mdev = ...`
+pub fn parse_output(output: &str) -> Option<PingStats> {
+    let summary = output.lines().find(|l| l.contains("packets transmitted"))?;
+    let mut stats = PingStats::default();
+    for part in summary.split(',').map(str::trim) {
+        let value = part.split_whitespace().next().unwrap_or("");
+        if part.ends_with("transmitted") {
+            stats.transmitted = value.parse().ok()?;
+        } else if part.ends_with("received") {
+            stats.received = value.parse().ok()?;
+        } else if part.ends_with("packet loss") {
+            stats.loss = value.trim_end_matches('%').parse().ok()?;
+        }
+    }
+
+    if let Some(rtt) = output.lines().find_map(|l| l.split_once(" = ").filter(|(k, _)| k.contains("min/avg/max"))) {
+        let mut values = rtt.1.split_whitespace().next().unwrap_or("").split('/').map(|v| v.parse::<f64>().ok());
+        stats.min = values.next().flatten();
+        stats.avg = values.next().flatten();
+        stats.max = values.next().flatten();
+    }
+    Some(stats)
+}
+
+/// 执行 ping，`interface` 为 None 时走默认路由
+pub async fn run(request: &PingRequest, interface: Option<&str>) -> Result<PingStats> {
+    let mut cmd = Command::new("ping");
+    if request.host.contains(':') {
+        cmd.arg("-6");
+    }
+    cmd.args(["-c", &request.count.to_string(), "-W", &REPLY_TIMEOUT_SECS.to_string()]);
+    if let Some(ifname) = interface {
+        cmd.args(["-I", ifname]);
+    }
+    cmd.arg(&request.host).kill_on_drop(true);
+
+    // 每个包间隔约 1 秒，再留出最后一个包的等待时间
+    let deadline = Duration::from_secs((request.count + REPLY_TIMEOUT_SECS + 3) as u64);
+    let output = tokio::time::timeout(deadline, cmd.output())
+        .await
+        .map_err(|_| anyhow!("ping timed out"))?
+        .map_err(|e| anyhow!("Failed to run ping: {}", e))?;
+
+    // 全部丢包时 ping 的退出码非 0，但统计行仍然有效
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_output(&stdout).ok_or_else(|| {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow!("ping failed: {}", stderr.trim().lines().next().unwrap_or("no statistics in output"))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_request_and_rejects_injection() {
+        assert_eq!(PingRequest::parse("8.8.8.8").unwrap(), PingRequest { host: "8.8.8.8".into(), count: 4 });
+        assert_eq!(PingRequest::parse("www.baidu.com,50").unwrap().count, MAX_COUNT);
+        assert!(PingRequest::parse("2400:3200::1").is_ok());
+        assert!(PingRequest::parse("8.8.8.8; reboot").is_err());
+        assert!(PingRequest::parse("-f 8.8.8.8").is_err());
+        assert!(PingRequest::parse("$(id)").is_err());
+    }
+
+    #[test]
+    fn parses_busybox_and_iputils_output() {
+        let busybox = "4 packets transmitted, 3 packets received, 25% packet loss\nround-trip min/avg/max = 20.1/25.3/31.0 ms\n";
+        let stats = parse_output(busybox).unwrap();
+        assert_eq!((stats.transmitted, stats.received, stats.loss), (4, 3, 25.0));
+        assert_eq!((stats.min, stats.avg, stats.max), (Some(20.1), Some(25.3), Some(31.0)));
+
+        let iputils = "4 packets transmitted, 0 received, 100% packet loss, time 3059ms\n";
+        let stats = parse_output(iputils).unwrap();
+        assert_eq!((stats.received, stats.loss, stats.avg), (0, 100.0, None));
+
+        let iputils_ok = "2 packets transmitted, 2 received, 0% packet loss, time 1001ms\nrtt min/avg/max/mdev = 9.5/10.0/10.5/0.5 ms\n";
+        assert_eq!(parse_output(iputils_ok).unwrap().max, Some(10.5));
+    }
+}
@@ -0,0 +1,107 @@
+//! 射频开关（AT+CFUN）查询与设置
+//!
+//! 所有指令都经 `ATClient` 的同一条指令通道下发，不绕过 Actor 直接写串口。
+//!
+//! ## 射频操作锁
+//!
+//! 单条指令由 Actor 串行执行，但定时锁频是一整段序列（CFUN=0 → 锁频 → CFUN=1），
+//! 中途插入用户的 CFUN 会让模组停在不一致的状态。因此：
+//! - 定时锁频（schedule.rs）在整段序列期间持有 `RADIO_LOCK`，需要时排队等待；
+//! - 用户侧的 CFUN（`SET_RADIO_STATE` 与终端直发的 `AT+CFUN=`）只尝试加锁，
+//!   锁被占用时立即返回 busy 错误，不排队、不与序列交错；
+//! - 终端直发的指令在序列进行中提交时，按提交顺序排队等锁释放后再执行（`wait_radio_idle`），
+//!   超过 `radio_defer_timeout` 仍未释放则返回 busy，回复中以 `deferred` 标明曾被推迟。
+
+use crate::client::ATClient;
+use anyhow::{anyhow, Result};
+use log::info;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+
+static RADIO_LOCK: OnceLock<Mutex<()>> = OnceLock::new();
+
+/// 射频操作被占用时返回给前端的错误
+pub const RADIO_BUSY: &str = "Radio operation in progress (scheduled band lock), try again later";
+
+fn radio_lock() -> &'static Mutex<()> {
+    RADIO_LOCK.get_or_init(|| Mutex::new(()))
+}
+
+/// 等待并持有射频操作锁，用于多步射频序列
+pub async fn lock_radio() -> MutexGuard<'static, ()> {
+    radio_lock().lock().await
+}
+
+/// 尝试持有射频操作锁，被占用时返回 None
+pub fn try_lock_radio() -> Option<MutexGuard<'static, ()>> {
+    radio_lock().try_lock().ok()
+}
+
+/// 射频操作是否正在进行
+pub fn is_busy() -> bool {
+    radio_lock().try_lock().is_err()
+}
+
+/// 等待射频操作结束并持有锁，超时返回 None。锁按等待顺序交给排队的指令
+pub async fn wait_radio_idle(timeout: Duration) -> Option<MutexGuard<'static, ()>> {
+    tokio::time::timeout(timeout, radio_lock().lock()).await.ok()
+}
+
+/// 是否为会改变射频状态的 CFUN 设置指令（查询 `AT+CFUN?` 不算）
+pub fn is_cfun_set(cmd: &str) -> bool {
+    cmd.trim().to_uppercase().starts_with("AT+CFUN=")
+}
+
+/// 允许前端设置的 CFUN 模式：0=最小功能，1=全功能，4=飞行模式
+pub const ALLOWED_MODES: [u8; 3] = [0, 1, 4];
+
+fn mode_label(mode: u8) -> &'static str {
+    match mode {
+        0 => "minimum",
+        1 => "online",
+        4 => "airplane",
+        _ => "unknown",
+    }
+}
+
+/// 解析 `+CFUN: <fun>`
+pub fn parse_cfun(data: &str) -> Option<u8> {
+    data.lines()
+        .find_map(|line| line.trim().strip_prefix("+CFUN:"))
+        .and_then(|rest| rest.split(',').next())
+        .and_then(|fun| fun.trim().parse().ok())
+}
+
+pub fn radio_state_json(mode: u8) -> String {
+    serde_json::json!({
+        "mode": mode,
+        "state": mode_label(mode),
+    })
+    .to_string()
+}
+
+pub async fn query_radio_state(client: &ATClient) -> Result<u8> {
+    let resp = client.send_command("AT+CFUN?".to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT+CFUN? failed".to_string())));
+    }
+    resp.data
+        .as_deref()
+        .and_then(parse_cfun)
+        .ok_or_else(|| anyhow!("Unexpected AT+CFUN? response"))
+}
+
+/// 设置射频模式，成功后回读并返回模组实际所处的模式
+pub async fn set_radio_state(client: &ATClient, mode: u8) -> Result<u8> {
+    if !ALLOWED_MODES.contains(&mode) {
+        return Err(anyhow!("Unsupported CFUN mode {}, expected one of {:?}", mode, ALLOWED_MODES));
+    }
+    let _guard = try_lock_radio().ok_or_else(|| anyhow!(RADIO_BUSY))?;
+    info!("Setting radio state to {} ({})", mode, mode_label(mode));
+    let resp = client.send_command(format!("AT+CFUN={}", mode)).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("AT+CFUN={} failed", mode))));
+    }
+    query_radio_state(client).await
+}
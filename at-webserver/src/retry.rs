@@ -0,0 +1,74 @@
+//! 瞬时失败的 AT 指令重试
+//!
+//! 模组忙时 `AT^MONSC` 等查询偶尔直接返回 ERROR 或超时，过一会再发即可成功。
+//! 只对无副作用的指令自动重试；CFUN、拨号、短信收发删除等重复执行会改变状态，
+//! 除非调用方显式允许，否则只发一次。
+
+use crate::config::AtConfig;
+use crate::models::{ATResponse, CommandSender};
+use anyhow::{anyhow, Result};
+use log::debug;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::sleep;
+
+/// 带副作用、默认不重试的指令前缀
+const SIDE_EFFECT_PREFIXES: &[&str] = &[
+    "AT+CFUN=", "ATD", "AT^NDISDUP", "AT+CGACT", "AT+CGDATA", "AT+CMGS", "AT+CMGW", "AT+CMGD",
+];
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 总尝试次数（含首次）
+    pub attempts: u32,
+    pub delay: Duration,
+}
+
+static POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+/// 启动时按配置设置全局重试策略
+pub fn init(config: &AtConfig) {
+    let _ = POLICY.set(RetryPolicy {
+        attempts: config.retry_attempts.max(1),
+        delay: Duration::from_millis(config.retry_delay_ms),
+    });
+}
+
+fn policy() -> RetryPolicy {
+    *POLICY.get_or_init(|| RetryPolicy { attempts: 3, delay: Duration::from_millis(500) })
+}
+
+fn has_side_effects(cmd: &str) -> bool {
+    let cmd = cmd.trim().to_uppercase();
+    SIDE_EFFECT_PREFIXES.iter().any(|p| cmd.starts_with(p))
+}
+
+/// 纯 ERROR（模组忙）与超时可重试；+CME/+CMS 带错误码的属于确定性失败
+fn is_retryable(resp: &ATResponse) -> bool {
+    if resp.success {
+        return false;
+    }
+    let err = resp.error.as_deref().unwrap_or("");
+    err == "Timeout" || err.lines().last().is_some_and(|l| l.trim() == "ERROR")
+}
+
+/// 发送指令，遇到可重试的失败按全局策略重发；`allow_side_effects` 为 true 时带副作用的指令也重试
+pub async fn send_with_retry(cmd_tx: &CommandSender, cmd: &str, allow_side_effects: bool) -> Result<ATResponse> {
+    let policy = policy();
+    let attempts = if allow_side_effects || !has_side_effects(cmd) { policy.attempts } else { 1 };
+
+    let mut attempt = 1;
+    loop {
+        let (tx, rx) = oneshot::channel();
+        cmd_tx.send((cmd.into(), tx)).await.map_err(|_| anyhow!("Failed to send command"))?;
+        let resp = rx.await.map_err(|_| anyhow!("Failed to receive response"))?;
+
+        if attempt >= attempts || !is_retryable(&resp) {
+            return Ok(resp);
+        }
+        debug!("{} failed transiently ({:?}), retrying {}/{}", cmd.trim(), resp.error, attempt + 1, attempts);
+        attempt += 1;
+        sleep(policy.delay).await;
+    }
+}
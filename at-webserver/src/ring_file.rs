@@ -0,0 +1,81 @@
+//! 定长记录的 JSON 文件持久化
+//!
+//! 来电记录与短信记录共用：内存里是按时间顺序的 `VecDeque`，超过上限丢弃最旧的，
+//! 开启持久化时每次变化整体写回文件，启动时读回。`what` 只用于日志。
+
+use log::{info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::VecDeque;
+
+/// 读取持久化的记录，文件不存在时为空；损坏的文件忽略，下次写入时覆盖。
+/// 只保留最新的 `cap` 条，上限调小后旧文件也不会超出。
+pub fn load<T: DeserializeOwned>(path: &str, cap: usize, what: &str) -> VecDeque<T> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => match serde_json::from_str::<VecDeque<T>>(&content) {
+            Ok(mut saved) => {
+                while saved.len() > cap {
+                    saved.pop_front();
+                }
+                info!("Restored {} {} entries", saved.len(), what);
+                saved
+            }
+            Err(e) => {
+                warn!("Ignoring corrupt {} {}: {}", what, path, e);
+                VecDeque::new()
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+        Err(e) => {
+            warn!("Failed to read {} {}: {}", what, path, e);
+            VecDeque::new()
+        }
+    }
+}
+
+/// 整体写回文件，失败只记日志
+pub fn save<T: Serialize>(path: &str, records: &VecDeque<T>, what: &str) {
+    match serde_json::to_string(records) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(path, json) {
+                warn!("Failed to persist {}: {}", what, e);
+            }
+        }
+        Err(e) => warn!("Failed to serialize {}: {}", what, e),
+    }
+}
+
+/// 追加一条，超过 `cap` 时丢弃最旧的
+pub fn push_capped<T>(records: &mut VecDeque<T>, cap: usize, item: T) {
+    while records.len() >= cap {
+        records.pop_front();
+    }
+    records.push_back(item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_keeps_newest() {
+        let path = std::env::temp_dir().join(format!("at-webserver-ring-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut records = VecDeque::new();
+        for i in 0..5 {
+            push_capped(&mut records, 3, i);
+        }
+        assert_eq!(records, [2, 3, 4]);
+
+        save(path, &records, "test history");
+        assert_eq!(load::<i32>(path, 3, "test history"), [2, 3, 4]);
+        // 上限调小后只读回最新的
+        assert_eq!(load::<i32>(path, 2, "test history"), [3, 4]);
+
+        std::fs::write(path, "not json").unwrap();
+        assert!(load::<i32>(path, 3, "test history").is_empty());
+        std::fs::remove_file(path).unwrap();
+        assert!(load::<i32>(path, 3, "test history").is_empty());
+    }
+}
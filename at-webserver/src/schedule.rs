@@ -1,41 +1,92 @@
 use crate::client::ATClient;
-use crate::config::ScheduleConfig;
+use crate::config::{Config, ScheduleConfig};
+use crate::dial_monitor;
+use crate::freq_lock::{build_lte_command, build_nr_command};
 use crate::models::ATResponse;
-use anyhow::{anyhow, Result};
+use crate::radio;
+use crate::retry;
+use anyhow::Result;
 use chrono::{Local, NaiveTime};
 use log::{error, info, warn, debug};
-use std::sync::Arc;
-use tokio::sync::oneshot;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::mpsc;
 use tokio::time::{sleep, Duration, Instant};
 
-pub async fn monitor_loop(client: Arc<ATClient>, config: ScheduleConfig) {
-    if !config.enabled {
+/// 监控循环的控制通道：收到新的定时锁频配置后立即重新判断并应用
+static CONTROL: OnceLock<mpsc::Sender<ScheduleConfig>> = OnceLock::new();
+
+/// 重新读取 UCI 配置并让监控循环立即重新判断当前时段（SCHEDULE_REEVALUATE / SIGHUP）
+pub async fn reload_and_reevaluate() -> Result<(), String> {
+    let config = tokio::task::spawn_blocking(Config::load)
+        .await
+        .map_err(|e| format!("Failed to load config: {}", e))?;
+    let tx = CONTROL.get().ok_or("Schedule monitor is not running")?;
+    tx.try_send(config.schedule_config).map_err(|_| "A re-evaluation is already pending".to_string())
+}
+
+/// 定时锁频关闭时挂起，直到收到启用了的配置；控制通道关闭时返回 None
+async fn wait_until_enabled(rx: &mut mpsc::Receiver<ScheduleConfig>, mut config: ScheduleConfig) -> Option<ScheduleConfig> {
+    while !config.enabled {
         debug!("Schedule frequency lock is disabled.");
-        return;
+        config = rx.recv().await?;
+    }
+    Some(config)
+}
+
+pub async fn monitor_loop(client: Arc<ATClient>, config: ScheduleConfig) {
+    let (control_tx, mut control_rx) = mpsc::channel(1);
+    let _ = CONTROL.set(control_tx);
+
+    if config.unlock_on_startup {
+        info!("Unlock on startup enabled, clearing any leftover frequency lock...");
+        match unlock_all(&client, &config).await {
+            Ok(()) => info!("Startup unlock completed."),
+            Err(e) => error!("Startup unlock failed: {}", e),
+        }
     }
 
+    // 重启后先记下模组里已生效的锁定，便于排查与定时锁频的预期是否一致
+    info!("Active frequency lock at startup: {}", crate::freq_lock::query(&client.get_sender()).await);
+
+    let Some(mut config) = wait_until_enabled(&mut control_rx, config).await else { return };
+
     info!("Starting schedule frequency lock monitor...");
     debug!("  Check interval: {}s", config.check_interval);
     debug!("  Timeout: {}s", config.timeout);
     debug!("  Night mode: {} ({}-{})", if config.night_enabled { "Enabled" } else { "Disabled" }, config.night_start, config.night_end);
     debug!("  Day mode: {}", if config.day_enabled { "Enabled" } else { "Disabled" });
+    debug!("  Minimum dwell: {}s", config.min_dwell);
 
     let mut last_service_time = Instant::now();
     let mut current_mode: Option<String> = None;
     let mut switch_count = 0;
+    // 最近一次成功切换的时间，用于最短停留期
+    let mut last_switch: Option<Instant> = None;
+    // 手动触发的重新判断：不受最短停留期限制，同一模式也按新配置重新下发
+    let mut forced = false;
 
     loop {
         // Determine current mode
         let target_mode = get_current_mode(&config);
 
-        if target_mode != current_mode {
-            if let Some(mode) = &target_mode {
+        let dwell_remaining = last_switch
+            .map(|t| Duration::from_secs(config.min_dwell).saturating_sub(t.elapsed()))
+            .filter(|d| !d.is_zero() && !forced);
+
+        if target_mode != current_mode || (forced && target_mode.is_some()) {
+            if let Some(remaining) = dwell_remaining {
+                info!(
+                    "Suppressing mode switch {:?} -> {:?}: minimum dwell not reached ({}s remaining)",
+                    current_mode, target_mode, remaining.as_secs()
+                );
+            } else if let Some(mode) = &target_mode {
                 debug!("Mode switch detected: {:?} -> {}", current_mode, mode);
                 switch_count += 1;
                 if let Err(e) = set_frequency_lock(&client, &config, mode, switch_count).await {
                     error!("Failed to set frequency lock for mode {}: {}", mode, e);
                 } else {
                     current_mode = Some(mode.clone());
+                    last_switch = Some(Instant::now());
                 }
             } else if current_mode.is_some() {
                 // Target is None (no lock needed), but we are in a mode. Unlock everything.
@@ -44,12 +95,13 @@ pub async fn monitor_loop(client: Arc<ATClient>, config: ScheduleConfig) {
                     error!("Failed to unlock all: {}", e);
                 } else {
                     current_mode = None;
+                    last_switch = Some(Instant::now());
                 }
             }
         }
 
         // Check network status
-        match check_network_status(&client).await {
+        match check_network_status(&client, &config).await {
             Ok(has_service) => {
                 if has_service {
                     last_service_time = Instant::now();
@@ -71,7 +123,23 @@ pub async fn monitor_loop(client: Arc<ATClient>, config: ScheduleConfig) {
             }
         }
 
-        sleep(Duration::from_secs(config.check_interval)).await;
+        forced = false;
+        tokio::select! {
+            _ = sleep(Duration::from_secs(config.check_interval)) => {}
+            Some(new_config) = control_rx.recv() => {
+                info!("Re-evaluating schedule frequency lock immediately");
+                if !new_config.enabled && current_mode.is_some() {
+                    info!("Schedule frequency lock disabled, unlocking all.");
+                    match unlock_all(&client, &new_config).await {
+                        Ok(()) => current_mode = None,
+                        Err(e) => error!("Failed to unlock all: {}", e),
+                    }
+                }
+                let Some(new_config) = wait_until_enabled(&mut control_rx, new_config).await else { return };
+                config = new_config;
+                forced = true;
+            }
+        }
     }
 }
 
@@ -102,24 +170,22 @@ fn get_current_mode(config: &ScheduleConfig) -> Option<String> {
     None
 }
 
-async fn check_network_status(client: &ATClient) -> Result<bool> {
-    // Check CREG
-    let resp = send_command(client, "AT+CREG?\r\n").await?;
-    if let Some(data) = resp.data {
-        if data.contains("+CREG: 0,1") || data.contains("+CREG: 0,5") {
-            return Ok(true);
+/// 按 `service_check` 判断是否有服务：registration 只看注册状态（CREG/CEREG/C5GREG 任一 stat 为 1/5），
+/// data 还要求 AT+CGPADDR 已分配到 IP
+async fn check_network_status(client: &ATClient, config: &ScheduleConfig) -> Result<bool> {
+    let mut registered = false;
+    for cmd in ["AT+CREG?\r\n", "AT+CEREG?\r\n", "AT+C5GREG?\r\n"] {
+        let resp = send_command(client, cmd).await?;
+        if resp.data.as_deref().is_some_and(dial_monitor::parse_registered) {
+            registered = true;
+            break;
         }
     }
 
-    // Check CEREG
-    let resp = send_command(client, "AT+CEREG?\r\n").await?;
-    if let Some(data) = resp.data {
-        if data.contains("+CEREG: 0,1") || data.contains("+CEREG: 0,5") {
-            return Ok(true);
-        }
+    if !registered || config.service_check != "data" {
+        return Ok(registered);
     }
-
-    Ok(false)
+    dial_monitor::has_data_ip(client).await
 }
 
 async fn unlock_all(client: &ATClient, config: &ScheduleConfig) -> Result<()> {
@@ -127,6 +193,9 @@ async fn unlock_all(client: &ATClient, config: &ScheduleConfig) -> Result<()> {
     // But since set_frequency_lock reads from config based on mode string, we should probably construct a manual unlock
     
     debug!("Unlocking all frequencies...");
+    let _radio_guard = radio::lock_radio().await;
+    // 调度器的每次改写（set_frequency_lock 锁定与这里的解锁）都会清除手动应用的锁频配置标记
+    crate::freq_lock::clear_active_profile();
     
     // Toggle airplane if configured
     if config.toggle_airplane {
@@ -135,19 +204,16 @@ async fn unlock_all(client: &ATClient, config: &ScheduleConfig) -> Result<()> {
         sleep(Duration::from_secs(2)).await;
     }
 
-    // Unlock LTE
-    debug!("Step 2: Unlock LTE...");
-    send_command(client, "AT^LTEFREQLOCK=0\r\n").await?;
-    sleep(Duration::from_secs(1)).await;
-
-    // Unlock NR
-    debug!("Step 3: Unlock NR...");
-    send_command(client, "AT^NRFREQLOCK=0\r\n").await?;
-    sleep(Duration::from_secs(1)).await;
+    // 依次下发配置的解锁指令（默认 LTE、NR 各一条）
+    for (i, cmd) in config.unlock_cmds.iter().enumerate() {
+        debug!("Step {}: {}", i + 2, cmd);
+        send_command(client, &format!("{}\r\n", cmd.trim())).await?;
+        sleep(Duration::from_secs(1)).await;
+    }
 
     // Exit airplane mode
     if config.toggle_airplane {
-        debug!("Step 4: Exit airplane mode...");
+        debug!("Step {}: Exit airplane mode...", config.unlock_cmds.len() + 2);
         send_command(client, "AT+CFUN=1\r\n").await?;
         sleep(Duration::from_secs(5)).await;
     }
@@ -159,6 +225,10 @@ async fn set_frequency_lock(client: &ATClient, config: &ScheduleConfig, mode: &s
     debug!("============================================================");
     info!("Switching to {} mode frequency lock (Count: {})", mode, switch_count);
     debug!("============================================================");
+    // 整段锁频序列期间独占射频操作，用户侧的 CFUN 会被拒绝
+    let _radio_guard = radio::lock_radio().await;
+    // 定时锁频会覆盖手动应用的锁频配置
+    crate::freq_lock::clear_active_profile();
 
     let (lte_type, lte_bands, lte_arfcns, lte_pcis, nr_type, nr_bands, nr_arfcns, nr_scs, nr_pcis) = if mode == "night" {
         (
@@ -260,73 +330,5 @@ async fn set_frequency_lock(client: &ATClient, config: &ScheduleConfig, mode: &s
 }
 
 async fn send_command(client: &ATClient, cmd: &str) -> Result<ATResponse> {
-    let (tx, rx) = oneshot::channel();
-    client.get_sender().send((cmd.to_string(), tx)).await.map_err(|_| anyhow!("Failed to send command"))?;
-    match rx.await {
-        Ok(resp) => Ok(resp),
-        Err(_) => Err(anyhow!("Failed to receive response")),
-    }
-}
-
-fn build_lte_command(lock_type: u8, bands: &[&str], arfcns: &str, pcis: &str) -> String {
-    // Type 1: Frequency point lock (Band + ARFCN)
-    // Type 2: Cell lock (Band + ARFCN + PCI)
-    // Type 3: Band lock (Band only)
-    
-    if lock_type == 3 {
-        // AT^LTEFREQLOCK=3,0,<count>,"<band1>,<band2>,..."
-        return format!("AT^LTEFREQLOCK=3,0,{},\"{}\"\r\n", bands.len(), bands.join(","));
-    } else if lock_type == 1 {
-        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        if bands.len() != arfcn_list.len() {
-            warn!("LTE Frequency Lock: Band count ({}) != ARFCN count ({}), unlocking", bands.len(), arfcn_list.len());
-            return "AT^LTEFREQLOCK=0\r\n".to_string();
-        }
-        // AT^LTEFREQLOCK=1,0,<count>,"<band1>,...","<arfcn1>,..."
-        return format!("AT^LTEFREQLOCK=1,0,{},\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","));
-    } else if lock_type == 2 {
-        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        let pci_list: Vec<&str> = pcis.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        
-        if bands.len() != arfcn_list.len() || arfcn_list.len() != pci_list.len() {
-            warn!("LTE Cell Lock: Count mismatch (Band:{}, ARFCN:{}, PCI:{}), unlocking", bands.len(), arfcn_list.len(), pci_list.len());
-            return "AT^LTEFREQLOCK=0\r\n".to_string();
-        }
-        // AT^LTEFREQLOCK=2,0,<count>,"<band1>,...","<arfcn1>,...","<pci1>,..."
-        return format!("AT^LTEFREQLOCK=2,0,{},\"{}\",\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","), pci_list.join(","));
-    }
-
-    "AT^LTEFREQLOCK=0\r\n".to_string()
-}
-
-fn build_nr_command(lock_type: u8, bands: &[&str], arfcns: &str, scs_types: &str, pcis: &str) -> String {
-    // Type 1: Frequency point lock (Band + ARFCN)
-    // Type 2: Cell lock (Band + ARFCN + SCS + PCI)
-    // Type 3: Band lock (Band only)
-
-    if lock_type == 3 {
-        // AT^NRFREQLOCK=3,0,<count>,"<band1>,..."
-        return format!("AT^NRFREQLOCK=3,0,{},\"{}\"\r\n", bands.len(), bands.join(","));
-    } else if lock_type == 1 {
-        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        if bands.len() != arfcn_list.len() {
-            warn!("NR Frequency Lock: Band count ({}) != ARFCN count ({}), unlocking", bands.len(), arfcn_list.len());
-            return "AT^NRFREQLOCK=0\r\n".to_string();
-        }
-        // AT^NRFREQLOCK=1,0,<count>,"<band1>,...","<arfcn1>,..."
-        return format!("AT^NRFREQLOCK=1,0,{},\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","));
-    } else if lock_type == 2 {
-        let arfcn_list: Vec<&str> = arfcns.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        let scs_list: Vec<&str> = scs_types.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-        let pci_list: Vec<&str> = pcis.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-
-        if bands.len() != arfcn_list.len() || arfcn_list.len() != scs_list.len() || scs_list.len() != pci_list.len() {
-            warn!("NR Cell Lock: Count mismatch (Band:{}, ARFCN:{}, SCS:{}, PCI:{}), unlocking", bands.len(), arfcn_list.len(), scs_list.len(), pci_list.len());
-            return "AT^NRFREQLOCK=0\r\n".to_string();
-        }
-        // AT^NRFREQLOCK=2,0,<count>,"<band1>,...","<arfcn1>,...","<scs1>,...","<pci1>,..."
-        return format!("AT^NRFREQLOCK=2,0,{},\"{}\",\"{}\",\"{}\",\"{}\"\r\n", bands.len(), bands.join(","), arfcn_list.join(","), scs_list.join(","), pci_list.join(","));
-    }
-
-    "AT^NRFREQLOCK=0\r\n".to_string()
+    retry::send_with_retry(&client.get_sender(), cmd, false).await
 }
@@ -1,15 +1,33 @@
+use crate::audit;
 use crate::client::ATClient;
+use crate::command_rules;
+use crate::config::{Config, WebSocketConfig};
+use crate::models::{AtCommand, ConnectionType};
+use crate::modem;
+use crate::network;
+use crate::pdcp;
+use crate::radio;
+use crate::signal;
+use crate::sim;
+use crate::sms_export::{self, ExportFormat};
+use crate::sms_index;
+use crate::urc::{self, UrcStream};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use futures::{SinkExt, StreamExt};
-use log::{error, info, debug, warn};
+use log::{error, info, debug, warn, Level, LevelFilter};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::io::Write;
 use tokio::sync::Mutex;
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
 use tokio::sync::{oneshot, broadcast};
 use tokio::time::{timeout, Duration};
-use warp::Filter;
+use warp::{Filter, Reply};
 use std::sync::OnceLock;
 
 pub static WS_BROADCASTER: OnceLock<broadcast::Sender<String>> = OnceLock::new();
@@ -18,11 +36,63 @@ pub static CLIENT_CONNECTIONS: OnceLock<Mutex<HashMap<std::net::IpAddr, tokio::s
 #[derive(Deserialize)]
 struct WSCommand {
     command: String,
+    /// 可选的单条指令响应超时（秒），范围见 `COMMAND_TIMEOUT_RANGE`
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    /// 可选的请求 id，原样回传到回复中，前端据此对应并发请求的回复
+    #[serde(default)]
+    id: Option<serde_json::Value>,
 }
 
+/// 前端可指定的指令超时范围（秒）
+const COMMAND_TIMEOUT_RANGE: std::ops::RangeInclusive<u64> = 1..=120;
+
+/// `BATCH:` 的参数：指令数组，或带 `stop_on_error` 的对象
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BatchRequest {
+    Commands(Vec<String>),
+    Options {
+        commands: Vec<String>,
+        #[serde(default)]
+        stop_on_error: bool,
+    },
+}
+
+/// 单次 BATCH 最多包含的指令数
+const MAX_BATCH_COMMANDS: usize = 50;
+
+/// 认证消息：plain 模式携带 auth_key，hmac 模式携带对挑战随机数的 auth_hmac
 #[derive(Deserialize)]
 struct AuthMessage {
-    auth_key: String,
+    #[serde(default)]
+    auth_key: Option<String>,
+    #[serde(default)]
+    auth_hmac: Option<String>,
+}
+
+/// 生成 hmac 认证的随机数（十六进制）
+fn auth_nonce() -> Option<String> {
+    let mut nonce = [0u8; 32];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut nonce).ok()?;
+    Some(hex::encode(nonce))
+}
+
+/// 校验认证消息。有随机数时只接受 HMAC-SHA256(key, nonce)，不接受明文密钥
+fn verify_auth(key: &str, nonce: Option<&str>, text: &str) -> bool {
+    let Ok(auth) = serde_json::from_str::<AuthMessage>(text) else {
+        return false;
+    };
+    match nonce {
+        Some(nonce) => {
+            let Some(tag) = auth.auth_hmac.and_then(|h| hex::decode(h.trim()).ok()) else {
+                return false;
+            };
+            let hmac_key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, key.as_bytes());
+            ring::hmac::verify(&hmac_key, nonce.as_bytes(), &tag).is_ok()
+        }
+        None => auth.auth_key.as_deref() == Some(key),
+    }
 }
 
 #[derive(Serialize)]
@@ -32,15 +102,64 @@ struct WSResponse {
     error: Option<String>,
 }
 
+/// 回复标记：seq 为本连接收到的第几条指令，id 为请求中携带的 id
+#[derive(Clone)]
+struct ReplyTag {
+    seq: u64,
+    id: Option<serde_json::Value>,
+    /// 审计日志用：发起的客户端与原始指令
+    client: Option<IpAddr>,
+    command: Arc<str>,
+}
+
+impl ReplyTag {
+    /// 同一请求中的单条指令（BATCH、多指令输入）：审计日志按实际执行的指令分别记录
+    fn for_command(&self, command: &str) -> ReplyTag {
+        ReplyTag { command: Arc::from(command), ..self.clone() }
+    }
+
+    /// 只写审计日志，不产生回复
+    fn record(&self, success: bool) {
+        audit::record(self.client, self.seq, &self.command, Some(success));
+    }
+
+    /// 在 JSON 对象回复中加入 seq 与 id 字段，非对象原样返回；同时写入审计日志
+    fn apply(&self, resp: String) -> String {
+        match serde_json::from_str::<serde_json::Value>(&resp) {
+            Ok(serde_json::Value::Object(mut obj)) => {
+                audit::record(self.client, self.seq, &self.command, obj.get("success").and_then(|s| s.as_bool()));
+                obj.insert("seq".to_string(), self.seq.into());
+                if let Some(id) = &self.id {
+                    obj.insert("id".to_string(), id.clone());
+                }
+                serde_json::Value::Object(obj).to_string()
+            }
+            _ => {
+                audit::record(self.client, self.seq, &self.command, None);
+                resp
+            }
+        }
+    }
+}
+
+impl WSResponse {
+    fn ok(data: String) -> Self {
+        Self { success: true, data: Some(data), error: None }
+    }
+
+    fn error(err: String) -> Self {
+        Self { success: false, data: None, error: Some(err) }
+    }
+}
+
 pub async fn start_server(
-    _ipv4_port: u16,
-    ipv6_port: u16,
     auth_key: Option<String>,
     at_client: ATClient,
     log_rx: broadcast::Receiver<String>,
     log_path: String,
+    config: Config,
 ) {
-    let (ws_tx, _) = broadcast::channel(100);
+    let (ws_tx, _) = broadcast::channel(config.websocket_config.broadcast_capacity);
     let _ = WS_BROADCASTER.set(ws_tx.clone());
     let _ = CLIENT_CONNECTIONS.set(Mutex::new(HashMap::new()));
 
@@ -48,25 +167,134 @@ pub async fn start_server(
     let auth_key = Arc::new(auth_key);
     let log_rx = Arc::new(log_rx);
     let log_path = Arc::new(log_path);
+    let metrics_token = Arc::new(config.websocket_config.metrics_token.clone());
+    let bind_addrs = resolve_bind_addrs(&config.websocket_config).await;
+    let fallback_port = config.websocket_config.ipv6.port;
+    let config = Arc::new(config);
 
     let at_client_filter = warp::any().map(move || at_client.clone());
     let auth_key_filter = warp::any().map(move || auth_key.clone());
     let log_rx_filter = warp::any().map(move || log_rx.clone());
     let log_path_filter = warp::any().map(move || log_path.clone());
+    let config_filter = warp::any().map(move || config.clone());
 
-    let routes = warp::path::end()
+    let ws_route = warp::path::end()
         .and(warp::ws())
         .and(warp::addr::remote())
         .and(at_client_filter)
         .and(auth_key_filter)
         .and(log_rx_filter)
         .and(log_path_filter)
-        .map(|ws: warp::ws::Ws, addr: Option<SocketAddr>, client, key, rx, path| {
-            ws.on_upgrade(move |socket| handle_client(socket, addr, client, key, rx, path))
+        .and(config_filter)
+        .map(|ws: warp::ws::Ws, addr: Option<SocketAddr>, client, key, rx, path, config| {
+            ws.on_upgrade(move |socket| handle_client(socket, addr, client, key, rx, path, config))
         });
 
-    info!("Starting WebSocket server on [::]:{} (Dual-stack IPv4 & IPv6)", ipv6_port);
-    warp::serve(routes).run(([0, 0, 0, 0, 0, 0, 0, 0], ipv6_port)).await;
+    // Prometheus 抓取入口，只读共享状态，不下发 AT 指令
+    let metrics_route = warp::path("metrics")
+        .and(warp::path::end())
+        .and(warp::get())
+        .and(warp::header::optional::<String>("authorization"))
+        .map(move |auth: Option<String>| {
+            let authorized = match metrics_token.as_ref() {
+                Some(token) => auth.as_deref().and_then(|a| a.strip_prefix("Bearer ")) == Some(token.as_str()),
+                None => true,
+            };
+            if !authorized {
+                return warp::reply::with_status("Unauthorized\n".to_string(), warp::http::StatusCode::UNAUTHORIZED).into_response();
+            }
+            warp::reply::with_header(crate::metrics::render(), "content-type", "text/plain; version=0.0.4").into_response()
+        });
+
+    let routes = ws_route.or(metrics_route);
+
+    let mut servers = Vec::new();
+    for addr in &bind_addrs {
+        match warp::serve(routes.clone()).try_bind_ephemeral(*addr) {
+            Ok((bound, server)) => {
+                info!("Starting WebSocket server on {}", bound);
+                servers.push(server);
+            }
+            Err(e) => error!("Failed to bind WebSocket server on {}: {}", addr, e),
+        }
+    }
+
+    if servers.is_empty() {
+        warn!("No configured WebSocket address could be bound, falling back to [::]:{}", fallback_port);
+        info!("Starting WebSocket server on [::]:{} (Dual-stack IPv4 & IPv6)", fallback_port);
+        warp::serve(routes).run((Ipv6Addr::UNSPECIFIED, fallback_port)).await;
+        return;
+    }
+    futures::future::join_all(servers).await;
+}
+
+/// 根据 IpConfig 的 host 计算监听地址
+///
+/// IPv6 为 `::` 且 IPv4 也是通配（或不监听 IPv4）时，双栈监听即可覆盖 IPv4（再单独绑定 0.0.0.0 会端口冲突）；
+/// IPv4 限定为具体地址（如只监听 LAN）时不再追加 `::`，否则会经 IPv6 在所有接口上暴露，需要 IPv6 时应填具体地址。
+/// 地址无法解析时告警并回退到 `[::]`。
+async fn resolve_bind_addrs(ws_config: &WebSocketConfig) -> Vec<SocketAddr> {
+    let dual_stack = SocketAddr::from((Ipv6Addr::UNSPECIFIED, ws_config.ipv6.port));
+    let v4_host = ws_config.ipv4.host.trim();
+    let mut v6_host = ws_config.ipv6.host.trim();
+
+    if v6_host == "::" {
+        if v4_host.is_empty() || v4_host == "0.0.0.0" {
+            return vec![dual_stack];
+        }
+        info!("WebSocket IPv4 host is restricted to {}, not listening on [::]; set a specific IPv6 host to accept IPv6", v4_host);
+        v6_host = "";
+    }
+
+    let mut addrs = Vec::new();
+    if !v4_host.is_empty() {
+        match v4_host.parse::<Ipv4Addr>() {
+            Ok(ip) => addrs.push(SocketAddr::from((ip, ws_config.ipv4.port))),
+            Err(_) => {
+                warn!("Invalid WebSocket IPv4 host '{}', falling back to [::]", v4_host);
+                return vec![dual_stack];
+            }
+        }
+    }
+    if !v6_host.is_empty() {
+        match parse_scoped_ipv6(v6_host).await {
+            Some((ip, scope_id)) => {
+                addrs.push(SocketAddr::V6(SocketAddrV6::new(ip, ws_config.ipv6.port, 0, scope_id)));
+            }
+            None => {
+                warn!("Invalid WebSocket IPv6 host '{}', falling back to [::]", v6_host);
+                return vec![dual_stack];
+            }
+        }
+    }
+
+    if addrs.is_empty() {
+        warn!("Both WebSocket hosts are empty, falling back to [::]");
+        return vec![dual_stack];
+    }
+    addrs
+}
+
+/// 解析 `fe80::1%br-lan` / `fe80::1%3` 形式的 IPv6 地址，作用域可为网卡名或索引
+async fn parse_scoped_ipv6(host: &str) -> Option<(Ipv6Addr, u32)> {
+    let (ip, scope) = match host.split_once('%') {
+        Some((ip, scope)) => (ip, Some(scope)),
+        None => (host, None),
+    };
+    let ip: Ipv6Addr = ip.parse().ok()?;
+    let scope_id = match scope {
+        None => 0,
+        Some(scope) => match scope.parse::<u32>() {
+            Ok(index) => index,
+            Err(_) => tokio::fs::read_to_string(format!("/sys/class/net/{}/ifindex", scope))
+                .await
+                .ok()?
+                .trim()
+                .parse()
+                .ok()?,
+        },
+    };
+    Some((ip, scope_id))
 }
 
 async fn handle_client(
@@ -76,20 +304,29 @@ async fn handle_client(
     auth_key: Arc<Option<String>>,
     log_rx: Arc<broadcast::Receiver<String>>,
     log_path: Arc<String>,
+    config: Arc<Config>,
 ) {
     // Authentication
     if let Some(key) = auth_key.as_ref() {
+        // hmac 模式先下发随机数，客户端回复 {"auth_hmac": hex(HMAC-SHA256(key, nonce))}
+        let nonce = if config.websocket_config.auth_mode == "hmac" {
+            let Some(nonce) = auth_nonce() else {
+                error!("Failed to generate WebSocket auth nonce");
+                let _ = ws.close().await;
+                return;
+            };
+            let challenge = json!({ "type": "auth_challenge", "algorithm": "HMAC-SHA256", "nonce": nonce });
+            if ws.send(warp::ws::Message::text(challenge.to_string())).await.is_err() {
+                return;
+            }
+            Some(nonce)
+        } else {
+            None
+        };
         match timeout(Duration::from_secs(10), ws.next()).await {
             Ok(Some(Ok(msg))) => {
                 if let Ok(text) = msg.to_str() {
-                    let mut authenticated = false;
-                    if let Ok(auth_data) = serde_json::from_str::<AuthMessage>(text) {
-                        if &auth_data.auth_key == key {
-                            authenticated = true;
-                        }
-                    }
-                    
-                    if authenticated {
+                    if verify_auth(key, nonce.as_deref(), text) {
                         let _ = ws.send(warp::ws::Message::text(json!({
                             "success": true,
                             "message": "认证成功"
@@ -133,10 +370,19 @@ async fn handle_client(
     let (mut tx, mut rx) = ws.split();
     let sender = at_client.get_sender();
     let mut log_rx = log_rx.resubscribe();
+    // 系统日志推送：默认不推，前端发 SET_LOG_LEVEL_FILTER:<level> 后只推该级别及以上的日志
+    let mut log_filter: Option<LevelFilter> = None;
+    // 响应回显清洗：默认取配置，前端可发 SET_ECHO_STRIP:<0|1> 按连接调整
+    let mut response_trim = command_rules::ResponseTrim {
+        strip_echo: config.at_config.strip_echo_default(crate::client::active_connection_type(&config.at_config)),
+    };
     let mut ws_raw_rx = WS_BROADCASTER.get().unwrap().subscribe();
 
     // 【步骤1】：新增一个专门用于异步接收后台 AT 指令结果的通道
-    let (conn_tx, mut conn_rx) = tokio::sync::mpsc::channel::<String>(32);
+    // 回复附带 seq（本连接收到的第几条指令）与请求 id，慢指令在后台执行期间其它指令的回复可能先到，
+    // 前端按 seq / id 对应请求
+    let (conn_tx, mut conn_rx) = tokio::sync::mpsc::channel::<(ReplyTag, String)>(32);
+    let mut seq: u64 = 0;
 
     // Highlander Rule: Kick old connections from same IP
     let (cmd_tx, mut cmd_rx) = tokio::sync::mpsc::unbounded_channel::<warp::ws::Message>();
@@ -164,15 +410,43 @@ async fn handle_client(
     loop {
         tokio::select! {
             // Handle global broadcast events (raw_data, new_sms, etc.)
-            Ok(broadcast_msg) = ws_raw_rx.recv() => {
-                 if let Err(e) = tx.send(warp::ws::Message::text(broadcast_msg)).await {
-                     debug!("Failed to send broadcast to WS: {}", e);
-                     break;
+            // 客户端消费太慢时广播通道会丢掉最旧的消息并返回 Lagged，记录后继续接收
+            result = ws_raw_rx.recv() => {
+                 match result {
+                     Ok(broadcast_msg) => {
+                         if let Err(e) = tx.send(warp::ws::Message::text(broadcast_msg)).await {
+                             debug!("Failed to send broadcast to WS: {}", e);
+                             break;
+                         }
+                     }
+                     Err(broadcast::error::RecvError::Lagged(n)) => {
+                         warn!("WebSocket client {:?} too slow, dropped {} broadcast messages", client_ip, n);
+                     }
+                     Err(broadcast::error::RecvError::Closed) => break,
+                 }
+            }
+            // 按本连接的级别过滤后推送系统日志
+            result = log_rx.recv(), if log_filter.is_some() => {
+                 match result {
+                     Ok(log_line) => {
+                         if log_line_level(&log_line).is_some_and(|level| log_filter.is_some_and(|f| level <= f)) {
+                             let msg = json!({ "type": "system_log", "data": log_line }).to_string();
+                             if let Err(e) = tx.send(warp::ws::Message::text(msg)).await {
+                                 debug!("Failed to send system log to WS: {}", e);
+                                 break;
+                             }
+                         }
+                     }
+                     Err(broadcast::error::RecvError::Lagged(n)) => {
+                         warn!("WebSocket client {:?} too slow, dropped {} system log lines", client_ip, n);
+                     }
+                     // 日志通道已关闭，停止推送
+                     Err(broadcast::error::RecvError::Closed) => log_filter = None,
                  }
             }
             // 【步骤2】：监听后台发回的异步 AT 指令结果，并秒发给前端
-            Some(resp_str) = conn_rx.recv() => {
-                 if let Err(e) = tx.send(warp::ws::Message::text(resp_str)).await {
+            Some((tag, resp_str)) = conn_rx.recv() => {
+                 if let Err(e) = tx.send(warp::ws::Message::text(tag.apply(resp_str))).await {
                      log::debug!("Failed to send async response to WS: {}", e);
                      break;
                  }
@@ -211,12 +485,16 @@ async fn handle_client(
 
                          // 3. 【终极容错解析】：智能判断并精准提取指令，绝不误伤指令自身的引号！
                          let mut cmd_str = String::new();
+                         let mut timeout_secs: Option<u64> = None;
+                         let mut req_id: Option<serde_json::Value> = None;
                          let text_trimmed = text.trim();
                          
-                         // 尝试 1：当作完整的 JSON 对象解析 (比如 {"command": "AT+CFUN=0"})
+                         // 尝试 1：当作完整的 JSON 对象解析 (比如 {"command": "AT+CFUN=0", "timeout_secs": 30})
                          if text_trimmed.starts_with('{') {
                              if let Ok(r) = serde_json::from_str::<WSCommand>(text_trimmed) {
                                  cmd_str = r.command;
+                                 timeout_secs = r.timeout_secs;
+                                 req_id = r.id;
                              }
                          }
                          
@@ -234,7 +512,9 @@ async fn handle_client(
                              continue;
                          }
 
-                         log::debug!("WS Command: {}", cmd_str);
+                         seq += 1;
+                         log::debug!("WS Command #{}: {}", seq, cmd_str);
+                         let tag = ReplyTag { seq, id: req_id, client: client_ip, command: Arc::from(cmd_str.as_str()) };
 
                          // 【新增】：哪怕前端包装成 JSON，只要解析出来是 ping，直接秒回 pong，绝不麻烦硬件！
                          if cmd_str.trim() == "ping" || cmd_str.trim().to_lowercase() == "keepalive" {
@@ -244,11 +524,21 @@ async fn handle_client(
 
                          if cmd_str.trim() == "AT+CONNECT?" {
                              let resp = WSResponse { success: true, data: Some("+CONNECT: 0\r\nOK".to_string()), error: None };
-                             let _ = tx.send(warp::ws::Message::text(serde_json::to_string(&resp).unwrap())).await;
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
                              continue;
                          }
                          
-                         if cmd_str.trim() == "GET_SYS_LOGS" {
+                         // GET_SYS_LOGS 返回全部日志；GET_SYS_LOGS:lines=200,gzip 只取最后 N 行，
+                         // gzip 时 data 为 gzip 压缩后的 base64 字符串
+                         if let Some(rest) = cmd_str.trim().strip_prefix("GET_SYS_LOGS").filter(|r| r.is_empty() || r.starts_with(':')) {
+                             let options = match SysLogOptions::parse(rest.trim_start_matches(':')) {
+                                 Ok(options) => options,
+                                 Err(e) => {
+                                     let resp = WSResponse::error(e);
+                                     let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                     continue;
+                                 }
+                             };
                              let content = match tokio::fs::read_to_string(log_path.as_str()).await {
                                  Ok(c) => if c.is_empty() { 
                                      "------ 暂无系统日志记录 ------".to_string() 
@@ -257,8 +547,83 @@ async fn handle_client(
                                  },
                                  Err(_) => "------ 系统日志文件暂未生成 ------".to_string(),
                              };
-                             let resp = WSResponse { success: true, data: Some(content), error: None };
-                             let _ = tx.send(warp::ws::Message::text(serde_json::to_string(&resp).unwrap())).await;
+                             let resp = match options.render(content) {
+                                 Ok(data) => WSResponse::ok(data),
+                                 Err(e) => WSResponse::error(format!("Failed to compress logs: {}", e)),
+                             };
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【日志订阅】：SET_LOG_LEVEL_FILTER:<error|warn|info|debug|off>，仅对本连接生效
+                         if let Some(level) = cmd_str.trim().strip_prefix("SET_LOG_LEVEL_FILTER:") {
+                             let resp = match level.trim().parse::<LevelFilter>() {
+                                 Ok(LevelFilter::Off) => {
+                                     log_filter = None;
+                                     WSResponse::ok("Log streaming disabled".to_string())
+                                 }
+                                 Ok(filter) => {
+                                     log_filter = Some(filter);
+                                     // 丢弃订阅前积压的日志，只推送之后产生的
+                                     log_rx = log_rx.resubscribe();
+                                     WSResponse::ok(format!("Log streaming filter set to {}", filter))
+                                 }
+                                 Err(_) => WSResponse::error(format!("Invalid log level: {}", level.trim())),
+                             };
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【回显清洗】：SET_ECHO_STRIP:<0|1>，仅对本连接生效；模组 ATE0 关闭回显时应设为 0
+                         if let Some(arg) = cmd_str.trim().strip_prefix("SET_ECHO_STRIP:") {
+                             let resp = match arg.trim() {
+                                 "1" | "0" => {
+                                     response_trim.strip_echo = arg.trim() == "1";
+                                     WSResponse::ok(json!({ "stripEcho": response_trim.strip_echo }).to_string())
+                                 }
+                                 _ => WSResponse::error("Usage: SET_ECHO_STRIP:<0|1>".to_string()),
+                             };
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【连接方式】：GET_CONNECTION 查询，SET_CONNECTION:<network|serial|simulation> 断开当前连接并改用另一种方式重连，
+                         // 串口/网络参数沿用配置，不写入 UCI，重启服务后恢复配置中的方式；切到 simulation 需开启 allow_inject_urc 或配置中即为模拟连接
+                         if cmd_str.trim() == "GET_CONNECTION" || cmd_str.trim().starts_with("SET_CONNECTION:") {
+                             if let Some(arg) = cmd_str.trim().strip_prefix("SET_CONNECTION:") {
+                                 let kind = match arg.trim().to_lowercase().as_str() {
+                                     "network" => ConnectionType::Network,
+                                     "serial" => ConnectionType::Serial,
+                                     "simulation" => ConnectionType::Simulation,
+                                     _ => {
+                                         let resp = WSResponse::error("Usage: SET_CONNECTION:<network|serial|simulation>".to_string());
+                                         let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                         continue;
+                                     }
+                                 };
+                                 if kind == ConnectionType::Simulation && !config.at_config.allows_simulation() {
+                                     let resp = WSResponse::error("Switching to simulation is disabled (set allow_inject_urc=1 to enable)".to_string());
+                                     let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                     continue;
+                                 }
+                                 crate::client::switch_connection(kind);
+                             }
+                             let kind = crate::client::active_connection_type(&config.at_config);
+                             let resp = WSResponse::ok(json!({ "connectionType": kind }).to_string());
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【配置排查】：返回守护进程实际加载的配置，密钥类字段已脱敏
+                         if cmd_str.trim() == "GET_CONFIG" {
+                             // connection_type 反映 SET_CONNECTION 切换后的实际连接方式
+                             let mut live = config.as_ref().clone();
+                             live.at_config.connection_type = crate::client::active_connection_type(&config.at_config);
+                             let resp = match serde_json::to_string(&live) {
+                                 Ok(json) => WSResponse::ok(json),
+                                 Err(e) => WSResponse::error(format!("Failed to serialize config: {}", e)),
+                             };
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
                              continue;
                          }
 
@@ -268,7 +633,7 @@ async fn handle_client(
                                  success, data: if success { Some("Logs cleared".to_string()) } else { None },
                                  error: if success { None } else { Some("Failed to clear logs".to_string()) },
                              };
-                             let _ = tx.send(warp::ws::Message::text(serde_json::to_string(&resp).unwrap())).await;
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
                              continue;
                          }
 
@@ -313,60 +678,632 @@ async fn handle_client(
                                      false
                                  };
 
-                                 let mut apply_error: Option<String> = None;
-                                 if success && key == "sms_storage" {
-                                     let cpms_cmd = format!(
-                                         "AT+CPMS=\"{}\",\"{}\",\"{}\"",
-                                         normalized_value, normalized_value, normalized_value
-                                     );
-                                     let (apply_tx, apply_rx) = oneshot::channel();
-                                     if sender.send((cpms_cmd.clone(), apply_tx)).await.is_err() {
-                                         apply_error = Some("Failed to send AT+CPMS command".to_string());
-                                     } else {
-                                         match apply_rx.await {
-                                             Ok(resp) if resp.success => {
-                                                 info!("sms_storage applied immediately via {}", cpms_cmd);
-                                             }
-                                             Ok(resp) => {
-                                                 apply_error = Some(resp.error.unwrap_or_else(|| "AT+CPMS failed".to_string()));
-                                             }
-                                             Err(_) => {
-                                                 apply_error = Some("Failed to receive AT+CPMS response".to_string());
+                                 // 立即下发 AT+CPMS 生效，放到后台执行，不阻塞本连接的其它指令
+                                 let sender_clone = sender.clone();
+                                 let conn_tx_clone = conn_tx.clone();
+                                 tokio::spawn(async move {
+                                     let mut apply_error: Option<String> = None;
+                                     if success && key == "sms_storage" {
+                                         let cpms_cmd = format!(
+                                             "AT+CPMS=\"{}\",\"{}\",\"{}\"",
+                                             normalized_value, normalized_value, normalized_value
+                                         );
+                                         let (apply_tx, apply_rx) = oneshot::channel();
+                                         if sender_clone.send((cpms_cmd.clone().into(), apply_tx)).await.is_err() {
+                                             apply_error = Some("Failed to send AT+CPMS command".to_string());
+                                         } else {
+                                             match apply_rx.await {
+                                                 Ok(resp) if resp.success => {
+                                                     info!("sms_storage applied immediately via {}", cpms_cmd);
+                                                 }
+                                                 Ok(resp) => {
+                                                     apply_error = Some(resp.error.unwrap_or_else(|| "AT+CPMS failed".to_string()));
+                                                 }
+                                                 Err(_) => {
+                                                     apply_error = Some("Failed to receive AT+CPMS response".to_string());
+                                                 }
                                              }
                                          }
                                      }
+
+                                     let resp = WSResponse {
+                                         success: success && apply_error.is_none(),
+                                         data: if success && apply_error.is_none() {
+                                             Some(format!("{}={} saved and applied", key, normalized_value))
+                                         } else if success {
+                                             Some(format!("{}={} saved", key, normalized_value))
+                                         } else {
+                                             None
+                                         },
+                                         error: if !success {
+                                             Some(format!("Failed to save {}", key))
+                                         } else {
+                                             apply_error
+                                         },
+                                     };
+                                     let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                                 });
+                             }
+                             continue;
+                         }
+
+                         // 【射频开关】：GET_RADIO_STATE 查询，SET_RADIO_STATE:<0|1|4> 设置并回读
+                         if cmd_str.trim() == "GET_RADIO_STATE" || cmd_str.trim().starts_with("SET_RADIO_STATE:") {
+                             let target = cmd_str.trim().strip_prefix("SET_RADIO_STATE:").map(|m| m.trim().parse::<u8>());
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let result = match target {
+                                     None => radio::query_radio_state(&client).await,
+                                     Some(Ok(mode)) => radio::set_radio_state(&client, mode).await,
+                                     Some(Err(_)) => Err(anyhow::anyhow!("Invalid radio mode, expected 0, 1 or 4")),
+                                 };
+                                 let resp = match result {
+                                     Ok(mode) => WSResponse::ok(radio::radio_state_json(mode)),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【双卡槽】：GET_SIM_SLOTS 查询，SET_SIM_SLOT:<n>,confirm 切换（会重启射频，必须带 confirm）
+                         if cmd_str.trim() == "GET_SIM_SLOTS" || cmd_str.trim().starts_with("SET_SIM_SLOT:") {
+                             let target = cmd_str.trim().strip_prefix("SET_SIM_SLOT:").map(|arg| {
+                                 let (slot, flag) = arg.split_once(',').unwrap_or((arg, ""));
+                                 match slot.trim().parse::<u8>() {
+                                     Ok(slot) if flag.trim() == "confirm" => Ok(slot),
+                                     Ok(slot) => Err(format!(
+                                         "Switching SIM slot resets the radio; resend as SET_SIM_SLOT:{},confirm", slot
+                                     )),
+                                     Err(_) => Err(format!("Invalid SIM slot: {}", slot.trim())),
                                  }
+                             });
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let result = match target {
+                                     None => sim::query_sim_slots(&client).await,
+                                     Some(Ok(slot)) => sim::set_sim_slot(&client, slot).await,
+                                     Some(Err(e)) => Err(anyhow::anyhow!(e)),
+                                 };
+                                 let resp = match result {
+                                     Ok(state) => WSResponse::ok(serde_json::to_string(&state).unwrap()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
 
-                                 let resp = WSResponse {
-                                     success: success && apply_error.is_none(),
-                                     data: if success && apply_error.is_none() {
-                                         Some(format!("{}={} saved and applied", key, normalized_value))
-                                     } else if success {
-                                         Some(format!("{}={} saved", key, normalized_value))
-                                     } else {
-                                         None
+                         // 【载波聚合】：GET_CA_INFO 返回各成员载波的频段、带宽与信号，单载波时 active 为 false
+                         if cmd_str.trim() == "GET_CA_INFO" {
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match signal::query_ca_info(&client).await {
+                                     Ok(info) => WSResponse::ok(serde_json::to_string(&info).unwrap()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【运营商】：GET_OPERATOR 返回 {operator, plmn, rat, registration}，数字 PLMN 按内置表换算名称
+                         if cmd_str.trim() == "GET_OPERATOR" {
+                             let cmd_tx = sender.clone();
+                             let config = config.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match crate::operator::query(&cmd_tx, &config.at_config.operator_names).await {
+                                     Ok(info) => WSResponse::ok(serde_json::to_string(&info).unwrap()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【状态汇总】：GET_STATUS 一次返回连接、注册、运营商、制式、信号格数/RSRP/SINR 与拨号 IP，
+                         // 优先使用缓存，缺失时才补查
+                         if cmd_str.trim() == "GET_STATUS" {
+                             let cmd_tx = sender.clone();
+                             let config = config.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let status = crate::status::collect(&cmd_tx, &config.at_config.operator_names).await;
+                                 let resp = WSResponse::ok(status.to_string());
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【PDP 上下文】：GET_CONTEXTS 返回 AT+CGDCONT? 中定义的全部上下文与 APN
+                         if cmd_str.trim() == "GET_CONTEXTS" {
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match crate::pdp::query_contexts(&cmd_tx).await {
+                                     Ok(contexts) => WSResponse::ok(serde_json::to_string(&contexts).unwrap()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【锁频状态】：GET_FREQ_LOCK 返回模组当前生效的 LTE/NR 锁频、锁小区设置 {lte, nr}，重启后也能看到实际锁定
+                         if cmd_str.trim() == "GET_FREQ_LOCK" {
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let data = crate::freq_lock::query(&cmd_tx).await;
+                                 let resp = if data["lte"].is_null() && data["nr"].is_null() {
+                                     WSResponse { success: false, data: Some(data.to_string()), error: Some("Failed to query frequency lock".to_string()) }
+                                 } else {
+                                     WSResponse::ok(data.to_string())
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【IMS 状态】：GET_IMS_STATUS 查询 VoLTE 注册状态，模组不支持时返回 unsupported
+                         if cmd_str.trim() == "GET_IMS_STATUS" {
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match crate::ims::query(&cmd_tx).await {
+                                     Ok(Some(status)) => WSResponse::ok(serde_json::to_string(&status).unwrap()),
+                                     Ok(None) => WSResponse::error("IMS status is unsupported on this modem".to_string()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【PDCP 统计】：主动查询一次 ^PDCPDATAINFO，无需等待上报
+                         if cmd_str.trim() == "GET_PDCP_INFO" {
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match pdcp::query_pdcp_info(&client).await {
+                                     Ok(infos) => WSResponse::ok(serde_json::to_string(&infos).unwrap()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【模组日志】：GET_MODEM_LOG 读取、CLEAR_MODEM_LOG 清空模组内部日志（按型号启用，区别于本服务的 GET_SYS_LOGS）
+                         if cmd_str.trim() == "GET_MODEM_LOG" || cmd_str.trim() == "CLEAR_MODEM_LOG" {
+                             let clear = cmd_str.trim() == "CLEAR_MODEM_LOG";
+                             let client = at_client.clone();
+                             let config = config.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = if clear {
+                                     match modem::clear_log(&client, &config.at_config).await {
+                                         Ok(()) => WSResponse::ok("Modem log cleared".to_string()),
+                                         Err(e) => WSResponse::error(e.to_string()),
+                                     }
+                                 } else {
+                                     match modem::read_log(&client, &config.at_config).await {
+                                         Ok(entries) => WSResponse::ok(serde_json::to_string(&entries).unwrap()),
+                                         Err(e) => WSResponse::error(e.to_string()),
+                                     }
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【指示灯】：GET_LED 查询、SET_LED:<mode> 设置模组指示灯，指令由配置提供，未配置或模组报错时返回 unsupported
+                         if cmd_str.trim() == "GET_LED" || cmd_str.trim().starts_with("SET_LED:") {
+                             let mode = cmd_str.trim().strip_prefix("SET_LED:").map(|m| m.trim().to_string());
+                             let client = at_client.clone();
+                             let config = config.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let result = match &mode {
+                                     Some(mode) => modem::set_led(&client, &config.at_config, mode).await,
+                                     None => Ok(()),
+                                 };
+                                 // 设置后回读一次，返回实际生效的状态；没有配置查询指令时只回显设置的值
+                                 let resp = match result {
+                                     Ok(()) => match modem::query_led(&client, &config.at_config).await {
+                                         Ok(values) => WSResponse::ok(json!({ "mode": values.first(), "values": values }).to_string()),
+                                         Err(e) if mode.is_some() => WSResponse::ok(json!({ "mode": mode, "verified": false, "error": e.to_string() }).to_string()),
+                                         Err(e) => WSResponse::error(e.to_string()),
                                      },
-                                     error: if !success {
-                                         Some(format!("Failed to save {}", key))
-                                     } else {
-                                         apply_error
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【短信记录】：GET_SMS_HISTORY，本服务收到过的短信（时间倒序），与模组存储无关
+                         if cmd_str.trim() == "GET_SMS_HISTORY" {
+                             let resp = WSResponse::ok(serde_json::to_string(&crate::sms_history::history()).unwrap());
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【来电记录】：GET_CALL_HISTORY，最近的来电（时间倒序）
+                         if cmd_str.trim() == "GET_CALL_HISTORY" {
+                             let resp = WSResponse::ok(serde_json::to_string(&crate::calls::history()).unwrap());
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【上报开关】：GET_URC_STATE 查询，SET_URC:<pdcp|signal>=<0|1> 开关对应上报与广播
+                         if cmd_str.trim() == "GET_URC_STATE" {
+                             let resp = WSResponse::ok(urc::state_json());
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+                         if let Some(arg) = cmd_str.trim().strip_prefix("SET_URC:") {
+                             let parsed = arg.split_once('=').and_then(|(name, value)| {
+                                 let enable = match value.trim() {
+                                     "1" => true,
+                                     "0" => false,
+                                     _ => return None,
+                                 };
+                                 Some((UrcStream::from_name(name)?, enable))
+                             });
+                             let Some((stream, enable)) = parsed else {
+                                 let resp = WSResponse::error("Usage: SET_URC:<pdcp|signal>=<0|1>".to_string());
+                                 let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                 continue;
+                             };
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match urc::set_enabled(&client, stream, enable).await {
+                                     Ok(()) => WSResponse::ok(urc::state_json()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【吞吐量历史】：最近的平滑上下行速率，供前端画图
+                         if cmd_str.trim() == "GET_THROUGHPUT_HISTORY" {
+                             let resp = WSResponse::ok(serde_json::to_string(&pdcp::throughput_history()).unwrap());
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【网络重配】：APPLY_NETWORK 重新读取 UCI 配置，在当前识别到的网卡上强制重跑网络配置
+                         if cmd_str.trim() == "APPLY_NETWORK" {
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = apply_network().await;
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【定时锁频】：SCHEDULE_REEVALUATE 重新读取 UCI 中的定时锁频配置并立即按当前时段应用，不必等下一个检测周期
+                         if cmd_str.trim() == "SCHEDULE_REEVALUATE" {
+                             let resp = match crate::schedule::reload_and_reevaluate().await {
+                                 Ok(()) => WSResponse::ok("Schedule re-evaluation triggered".to_string()),
+                                 Err(e) => WSResponse::error(e),
+                             };
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+
+                         // 【锁频配置】：LIST_PROFILES 列出配置的锁频方案，APPLY_PROFILE:<name> 按需应用，CLEAR_PROFILE 解除 LTE/NR 锁定，
+                         // 应用后回读校验，返回下发的指令、回读结果与 verified；与定时锁频独立，定时锁频下次切换时段仍会覆盖
+                         if cmd_str.trim() == "LIST_PROFILES" {
+                             let resp = WSResponse::ok(json!({
+                                 "profiles": config.schedule_config.lock_profiles,
+                                 "active": crate::freq_lock::active_profile(),
+                                 "scheduleEnabled": config.schedule_config.enabled,
+                             }).to_string());
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+                         if cmd_str.trim() == "CLEAR_PROFILE" || cmd_str.trim().starts_with("APPLY_PROFILE:") {
+                             let profile = match cmd_str.trim().strip_prefix("APPLY_PROFILE:").map(str::trim) {
+                                 Some(name) => match config.schedule_config.lock_profiles.iter().find(|p| p.name == name) {
+                                     Some(profile) => Some(profile.clone()),
+                                     None => {
+                                         let resp = WSResponse::error(format!("Unknown lock profile: {}", name));
+                                         let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                         continue;
+                                     }
+                                 },
+                                 None => None,
+                             };
+                             let toggle_airplane = config.schedule_config.toggle_airplane;
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let result = match &profile {
+                                     Some(profile) => crate::freq_lock::apply_profile(&cmd_tx, profile, toggle_airplane).await,
+                                     None => crate::freq_lock::clear_profile(&cmd_tx, toggle_airplane).await,
+                                 };
+                                 let resp = if result["verified"] == json!(true) {
+                                     WSResponse::ok(result.to_string())
+                                 } else {
+                                     WSResponse { success: false, data: Some(result.to_string()), error: Some("Frequency lock readback does not match".to_string()) }
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【链路时延】：PING:<host>[,<count>]，经模组网卡 ping 目标，返回丢包率与 min/avg/max（毫秒），count 最多 10
+                         if let Some(arg) = cmd_str.trim().strip_prefix("PING:") {
+                             let request = match crate::ping::PingRequest::parse(arg) {
+                                 Ok(request) => request,
+                                 Err(e) => {
+                                     let resp = WSResponse::error(e.to_string());
+                                     let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                     continue;
+                                 }
+                             };
+                             let configured_ifname = config.advanced_network_config.ifname.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 // 网卡不存在（未拨号或探测失败）时走默认路由
+                                 let ifname = crate::dial_monitor::detect_modem_ifname(&configured_ifname).await;
+                                 let interface = tokio::fs::metadata(format!("/sys/class/net/{}", ifname)).await.ok().map(|_| ifname);
+                                 let resp = match crate::ping::run(&request, interface.as_deref()).await {
+                                     Ok(stats) => {
+                                         let mut data = json!(stats);
+                                         data["host"] = json!(request.host);
+                                         data["interface"] = json!(interface);
+                                         WSResponse::ok(data.to_string())
+                                     }
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【短信列表】：LIST_SMS 或 LIST_SMS:<SM|ME>，长短信已按分段归并
+                         if let Some(rest) = cmd_str.trim().strip_prefix("LIST_SMS").filter(|r| r.is_empty() || r.starts_with(':')) {
+                             let mem = rest.trim_start_matches(':').trim().to_uppercase();
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = list_sms(&client, &mem).await;
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【短信导出】：EXPORT_SMS:<json|csv>，存储区与接收记录合并去重，
+                         // 回复 {format,count,chunk,chunks,content}，内容较大时分多条按 chunk 顺序拼接
+                         if let Some(arg) = cmd_str.trim().strip_prefix("EXPORT_SMS:") {
+                             let Some(format) = ExportFormat::from_name(arg) else {
+                                 let resp = WSResponse::error(format!("Invalid export format: {}, expected json or csv", arg.trim()));
+                                 let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                 continue;
+                             };
+                             let client = at_client.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 for resp in export_sms(&client, format).await {
+                                     if conn_tx_clone.send((tag.clone(), serde_json::to_string(&resp).unwrap())).await.is_err() {
+                                         break;
+                                     }
+                                 }
+                             });
+                             continue;
+                         }
+
+                         // 【读取短信】：READ_SMS:<id> 按 COMPACT_SMS 分配的逻辑 id 读取并解码一条短信，不删除，索引已被复用时拒绝
+                         if let Some(arg) = cmd_str.trim().strip_prefix("READ_SMS:") {
+                             let Ok(id) = arg.trim().parse::<u32>() else {
+                                 let resp = WSResponse::error(format!("Invalid SMS id: {}", arg.trim()));
+                                 let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                 continue;
+                             };
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match sms_index::read(&cmd_tx, id).await {
+                                     Ok((index, crate::pdu::IncomingMessage::Sms(sms))) => WSResponse::ok(
+                                         json!({
+                                             "id": id,
+                                             "index": index,
+                                             "sender": sms.sender,
+                                             "content": sms.content,
+                                             "timestamp": crate::sms::format_timestamp(&sms.date),
+                                             "timeOriginal": crate::sms::original_timestamp(&sms.date),
+                                             "timeUtc": crate::sms::utc_timestamp(&sms.date),
+                                             "partNumber": sms.partial_info.as_ref().map(|p| p.part_number),
+                                             "partsCount": sms.partial_info.as_ref().map(|p| p.parts_count),
+                                         })
+                                         .to_string(),
+                                     ),
+                                     Ok((index, crate::pdu::IncomingMessage::MmsNotification(mms))) => WSResponse::ok(
+                                         json!({
+                                             "id": id,
+                                             "index": index,
+                                             "sender": mms.sender,
+                                             "content": mms.content_location,
+                                             "timestamp": crate::sms::format_timestamp(&mms.date),
+                                             "mms": true,
+                                         })
+                                         .to_string(),
+                                     ),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【短信整理】：COMPACT_SMS 重新读取并返回逻辑 id 与存储索引的映射，
+                         // DELETE_SMS:<id> 按逻辑 id 删除，索引已被复用时拒绝
+                         if cmd_str.trim() == "COMPACT_SMS" || cmd_str.trim().starts_with("DELETE_SMS:") {
+                             let target = cmd_str.trim().strip_prefix("DELETE_SMS:").map(|id| id.trim().parse::<u32>());
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match target {
+                                     None => match sms_index::compact(&cmd_tx).await {
+                                         Ok(slots) => WSResponse::ok(serde_json::to_string(&slots).unwrap()),
+                                         Err(e) => WSResponse::error(e.to_string()),
                                      },
+                                     Some(Ok(id)) => match sms_index::delete(&cmd_tx, id).await {
+                                         Ok(index) => WSResponse::ok(format!("Deleted SMS id {} (index {})", id, index)),
+                                         Err(e) => WSResponse::error(e.to_string()),
+                                     },
+                                     Some(Err(_)) => WSResponse::error("Invalid SMS id".to_string()),
                                  };
-                                 let _ = tx.send(warp::ws::Message::text(serde_json::to_string(&resp).unwrap())).await;
-                             }
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【短信格式】：SET_SMS_MODE:<pdu|text>（或 0/1）。本服务读取短信前会自动切回 PDU 模式
+                         if let Some(arg) = cmd_str.trim().strip_prefix("SET_SMS_MODE:") {
+                             let mode = match arg.trim().to_lowercase().as_str() {
+                                 "pdu" | "0" => Some(0),
+                                 "text" | "1" => Some(1),
+                                 _ => None,
+                             };
+                             let Some(mode) = mode else {
+                                 let resp = WSResponse::error(format!("Invalid SMS mode: {}, expected pdu or text", arg.trim()));
+                                 let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                 continue;
+                             };
+                             let cmd_tx = at_client.get_sender();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let resp = match crate::sms::set_sms_mode(&cmd_tx, mode).await {
+                                     Ok(()) => WSResponse::ok(json!({ "mode": mode }).to_string()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【短信中心】：GET_SMSC 查询，SET_SMSC:<number> 设置并回读，带 + 的号码按国际格式（145）下发
+                         if cmd_str.trim() == "GET_SMSC" || cmd_str.trim().starts_with("SET_SMSC:") {
+                             let number = cmd_str.trim().strip_prefix("SET_SMSC:").map(|n| n.trim().to_string());
+                             let cmd_tx = at_client.get_sender();
+                             let conn_tx_clone = conn_tx.clone();
+                             tokio::spawn(async move {
+                                 let result = match number {
+                                     Some(number) => crate::sms::set_smsc(&cmd_tx, &number).await,
+                                     None => crate::sms::query_smsc(&cmd_tx).await,
+                                 };
+                                 let resp = match result {
+                                     Ok(smsc) => WSResponse::ok(smsc.to_string()),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 };
+                                 let _ = conn_tx_clone.send((tag, serde_json::to_string(&resp).unwrap())).await;
+                             });
+                             continue;
+                         }
+
+                         // 【模拟上报】：INJECT_URC:<行>，调试前端用，需在配置中开启 allow_inject_urc 或把 connection_type 配置为 SIMULATION
+                         if let Some(line) = cmd_str.trim_start().strip_prefix("INJECT_URC:") {
+                             let resp = if !config.at_config.allows_simulation() {
+                                 WSResponse::error("INJECT_URC is disabled (set allow_inject_urc=1 to enable)".to_string())
+                             } else {
+                                 match crate::client::inject_urc(line).await {
+                                     Ok(()) => WSResponse::ok(format!("Injected: {}", line.trim())),
+                                     Err(e) => WSResponse::error(e.to_string()),
+                                 }
+                             };
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
                              continue;
                          }
 
-                         if cmd_str.starts_with("AT^SYSCFGEX") {
-                             cmd_str = cmd_str.replace('\n', "").replace('\r', "").replace("OK", "");
-                             if cmd_str.contains(",\"\",\"\"") {
-                                 let parts: Vec<&str> = cmd_str.split(',').collect();
-                                 if parts.len() >= 5 {
-                                     let bands = parts[4].trim_matches('"');
-                                     cmd_str = format!("{},{},{},{},\"{}\",\"\",\"\"", parts[0], parts[1], parts[2], parts[3], bands);
+                         // 【批量指令】：BATCH:["AT+CSQ","AT+COPS?"] 或 BATCH:{"commands":[...],"stop_on_error":true}，
+                         // 按顺序逐条执行，每条的回复单独下发并带 index，最后一条回复带 batchComplete 汇总
+                         if let Some(arg) = cmd_str.trim().strip_prefix("BATCH:") {
+                             let (commands, stop_on_error) = match serde_json::from_str::<BatchRequest>(arg) {
+                                 Ok(BatchRequest::Commands(commands)) => (commands, false),
+                                 Ok(BatchRequest::Options { commands, stop_on_error }) => (commands, stop_on_error),
+                                 Err(e) => {
+                                     let resp = WSResponse::error(format!("Invalid BATCH payload: {}", e));
+                                     let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                     continue;
                                  }
+                             };
+                             if commands.is_empty() || commands.len() > MAX_BATCH_COMMANDS {
+                                 let resp = WSResponse::error(format!("BATCH expects 1-{} commands, got {}", MAX_BATCH_COMMANDS, commands.len()));
+                                 let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                 continue;
                              }
-                             cmd_str.push('\r');
+                             let cmd_tx = sender.clone();
+                             let conn_tx_clone = conn_tx.clone();
+                             let options = BatchOptions {
+                                 stop_on_error,
+                                 lenient: config.at_config.lenient_commands,
+                                 trim: response_trim,
+                                 defer_timeout: Duration::from_secs(config.at_config.radio_defer_timeout),
+                             };
+                             tokio::spawn(async move {
+                                 run_batch(&cmd_tx, commands, options, &conn_tx_clone, tag).await;
+                             });
+                             continue;
+                         }
+
+                         // 【多指令输入】：配置了 command_delimiter 时按分隔符（引号内不拆）拆成多条依次执行，
+                         // 全部完成后一次回复 results 数组；拆出的每条仍单独规范化并校验控制字符
+                         if !config.at_config.command_delimiter.is_empty() {
+                             let commands = command_rules::split_commands(&cmd_str, &config.at_config.command_delimiter);
+                             if commands.len() > MAX_BATCH_COMMANDS {
+                                 let resp = WSResponse::error(format!("At most {} commands per message, got {}", MAX_BATCH_COMMANDS, commands.len()));
+                                 let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                 continue;
+                             }
+                             if commands.len() > 1 {
+                                 let cmd_tx = sender.clone();
+                                 let conn_tx_clone = conn_tx.clone();
+                                 let options = BatchOptions {
+                                     stop_on_error: false,
+                                     lenient: config.at_config.lenient_commands,
+                                     trim: response_trim,
+                                     defer_timeout: Duration::from_secs(config.at_config.radio_defer_timeout),
+                                 };
+                                 tokio::spawn(async move {
+                                     run_split_commands(&cmd_tx, commands, options, &conn_tx_clone, tag).await;
+                                 });
+                                 continue;
+                             }
+                         }
+
+                         if config.at_config.lenient_commands {
+                             let normalized = command_rules::normalize_lenient(&cmd_str);
+                             if normalized != cmd_str.trim() {
+                                 debug!("Normalized command {:?} -> {:?}", cmd_str, normalized);
+                             }
+                             cmd_str = normalized;
+                         }
+                         cmd_str = command_rules::rewrite_request(cmd_str);
+                         // 审计日志记录规范化后实际下发的指令
+                         let tag = tag.for_command(&cmd_str);
+                         if let Err(e) = command_rules::sanitize_command(&cmd_str) {
+                             warn!("Rejected WebSocket command {:?}: {}", cmd_str, e);
+                             let resp = WSResponse::error(e);
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
+                         }
+                         if let Some(secs) = timeout_secs.filter(|s| !COMMAND_TIMEOUT_RANGE.contains(s)) {
+                             let resp = WSResponse::error(format!(
+                                 "Invalid timeout_secs {}, expected {}-{}",
+                                 secs, COMMAND_TIMEOUT_RANGE.start(), COMMAND_TIMEOUT_RANGE.end()
+                             ));
+                             let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                             continue;
                          }
                          
                          // 【异步并发】：将指令发给后端执行，主循环立刻回头去接客，绝不卡死 WebSocket！
@@ -375,37 +1312,74 @@ async fn handle_client(
                          // 我们这里使用之前创建的 conn_tx 通道将结果发回主循环，由主循环统一发送给 WebSocket。
                          let conn_tx_clone = conn_tx.clone();
                          let cmd_for_task = cmd_str.clone();
+                         let trim = response_trim;
+                         
+                         // 终端直发的指令与定时锁频序列互斥：序列进行中提交的指令排队等序列结束再执行，
+                         // 未开启排队时 CFUN 直接拒绝
+                         let is_cfun = radio::is_cfun_set(&cmd_str);
+                         let defer_timeout = Duration::from_secs(config.at_config.radio_defer_timeout);
+                         let deferred = radio::is_busy() && !defer_timeout.is_zero();
+                         let radio_guard = if deferred {
+                             None
+                         } else if is_cfun {
+                             match radio::try_lock_radio() {
+                                 Some(guard) => Some(guard),
+                                 None => {
+                                     let resp = WSResponse::error(radio::RADIO_BUSY.to_string());
+                                     let _ = tx.send(warp::ws::Message::text(tag.apply(serde_json::to_string(&resp).unwrap()))).await;
+                                     continue;
+                                 }
+                             }
+                         } else {
+                             None
+                         };
                          
                          tokio::spawn(async move {
+                             let mut _radio_guard = radio_guard;
+                             if deferred {
+                                 info!("Deferring command until the radio operation completes: {}", cmd_for_task);
+                                 match radio::wait_radio_idle(defer_timeout).await {
+                                     // CFUN 执行期间继续持锁，其它指令拿到锁即说明序列已结束
+                                     Some(guard) if is_cfun => _radio_guard = Some(guard),
+                                     Some(_) => {}
+                                     None => {
+                                         let resp = json!({ "success": false, "error": radio::RADIO_BUSY, "deferred": true });
+                                         let _ = conn_tx_clone.send((tag, resp.to_string())).await;
+                                         return;
+                                     }
+                                 }
+                             }
                              let (resp_tx, resp_rx) = oneshot::channel();
-                             if let Err(e) = sender_clone.send((cmd_for_task.clone(), resp_tx)).await {
+                             let at_cmd = AtCommand {
+                                 cmd: cmd_for_task.clone(),
+                                 timeout: timeout_secs.map(Duration::from_secs),
+                             };
+                             if let Err(e) = sender_clone.send((at_cmd, resp_tx)).await {
                                  error!("Failed to send command to actor: {}", e);
                                  return;
                              }
 
                              match resp_rx.await {
                                  Ok(response) => {
-                                     let mut filtered_data = response.data.clone();
-                                     if let Some(data) = &filtered_data {
-                                         let clean_cmd = cmd_for_task.trim();
-                                         let lines: Vec<&str> = data.lines()
-                                             .filter(|line| !line.trim().is_empty() && line.trim() != clean_cmd)
-                                             .collect();
-                                         filtered_data = Some(lines.join("\r\n"));
-                                     }
+                                     let filtered_data = response.data
+                                         .as_deref()
+                                         .map(|data| command_rules::filter_response(&cmd_for_task, data, trim));
                                      let ws_resp = WSResponse {
                                          success: response.success,
                                          data: filtered_data,
                                          error: response.error,
                                      };
-                                     if let Ok(json_resp) = serde_json::to_string(&ws_resp) {
-                                         let _ = conn_tx_clone.send(json_resp).await;
+                                     if let Ok(mut json_resp) = serde_json::to_value(&ws_resp) {
+                                         if deferred {
+                                             json_resp["deferred"] = json!(true);
+                                         }
+                                         let _ = conn_tx_clone.send((tag, json_resp.to_string())).await;
                                      }
                                  }
                                  Err(e) => {
                                      error!("Failed to receive response from actor: {}", e);
                                      let err_resp = json!({ "success": false, "error": "Internal Error" });
-                                     let _ = conn_tx_clone.send(err_resp.to_string()).await;
+                                     let _ = conn_tx_clone.send((tag, err_resp.to_string())).await;
                                  }
                              }
                          });
@@ -431,3 +1405,263 @@ async fn handle_client(
         }
     }
 }
+
+/// 从 syslog 的行格式 `时间 [LEVEL] target: msg` 中取出级别
+fn log_line_level(line: &str) -> Option<Level> {
+    let start = line.find('[')? + 1;
+    let end = start + line[start..].find(']')?;
+    line[start..end].parse().ok()
+}
+
+/// GET_SYS_LOGS 的可选参数
+#[derive(Debug, Default)]
+struct SysLogOptions {
+    /// 只返回最后 N 行
+    tail: Option<usize>,
+    gzip: bool,
+}
+
+impl SysLogOptions {
+    /// 解析 `lines=200,gzip` 形式的参数，`tail=` 与 `lines=` 等价
+    fn parse(params: &str) -> Result<Self, String> {
+        let mut options = Self::default();
+        for param in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match param.split_once('=') {
+                Some(("lines" | "tail", n)) => {
+                    options.tail = Some(n.trim().parse().map_err(|_| format!("Invalid line count: {}", n))?);
+                }
+                Some(("gzip", v)) => options.gzip = matches!(v.trim(), "1" | "true"),
+                None if param == "gzip" => options.gzip = true,
+                _ => return Err(format!("Unknown GET_SYS_LOGS parameter: {}", param)),
+            }
+        }
+        Ok(options)
+    }
+
+    fn render(&self, content: String) -> std::io::Result<String> {
+        let content = match self.tail {
+            Some(n) => {
+                let lines: Vec<&str> = content.lines().collect();
+                lines[lines.len().saturating_sub(n)..].join("\n")
+            }
+            None => content,
+        };
+        if !self.gzip {
+            return Ok(content);
+        }
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        Ok(BASE64.encode(encoder.finish()?))
+    }
+}
+
+/// APPLY_NETWORK：重新加载配置，在当前 modem 网卡上强制重新应用网络配置，返回逐步执行结果
+async fn apply_network() -> WSResponse {
+    let config = match tokio::task::spawn_blocking(Config::load).await {
+        Ok(config) => config,
+        Err(e) => return WSResponse::error(format!("Failed to load config: {}", e)),
+    };
+    let ifname = crate::dial_monitor::detect_modem_ifname(&config.advanced_network_config.ifname).await;
+    info!("Applying network config on {} by frontend request...", ifname);
+    let steps = network::setup_modem_network(&config, &ifname, true).await;
+    let data = json!({ "ifname": ifname, "steps": steps }).to_string();
+    if steps.iter().all(|s| s.success) {
+        WSResponse::ok(data)
+    } else {
+        WSResponse { success: false, data: Some(data), error: Some("Network setup failed".to_string()) }
+    }
+}
+
+/// 读取短信列表（PDU 模式 AT+CMGL=4），可临时切换读取存储，读完恢复原存储
+async fn list_sms(at_client: &ATClient, mem: &str) -> WSResponse {
+    match read_sms_list(at_client, mem).await {
+        Ok(items) => WSResponse::ok(serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string())),
+        Err(e) => WSResponse::error(e),
+    }
+}
+
+/// 导出存储区与接收记录中的短信，每个分片一条回复
+async fn export_sms(at_client: &ATClient, format: ExportFormat) -> Vec<WSResponse> {
+    let stored = match read_sms_list(at_client, "").await {
+        Ok(items) => items,
+        Err(e) => return vec![WSResponse::error(e)],
+    };
+    let records = sms_export::collect(&stored, &crate::sms_history::history());
+    let text = sms_export::render(&records, format);
+    let parts = sms_export::chunks(&text, sms_export::EXPORT_CHUNK_SIZE);
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, part)| {
+            WSResponse::ok(
+                json!({
+                    "format": format.name(),
+                    "count": records.len(),
+                    "chunk": i,
+                    "chunks": parts.len(),
+                    "content": part,
+                })
+                .to_string(),
+            )
+        })
+        .collect()
+}
+
+struct BatchOptions {
+    stop_on_error: bool,
+    lenient: bool,
+    trim: command_rules::ResponseTrim,
+    defer_timeout: Duration,
+}
+
+/// 顺序执行 BATCH 中的指令，与终端直发走同样的规范化、校验与定时锁频互斥
+async fn run_batch(
+    cmd_tx: &crate::models::CommandSender,
+    commands: Vec<String>,
+    options: BatchOptions,
+    conn_tx: &tokio::sync::mpsc::Sender<(ReplyTag, String)>,
+    tag: ReplyTag,
+) {
+    let total = commands.len();
+    let (mut executed, mut failed) = (0, 0);
+    for (index, raw) in commands.into_iter().enumerate() {
+        let (cmd, ws_resp) = execute_batch_command(cmd_tx, raw, &options).await;
+
+        executed += 1;
+        let success = ws_resp.success;
+        if !success {
+            failed += 1;
+        }
+        let mut json_resp = serde_json::to_value(&ws_resp).unwrap();
+        json_resp["index"] = json!(index);
+        json_resp["command"] = json!(cmd);
+        if conn_tx.send((tag.for_command(&cmd), json_resp.to_string())).await.is_err() {
+            return;
+        }
+        if !success && options.stop_on_error {
+            break;
+        }
+    }
+
+    let summary = json!({
+        "success": failed == 0,
+        "batchComplete": true,
+        "total": total,
+        "executed": executed,
+        "failed": failed,
+    });
+    let _ = conn_tx.send((tag.for_command(&format!("BATCH ({} commands)", total)), summary.to_string())).await;
+}
+
+/// 顺序执行按分隔符拆出的多条指令，全部完成后一次回复 `{success, results: [...]}`
+async fn run_split_commands(
+    cmd_tx: &crate::models::CommandSender,
+    commands: Vec<String>,
+    options: BatchOptions,
+    conn_tx: &tokio::sync::mpsc::Sender<(ReplyTag, String)>,
+    tag: ReplyTag,
+) {
+    let total = commands.len();
+    let mut results = Vec::with_capacity(total);
+    for raw in commands {
+        let (cmd, ws_resp) = execute_batch_command(cmd_tx, raw, &options).await;
+        tag.for_command(&cmd).record(ws_resp.success);
+        let mut json_resp = serde_json::to_value(&ws_resp).unwrap();
+        json_resp["command"] = json!(cmd);
+        results.push(json_resp);
+    }
+    let success = results.iter().all(|r| r["success"] == json!(true));
+    let resp = json!({ "success": success, "results": results });
+    let _ = conn_tx.send((tag.for_command(&format!("({} commands)", total)), resp.to_string())).await;
+}
+
+/// 执行批量或多指令输入中的一条，返回实际下发的指令与结果
+async fn execute_batch_command(
+    cmd_tx: &crate::models::CommandSender,
+    raw: String,
+    options: &BatchOptions,
+) -> (String, WSResponse) {
+    let mut cmd = if options.lenient { command_rules::normalize_lenient(&raw) } else { raw };
+    cmd = command_rules::rewrite_request(cmd);
+
+    let ws_resp = match command_rules::sanitize_command(&cmd) {
+        Err(e) => WSResponse::error(e),
+        Ok(_) => {
+            // 定时锁频序列进行中时等它结束；未开启排队时直接失败
+            let radio_guard = if radio::is_busy() || radio::is_cfun_set(&cmd) {
+                match radio::try_lock_radio() {
+                    Some(guard) => Some(guard),
+                    None if options.defer_timeout.is_zero() => None,
+                    None => radio::wait_radio_idle(options.defer_timeout).await,
+                }
+            } else {
+                None
+            };
+            if radio::is_cfun_set(&cmd) && radio_guard.is_none() {
+                WSResponse::error(radio::RADIO_BUSY.to_string())
+            } else {
+                let (resp_tx, resp_rx) = oneshot::channel();
+                if cmd_tx.send((cmd.as_str().into(), resp_tx)).await.is_err() {
+                    WSResponse::error("Internal Error".to_string())
+                } else {
+                    match resp_rx.await {
+                        Ok(response) => WSResponse {
+                            success: response.success,
+                            data: response.data.as_deref().map(|data| command_rules::filter_response(&cmd, data, options.trim)),
+                            error: response.error,
+                        },
+                        Err(_) => WSResponse::error("Internal Error".to_string()),
+                    }
+                }
+            }
+        }
+    };
+    (cmd, ws_resp)
+}
+
+/// 读取并归并存储区中的短信，mem 为空时使用当前存储区
+async fn read_sms_list(at_client: &ATClient, mem: &str) -> Result<Vec<crate::sms::SmsListItem>, String> {
+    let mut restore_mem = None;
+    if !mem.is_empty() {
+        if mem != "SM" && mem != "ME" {
+            return Err(format!("Unsupported SMS storage: {}", mem));
+        }
+        restore_mem = match at_client.send_command("AT+CPMS?".to_string()).await {
+            Ok(resp) => resp.data.as_deref().and_then(parse_current_mem),
+            Err(e) => return Err(e.to_string()),
+        };
+        match at_client.send_command(format!("AT+CPMS=\"{}\"", mem)).await {
+            Ok(resp) if resp.success => {}
+            Ok(resp) => return Err(resp.error.unwrap_or_else(|| "AT+CPMS failed".to_string())),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+
+    if let Err(e) = crate::sms::ensure_pdu_mode(&at_client.get_sender()).await {
+        warn!("Failed to verify SMS PDU mode: {}", e);
+    }
+    let result = at_client.send_command("AT+CMGL=4".to_string()).await;
+
+    if let Some(orig) = restore_mem.filter(|orig| orig != mem) {
+        let _ = at_client.send_command(format!("AT+CPMS=\"{}\"", orig)).await;
+    }
+
+    match result {
+        Ok(resp) if resp.success => {
+            let records: Vec<(u32, String)> = crate::pdu::extract_pdu_records(resp.data.as_deref().unwrap_or(""))
+                .into_iter()
+                .filter_map(|r| Some((r.index()?, r.pdu)))
+                .collect();
+            Ok(crate::sms::group_sms_list(&records))
+        }
+        Ok(resp) => Err(resp.error.unwrap_or_else(|| "AT+CMGL failed".to_string())),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// 从 `+CPMS: "SM",8,10,...` 中取出当前读取存储 mem1
+fn parse_current_mem(data: &str) -> Option<String> {
+    let rest = data.split("+CPMS:").nth(1)?;
+    let mem = rest.split(',').next()?.trim().trim_matches('"');
+    if mem.is_empty() { None } else { Some(mem.to_string()) }
+}
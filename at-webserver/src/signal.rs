@@ -0,0 +1,391 @@
+//! 信号强度归一化
+//!
+//! 不同制式、不同指令上报的信号值刻度不一（^HCSQ 为偏移量，^CERSSI / ^MONSC 为 dBm），
+//! 这里统一换算成 dBm，并按制式给出 0-4 格的信号格数，供广播与通知共用。
+
+use crate::client::ATClient;
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+
+/// 无线接入制式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Rat {
+    Gsm,
+    Wcdma,
+    Lte,
+    Nr,
+}
+
+impl Rat {
+    /// 解析 ^HCSQ / ^MONSC 中的制式名
+    pub fn from_mode(mode: &str) -> Option<Self> {
+        match mode.trim().trim_matches('"').to_uppercase().as_str() {
+            "GSM" => Some(Rat::Gsm),
+            "WCDMA" => Some(Rat::Wcdma),
+            "LTE" => Some(Rat::Lte),
+            "NR" => Some(Rat::Nr),
+            _ => None,
+        }
+    }
+}
+
+/// ^HCSQ 的 RSRP 原始值（0-97，255 表示未知）换算为 dBm
+pub fn hcsq_rsrp_dbm(raw: i32) -> Option<i32> {
+    (0..=97).contains(&raw).then_some(-140 + raw)
+}
+
+/// 按制式把信号强度（dBm）映射为 0-4 格
+///
+/// LTE/NR 使用 RSRP，WCDMA 使用 RSCP，GSM 使用 RSSI，阈值取业界常用分档。
+pub fn bars(rat: Rat, dbm: i32) -> u8 {
+    let thresholds = match rat {
+        Rat::Lte | Rat::Nr => [-85, -95, -105, -115],
+        Rat::Wcdma => [-75, -85, -95, -105],
+        Rat::Gsm => [-70, -85, -100, -110],
+    };
+    let above = thresholds.iter().filter(|&&t| dbm >= t).count();
+    above as u8
+}
+
+/// 信号质量文字描述，与格数一一对应
+pub fn quality_label(bars: u8) -> &'static str {
+    match bars {
+        4 => "优秀",
+        3 => "良好",
+        2 => "一般",
+        1 => "较差",
+        _ => "极差",
+    }
+}
+
+/// 服务小区详情，由 `^MONSC` 或 `+QENG: "servingcell"` 解析而来
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServingCell {
+    pub rat: Rat,
+    pub arfcn: String,
+    pub pci: String,
+    pub rsrp: i32,
+    pub rsrq: i32,
+    /// NR 上报 SINR，^MONSC 的 LTE 记录只有 RSSI
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sinr: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rssi: Option<i32>,
+}
+
+/// 查询服务小区详情的指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalCommand {
+    /// 华为/联发科平台：`AT^MONSC`
+    Monsc,
+    /// 移远：`AT+QENG="servingcell"`
+    Qeng,
+}
+
+/// 移远模组的型号前缀
+const QUECTEL_MODEL_PREFIXES: &[&str] = &["RM", "RG", "EM", "EG", "EC", "BG"];
+
+impl SignalCommand {
+    pub const ALL: [SignalCommand; 2] = [SignalCommand::Monsc, SignalCommand::Qeng];
+
+    /// 解析配置值，`auto` 或空返回 None 表示按型号选择
+    pub fn from_config(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "monsc" => Some(SignalCommand::Monsc),
+            "qeng" => Some(SignalCommand::Qeng),
+            _ => None,
+        }
+    }
+
+    /// 按 `AT+CGMM` 型号选择，未知型号使用 ^MONSC
+    pub fn for_model(model: &str) -> Self {
+        let model = model.trim().to_uppercase();
+        if QUECTEL_MODEL_PREFIXES.iter().any(|p| model.starts_with(p)) {
+            SignalCommand::Qeng
+        } else {
+            SignalCommand::Monsc
+        }
+    }
+
+    pub fn at(self) -> &'static str {
+        match self {
+            SignalCommand::Monsc => "AT^MONSC",
+            SignalCommand::Qeng => "AT+QENG=\"servingcell\"",
+        }
+    }
+
+    pub fn parse(self, data: &str) -> Option<ServingCell> {
+        match self {
+            SignalCommand::Monsc => parse_monsc(data),
+            SignalCommand::Qeng => parse_qeng(data),
+        }
+    }
+}
+
+fn int_field(fields: &[&str], i: usize) -> Option<i32> {
+    fields.get(i)?.trim().trim_matches('"').parse().ok()
+}
+
+fn str_field(fields: &[&str], i: usize) -> String {
+    fields.get(i).map_or("", |f| f.trim().trim_matches('"')).to_string()
+}
+
+/// `^MONSC: <rat>,<f1>,<arfcn>,<pci>,<f4>,<rsrp>,<rsrq>,<sinr|rssi>`，
+/// 与原正则一致：第 2、3 个字段为频点与 PCI，第 5-7 个为信号值（NR 最后一个为 SINR，LTE 为 RSSI）
+pub fn parse_monsc(data: &str) -> Option<ServingCell> {
+    let rest = data.lines().find_map(|l| l.trim().strip_prefix("^MONSC:"))?;
+    let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+    let rat = Rat::from_mode(fields.first()?)?;
+    if !matches!(rat, Rat::Lte | Rat::Nr) {
+        return None;
+    }
+    let last = int_field(&fields, 7)?;
+    Some(ServingCell {
+        rat,
+        arfcn: str_field(&fields, 2),
+        pci: str_field(&fields, 3),
+        rsrp: int_field(&fields, 5)?,
+        rsrq: int_field(&fields, 6)?,
+        sinr: (rat == Rat::Nr).then_some(last),
+        rssi: (rat == Rat::Lte).then_some(last),
+    })
+}
+
+/// 移远 `+QENG: "servingcell",<state>,"LTE",<is_tdd>,<mcc>,<mnc>,<cellid>,<pci>,<earfcn>,<band>,<ul_bw>,<dl_bw>,<tac>,<rsrp>,<rsrq>,<rssi>,<sinr>,...`、
+/// `+QENG: "servingcell",<state>,"NR5G-SA",<duplex>,<mcc>,<mnc>,<cellid>,<pci>,<tac>,<arfcn>,<band>,<bw>,<rsrp>,<rsrq>,<sinr>,...`，
+/// NSA 时 NR 小区单独一行 `+QENG: "NR5G-NSA",<mcc>,<mnc>,<pci>,<rsrp>,<sinr>,<rsrq>,<arfcn>,<band>`，优先取 NR
+pub fn parse_qeng(data: &str) -> Option<ServingCell> {
+    let records: Vec<Vec<&str>> = data
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix("+QENG:"))
+        .map(|rest| rest.split(',').map(str::trim).collect())
+        .collect();
+
+    let nsa = records.iter().find(|f| str_field(f, 0) == "NR5G-NSA").and_then(|f| {
+        Some(ServingCell {
+            rat: Rat::Nr,
+            arfcn: str_field(f, 7),
+            pci: str_field(f, 3),
+            rsrp: int_field(f, 4)?,
+            rsrq: int_field(f, 6)?,
+            sinr: int_field(f, 5),
+            rssi: None,
+        })
+    });
+    if nsa.is_some() {
+        return nsa;
+    }
+
+    records.iter().find_map(|f| {
+        let offset = if str_field(f, 0) == "servingcell" { 2 } else { 0 };
+        match str_field(f, offset).as_str() {
+            "NR5G-SA" => Some(ServingCell {
+                rat: Rat::Nr,
+                arfcn: str_field(f, offset + 7),
+                pci: str_field(f, offset + 5),
+                rsrp: int_field(f, offset + 10)?,
+                rsrq: int_field(f, offset + 11)?,
+                sinr: int_field(f, offset + 12),
+                rssi: None,
+            }),
+            "LTE" => Some(ServingCell {
+                rat: Rat::Lte,
+                arfcn: str_field(f, offset + 6),
+                pci: str_field(f, offset + 5),
+                rsrp: int_field(f, offset + 11)?,
+                rsrq: int_field(f, offset + 12)?,
+                sinr: int_field(f, offset + 14),
+                rssi: int_field(f, offset + 13),
+            }),
+            _ => None,
+        }
+    })
+}
+
+/// 载波聚合中的一个成员载波
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentCarrier {
+    /// 主载波（PCC）为 true，辅载波（SCC）为 false
+    pub primary: bool,
+    pub rat: Rat,
+    /// 频段，LTE 为 "B3"，NR 为 "n78"
+    pub band: String,
+    pub arfcn: String,
+    pub bandwidth_mhz: Option<f64>,
+    pub pci: Option<String>,
+    pub rsrp: Option<i32>,
+    pub rsrq: Option<i32>,
+    pub sinr: Option<i32>,
+}
+
+/// `GET_CA_INFO` 的结果，只有一个载波时 `active` 为 false
+#[derive(Debug, Clone, Serialize)]
+pub struct CaInfo {
+    pub active: bool,
+    pub carriers: Vec<ComponentCarrier>,
+}
+
+fn band_name(rat: Rat, band: &str) -> String {
+    match rat {
+        Rat::Nr => format!("n{}", band),
+        _ => format!("B{}", band),
+    }
+}
+
+/// 华为 `^HFREQINFO: <n>,<sysmode>,<band>,<dl_fcn>,<dl_freq>,<dl_bw>,<ul_fcn>,<ul_freq>,<ul_bw>[,<band>,...]`
+///
+/// sysmode 6 为 LTE、7 为 NR，每个载波占 7 个字段，带宽单位 kHz；第一行第一个载波为主载波。
+/// 该指令不带信号值，信号字段为 None。
+pub fn parse_hfreqinfo(data: &str) -> Vec<ComponentCarrier> {
+    let mut carriers = Vec::new();
+    for rest in data.lines().filter_map(|l| l.trim().strip_prefix("^HFREQINFO:")) {
+        let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+        let rat = match int_field(&fields, 1) {
+            Some(6) => Rat::Lte,
+            Some(7) => Rat::Nr,
+            _ => continue,
+        };
+        for group in fields[2..].chunks_exact(7) {
+            carriers.push(ComponentCarrier {
+                primary: carriers.is_empty(),
+                rat,
+                band: band_name(rat, group[0]),
+                arfcn: group[1].to_string(),
+                bandwidth_mhz: group[3].parse::<f64>().ok().map(|khz| khz / 1000.0),
+                pci: None,
+                rsrp: None,
+                rsrq: None,
+                sinr: None,
+            });
+        }
+    }
+    carriers
+}
+
+/// QCAINFO 的 LTE 带宽为资源块数（部分固件为 0-5 的序号），NR 为带宽序号
+fn qcainfo_bandwidth_mhz(rat: Rat, raw: i32) -> Option<f64> {
+    const LTE_BY_INDEX: [f64; 6] = [1.4, 3.0, 5.0, 10.0, 15.0, 20.0];
+    const NR_BY_INDEX: [f64; 14] = [5.0, 10.0, 15.0, 20.0, 25.0, 30.0, 40.0, 50.0, 60.0, 80.0, 90.0, 100.0, 200.0, 400.0];
+    match rat {
+        Rat::Nr => NR_BY_INDEX.get(usize::try_from(raw).ok()?).copied(),
+        _ => match raw {
+            0..=5 => Some(LTE_BY_INDEX[raw as usize]),
+            6 => Some(1.4),
+            15 => Some(3.0),
+            25 => Some(5.0),
+            50 => Some(10.0),
+            75 => Some(15.0),
+            100 => Some(20.0),
+            _ => None,
+        },
+    }
+}
+
+/// 移远 `+QCAINFO: "PCC"|"SCC",<arfcn>,<bandwidth>,"LTE BAND 3"|"NR5G BAND 78",<state>,<pci>,<rsrp>,<rsrq>,<rssi>,<sinr>`，
+/// 部分 SCC 记录只到 pci 或更短，缺少的字段为 None
+pub fn parse_qcainfo(data: &str) -> Vec<ComponentCarrier> {
+    data.lines()
+        .filter_map(|l| l.trim().strip_prefix("+QCAINFO:"))
+        .filter_map(|rest| {
+            let fields: Vec<&str> = rest.split(',').map(str::trim).collect();
+            let primary = match str_field(&fields, 0).as_str() {
+                "PCC" => true,
+                "SCC" => false,
+                _ => return None,
+            };
+            let band_desc = str_field(&fields, 3).to_uppercase();
+            let (rat, band) = if let Some(band) = band_desc.strip_prefix("NR5G BAND") {
+                (Rat::Nr, band.trim().to_string())
+            } else {
+                (Rat::Lte, band_desc.strip_prefix("LTE BAND")?.trim().to_string())
+            };
+            Some(ComponentCarrier {
+                primary,
+                rat,
+                band: band_name(rat, &band),
+                arfcn: str_field(&fields, 1),
+                bandwidth_mhz: int_field(&fields, 2).and_then(|raw| qcainfo_bandwidth_mhz(rat, raw)),
+                pci: fields.get(5).map(|p| p.trim_matches('"').to_string()),
+                rsrp: int_field(&fields, 6),
+                rsrq: int_field(&fields, 7),
+                sinr: int_field(&fields, 9),
+            })
+        })
+        .collect()
+}
+
+/// 按型号查询载波聚合信息：移远用 `AT+QCAINFO`，其余用 `AT^HFREQINFO?`
+pub async fn query_ca_info(client: &ATClient) -> Result<CaInfo> {
+    let command = match crate::modem::model(&client.get_sender()).await {
+        Ok(model) => SignalCommand::for_model(&model),
+        Err(_) => SignalCommand::Monsc,
+    };
+    let (cmd, parse): (&str, fn(&str) -> Vec<ComponentCarrier>) = match command {
+        SignalCommand::Qeng => ("AT+QCAINFO", parse_qcainfo),
+        SignalCommand::Monsc => ("AT^HFREQINFO?", parse_hfreqinfo),
+    };
+    let resp = client.send_command(cmd.to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    let carriers = parse(resp.data.as_deref().unwrap_or(""));
+    Ok(CaInfo { active: carriers.len() > 1, carriers })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hfreqinfo_lists_primary_then_secondary_carriers() {
+        let data = "^HFREQINFO: 1,6,3,1850,1867.5,20000,19850,1772.5,20000,1,100,2140.0,20000,18100,1950.0,20000\r\nOK";
+        let carriers = parse_hfreqinfo(data);
+        assert_eq!(carriers.len(), 2);
+        assert!(carriers[0].primary);
+        assert_eq!((carriers[0].band.as_str(), carriers[0].arfcn.as_str()), ("B3", "1850"));
+        assert_eq!(carriers[0].bandwidth_mhz, Some(20.0));
+        assert!(!carriers[1].primary);
+        assert_eq!(carriers[1].band, "B1");
+    }
+
+    #[test]
+    fn hfreqinfo_nsa_adds_nr_carrier() {
+        let data = "^HFREQINFO: 1,6,3,1850,1867.5,20000,19850,1772.5,20000\r\n^HFREQINFO: 1,7,78,627264,3408.96,100000,627264,3408.96,100000";
+        let carriers = parse_hfreqinfo(data);
+        assert_eq!(carriers.len(), 2);
+        assert_eq!((carriers[1].rat, carriers[1].band.as_str(), carriers[1].primary), (Rat::Nr, "n78", false));
+        assert_eq!(carriers[1].bandwidth_mhz, Some(100.0));
+    }
+
+    #[test]
+    fn qcainfo_parses_signal_per_carrier() {
+        let data = "+QCAINFO: \"PCC\",1300,100,\"LTE BAND 3\",1,123,-85,-11,-55,14\r\n\
+                    +QCAINFO: \"SCC\",100,3,\"LTE BAND 1\",1,301,-95,-13,-62,6\r\n\
+                    +QCAINFO: \"SCC\",627264,11,\"NR5G BAND 78\",412\r\nOK";
+        let carriers = parse_qcainfo(data);
+        assert_eq!(carriers.len(), 3);
+        assert_eq!(carriers[0], ComponentCarrier {
+            primary: true,
+            rat: Rat::Lte,
+            band: "B3".to_string(),
+            arfcn: "1300".to_string(),
+            bandwidth_mhz: Some(20.0),
+            pci: Some("123".to_string()),
+            rsrp: Some(-85),
+            rsrq: Some(-11),
+            sinr: Some(14),
+        });
+        assert_eq!(carriers[1].bandwidth_mhz, Some(10.0));
+        assert_eq!((carriers[2].band.as_str(), carriers[2].bandwidth_mhz, carriers[2].rsrp), ("n78", Some(100.0), None));
+    }
+
+    #[test]
+    fn single_carrier_is_not_aggregation() {
+        let carriers = parse_qcainfo("+QCAINFO: \"PCC\",1300,100,\"LTE BAND 3\",1,123,-85,-11,-55,14\r\nOK");
+        assert_eq!(carriers.len(), 1);
+        assert!(carriers[0].primary);
+        assert!(parse_hfreqinfo("OK").is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+//! 双卡槽查询与切换（AT^SIMSLOT）
+//!
+//! `AT^SIMSLOT?` 返回当前生效的卡槽，插卡状态只能对生效卡槽通过 `AT+CPIN?` 判断，
+//! 另一卡槽无法在不切换的情况下探测，报告为 null。切换卡槽会重启射频，
+//! 与定时锁频共用射频操作锁，并要求调用方显式确认。
+
+use crate::client::ATClient;
+use crate::models::CommandSender;
+use crate::radio;
+use crate::retry;
+use anyhow::{anyhow, Result};
+use log::info;
+use serde::Serialize;
+
+/// 支持切换的卡槽编号
+pub const SLOTS: [u8; 2] = [0, 1];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimSlotState {
+    /// 当前生效的卡槽
+    pub active_slot: u8,
+    /// 每个卡槽是否有卡，None 表示未知（非生效卡槽）
+    pub cards: Vec<Option<bool>>,
+}
+
+/// 解析 `^SIMSLOT: <slot>[,...]`，取第一个字段作为生效卡槽
+pub fn parse_simslot(data: &str) -> Option<u8> {
+    data.lines()
+        .find_map(|l| l.trim().strip_prefix("^SIMSLOT:"))
+        .and_then(|rest| rest.split(',').next())
+        .and_then(|slot| slot.trim().parse().ok())
+}
+
+/// 根据 `AT+CPIN?` 的结果判断生效卡槽是否有卡：+CME ERROR: 10 为未插卡
+fn card_present(success: bool, data: Option<&str>, error: Option<&str>) -> Option<bool> {
+    if success {
+        return data.map(|d| d.contains("+CPIN:"));
+    }
+    match error {
+        Some(e) if e.contains("+CME ERROR: 10") || e.to_uppercase().contains("NOT INSERTED") => Some(false),
+        // PIN/PUK 锁等错误说明卡在位
+        Some(e) if e.contains("+CME ERROR") => Some(true),
+        _ => None,
+    }
+}
+
+pub async fn query_sim_slots(client: &ATClient) -> Result<SimSlotState> {
+    let resp = client.send_command("AT^SIMSLOT?".to_string()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT^SIMSLOT? failed".to_string())));
+    }
+    let active_slot = resp
+        .data
+        .as_deref()
+        .and_then(parse_simslot)
+        .ok_or_else(|| anyhow!("Unexpected AT^SIMSLOT? response"))?;
+
+    let cpin = client.send_command("AT+CPIN?".to_string()).await?;
+    let present = card_present(cpin.success, cpin.data.as_deref(), cpin.error.as_deref());
+    let cards = SLOTS
+        .iter()
+        .map(|&slot| if slot == active_slot { present } else { None })
+        .collect();
+
+    Ok(SimSlotState { active_slot, cards })
+}
+
+/// 切换卡槽并广播切换后的状态
+pub async fn set_sim_slot(client: &ATClient, slot: u8) -> Result<SimSlotState> {
+    if !SLOTS.contains(&slot) {
+        return Err(anyhow!("Unsupported SIM slot {}, expected one of {:?}", slot, SLOTS));
+    }
+    let _guard = radio::try_lock_radio().ok_or_else(|| anyhow!(radio::RADIO_BUSY))?;
+    info!("Switching active SIM slot to {}", slot);
+    let resp = client.send_command(format!("AT^SIMSLOT={}", slot)).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("AT^SIMSLOT={} failed", slot))));
+    }
+
+    let state = query_sim_slots(client).await?;
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = serde_json::json!({ "type": "sim_slots", "data": state }).to_string();
+        let _ = tx.send(msg);
+    }
+    Ok(state)
+}
+
+/// 解析 ICCID，兼容 `^ICCID: <iccid>`、`+CCID: <iccid>` 与裸数字行
+pub fn parse_iccid(data: &str) -> Option<String> {
+    data.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && *l != "OK" && !l.starts_with("AT"))
+        .map(|l| {
+            let value = l.strip_prefix("^ICCID:").or_else(|| l.strip_prefix("+CCID:")).unwrap_or(l);
+            value.trim().trim_matches('"').to_string()
+        })
+        .find(|v| v.len() >= 18 && v.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// 查询当前 SIM 卡的 ICCID，华为 `AT^ICCID?` 不支持时回退到 `AT+CCID`
+pub async fn query_iccid(cmd_tx: &CommandSender) -> Option<String> {
+    for cmd in ["AT^ICCID?", "AT+CCID"] {
+        if let Ok(resp) = retry::send_with_retry(cmd_tx, cmd, false).await {
+            if let Some(iccid) = resp.data.as_deref().filter(|_| resp.success).and_then(parse_iccid) {
+                return Some(iccid);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,620 @@
+//! 短信列表、长短信拼装与存储用量
+//!
+//! 实时 URC 路径（`handlers::process_sms`）与主动拉取路径（`LIST_SMS`）共用这里的拼装逻辑。
+
+use crate::at_parse::split_fields;
+use crate::models::CommandSender;
+use crate::notifications::{NotificationManager, NotificationType};
+use crate::pdu::{read_incoming_sms, IncomingMessage, SmsData};
+use crate::retry;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, Local, SecondsFormat, Utc};
+use log::{debug, info, warn};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// 短信时间戳是否保留短信中心（SCTS）自带的时区，否则换算为本地时区
+static KEEP_SCTS_OFFSET: AtomicBool = AtomicBool::new(false);
+
+/// 启动时按 `sms_timezone` 配置调用：`original` 保留 SCTS 时区，其余换算为本地时区
+pub fn set_timestamp_zone(zone: &str) {
+    KEEP_SCTS_OFFSET.store(zone.eq_ignore_ascii_case("original"), Ordering::Relaxed);
+}
+
+/// 把短信时间戳格式化为 RFC 3339 字符串，用于推送给前端
+pub fn format_timestamp(date: &DateTime<FixedOffset>) -> String {
+    if KEEP_SCTS_OFFSET.load(Ordering::Relaxed) {
+        date.to_rfc3339()
+    } else {
+        date.with_timezone(&Local).to_rfc3339()
+    }
+}
+
+/// 保留短信中心（SCTS）原始时区的时间戳
+pub fn original_timestamp(date: &DateTime<FixedOffset>) -> String {
+    date.to_rfc3339()
+}
+
+/// 换算为 UTC 的时间戳，如 `2024-01-02T00:30:00Z`
+pub fn utc_timestamp(date: &DateTime<FixedOffset>) -> String {
+    date.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// 短信通知正文附带的时间：0 不附带，1 本地，2 原始时区，3 UTC
+static NOTIFY_TIME: AtomicU8 = AtomicU8::new(0);
+
+/// 启动时按 `sms_notify_time` 配置调用：none / local / original / utc，未知值按 none 处理
+pub fn set_notify_time(mode: &str) {
+    let value = match mode.to_lowercase().as_str() {
+        "local" => 1,
+        "original" => 2,
+        "utc" => 3,
+        _ => 0,
+    };
+    NOTIFY_TIME.store(value, Ordering::Relaxed);
+}
+
+/// 按 `sms_notify_time` 在通知正文后附上接收时间
+pub fn notification_text(content: &str, date: &DateTime<FixedOffset>) -> String {
+    let time = match NOTIFY_TIME.load(Ordering::Relaxed) {
+        1 => date.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(),
+        2 => date.format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        3 => date.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        _ => return content.to_string(),
+    };
+    format!("{}\n\n时间：{}", content, time)
+}
+
+/// 长短信的通知方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultipartMode {
+    /// 分段全部到齐后通知一次
+    Wait,
+    /// 每收到一段通知一次，拼装完成后不再通知
+    Immediate,
+    /// 每段通知，拼装完成后再通知完整内容
+    Both,
+}
+
+impl MultipartMode {
+    /// 收到单个分段时是否通知
+    pub fn notify_segments(self) -> bool {
+        self != MultipartMode::Wait
+    }
+
+    /// 拼装完成时是否通知
+    pub fn notify_complete(self) -> bool {
+        self != MultipartMode::Immediate
+    }
+}
+
+static MULTIPART_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// 启动时按 `multipart_mode` 配置调用：wait / immediate / both，未知值按 wait 处理
+pub fn set_multipart_mode(mode: &str) {
+    let value = match mode.to_lowercase().as_str() {
+        "immediate" => 1,
+        "both" => 2,
+        _ => 0,
+    };
+    MULTIPART_MODE.store(value, Ordering::Relaxed);
+}
+
+pub fn multipart_mode() -> MultipartMode {
+    match MULTIPART_MODE.load(Ordering::Relaxed) {
+        1 => MultipartMode::Immediate,
+        2 => MultipartMode::Both,
+        _ => MultipartMode::Wait,
+    }
+}
+
+static PARTIAL_MAX_AGE: AtomicU64 = AtomicU64::new(3600);
+static PARTIAL_MAX_ENTRIES: AtomicUsize = AtomicUsize::new(100);
+
+/// 启动时按 `partial_sms_max_age` / `partial_sms_max_entries` 配置调用
+pub fn set_partial_limits(max_age: u64, max_entries: usize) {
+    PARTIAL_MAX_AGE.store(max_age, Ordering::Relaxed);
+    PARTIAL_MAX_ENTRIES.store(max_entries.max(1), Ordering::Relaxed);
+}
+
+/// 未收齐长短信的 (最长等待秒数, 最多条数)
+pub fn partial_limits() -> (u64, usize) {
+    (PARTIAL_MAX_AGE.load(Ordering::Relaxed), PARTIAL_MAX_ENTRIES.load(Ordering::Relaxed))
+}
+
+/// 长短信缓存超过 `max_entries` 时应丢弃的键，按首个分段到达时间从早到晚；`keep` 为当前正在拼装的一条，不会被丢弃
+pub fn partial_evictions<'a>(entries: impl Iterator<Item = (&'a String, u64)>, keep: &str, max_entries: usize) -> Vec<String> {
+    let mut entries: Vec<(&String, u64)> = entries.collect();
+    let excess = entries.len().saturating_sub(max_entries);
+    entries.retain(|(key, _)| key.as_str() != keep);
+    entries.sort_by_key(|(_, ts)| *ts);
+    entries.into_iter().take(excess).map(|(key, _)| key.clone()).collect()
+}
+
+/// 展开为 `time`（按 `sms_timezone`）、`timeOriginal` 与 `timeUtc` 三个字段，配合 `#[serde(flatten)]` 使用
+fn serialize_timestamps<S: Serializer>(date: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(3))?;
+    map.serialize_entry("time", &format_timestamp(date))?;
+    map.serialize_entry("timeOriginal", &original_timestamp(date))?;
+    map.serialize_entry("timeUtc", &utc_timestamp(date))?;
+    map.end()
+}
+
+/// 按分段序号 1..=parts_count 拼接长短信，全部到齐返回 Some，否则返回 None
+pub fn join_parts(parts_count: u8, parts: &HashMap<u8, String>) -> Option<String> {
+    if parts.len() < parts_count as usize {
+        return None;
+    }
+    let mut content = String::new();
+    for i in 1..=parts_count {
+        content.push_str(parts.get(&i)?);
+    }
+    Some(content)
+}
+
+/// 拼接已收到的分段（允许缺段），缺失的分段用省略号占位
+pub fn join_available_parts(parts_count: u8, parts: &HashMap<u8, String>) -> String {
+    let mut content = String::new();
+    for i in 1..=parts_count {
+        match parts.get(&i) {
+            Some(part) => content.push_str(part),
+            None => content.push('…'),
+        }
+    }
+    content
+}
+
+/// 列表中的一条（已拼装的）短信
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmsListItem {
+    /// 模组存储索引，长短信包含所有已收到分段的索引
+    pub indexes: Vec<u32>,
+    pub sender: String,
+    pub content: String,
+    #[serde(flatten, serialize_with = "serialize_timestamps")]
+    pub time: DateTime<FixedOffset>,
+    pub is_complete: bool,
+    pub parts_received: usize,
+    pub parts_count: u8,
+}
+
+/// 解码 `AT+CMGL` 记录并按 (发送方, 参考号) 归并长短信
+pub fn group_sms_list(records: &[(u32, String)]) -> Vec<SmsListItem> {
+    let mut items: Vec<SmsListItem> = Vec::new();
+    // Key: "sender_reference", Value: (items 中的位置, 分段内容)
+    let mut multipart: HashMap<String, (usize, HashMap<u8, String>)> = HashMap::new();
+
+    for (index, pdu_hex) in records {
+        let sms: SmsData = match read_incoming_sms(pdu_hex) {
+            Ok(IncomingMessage::Sms(sms)) => sms,
+            Ok(IncomingMessage::MmsNotification(mms)) => {
+                debug!("Skipping MMS notification at index {} from {}", index, mms.sender);
+                continue;
+            }
+            Err(e) => {
+                warn!("Failed to decode SMS at index {}: {}", index, e);
+                continue;
+            }
+        };
+
+        let Some(partial) = sms.partial_info else {
+            items.push(SmsListItem {
+                indexes: vec![*index],
+                sender: sms.sender,
+                content: sms.content,
+                time: sms.date,
+                is_complete: true,
+                parts_received: 1,
+                parts_count: 1,
+            });
+            continue;
+        };
+
+        let key = format!("{}_{}", sms.sender, partial.reference);
+        let (pos, parts) = multipart.entry(key).or_insert_with(|| {
+            items.push(SmsListItem {
+                indexes: Vec::new(),
+                sender: sms.sender.clone(),
+                content: String::new(),
+                time: sms.date,
+                is_complete: false,
+                parts_received: 0,
+                parts_count: partial.parts_count,
+            });
+            (items.len() - 1, HashMap::new())
+        });
+        parts.insert(partial.part_number, sms.content);
+
+        let item = &mut items[*pos];
+        item.indexes.push(*index);
+        item.parts_received = parts.len();
+        // 以第一段的时间为准
+        if partial.part_number == 1 {
+            item.time = sms.date;
+        }
+        match join_parts(item.parts_count, parts) {
+            Some(content) => {
+                item.content = content;
+                item.is_complete = true;
+            }
+            None => {
+                item.content = join_available_parts(item.parts_count, parts);
+                item.is_complete = false;
+            }
+        }
+    }
+
+    items
+}
+
+/// `AT+CPMS?` 中一个存储区的用量
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StorageUsage {
+    /// 存储区名称，如 "SM" / "ME"
+    pub storage: String,
+    pub used: u32,
+    pub total: u32,
+    pub percent: u8,
+}
+
+/// 解析 `+CPMS: "SM",8,10,"SM",8,10,"SM",8,10`，依次为读取/写入/接收存储区
+pub fn parse_cpms(data: &str) -> Vec<StorageUsage> {
+    let Some(rest) = data.lines().find_map(|l| l.trim().strip_prefix("+CPMS:")) else {
+        return Vec::new();
+    };
+    let fields: Vec<&str> = rest.split(',').map(|f| f.trim()).collect();
+    fields
+        .chunks_exact(3)
+        .filter_map(|group| {
+            let used: u32 = group[1].parse().ok()?;
+            let total: u32 = group[2].parse().ok()?;
+            let percent = (used.min(total) * 100).checked_div(total).unwrap_or(0) as u8;
+            Some(StorageUsage {
+                storage: group[0].trim_matches('"').to_string(),
+                used,
+                total,
+                percent,
+            })
+        })
+        .collect()
+}
+
+/// 定时查询短信存储用量并广播 `storage`，使用率越过阈值时发送存储满通知
+///
+/// 部分模组存储满时既不发 `+CIEV: "MESSAGE",0` 也不回 `+CMS ERROR: 322`，
+/// 只能靠主动查询发现。通知只在越过阈值时发一次，降回阈值以下后重新计。
+pub async fn storage_monitor(cmd_tx: CommandSender, notifications: NotificationManager, interval_secs: u64) {
+    if interval_secs == 0 {
+        info!("SMS storage check disabled");
+        return;
+    }
+    let mut alerted = false;
+    loop {
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+
+        let data = match retry::send_with_retry(&cmd_tx, "AT+CPMS?", false).await {
+            Ok(resp) if resp.success => resp.data.unwrap_or_default(),
+            Ok(resp) => {
+                debug!("AT+CPMS? failed: {:?}", resp.error);
+                continue;
+            }
+            Err(e) => {
+                debug!("AT+CPMS? failed: {}", e);
+                continue;
+            }
+        };
+        let usage = parse_cpms(&data);
+        if usage.is_empty() {
+            continue;
+        }
+
+        if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+            let msg = serde_json::json!({ "type": "storage", "data": usage }).to_string();
+            let _ = tx.send(msg);
+        }
+
+        let threshold = notifications.memory_full_threshold();
+        if threshold == 0 {
+            continue;
+        }
+        // 接收存储区（第三组，缺省时取第一组）决定新短信能否落地
+        let inbox = usage.get(2).unwrap_or(&usage[0]);
+        if inbox.percent >= threshold {
+            if !alerted {
+                alerted = true;
+                warn!("SMS storage {} at {}/{} ({}%)", inbox.storage, inbox.used, inbox.total, inbox.percent);
+                let msg = format!(
+                    "短信存储 {} 已使用 {}/{} ({}%)，超过阈值 {}%，请及时清理",
+                    inbox.storage, inbox.used, inbox.total, inbox.percent, threshold
+                );
+                notifications.notify("短信存储", &msg, NotificationType::MemoryFull).await;
+            }
+        } else {
+            alerted = false;
+        }
+    }
+}
+
+/// 解析 `+CMGF: <mode>`，0=PDU，1=文本
+pub fn parse_cmgf(data: &str) -> Option<u8> {
+    data.lines()
+        .find_map(|l| l.trim().strip_prefix("+CMGF:"))
+        .and_then(|mode| mode.trim().parse().ok())
+}
+
+/// 设置短信格式（0=PDU，1=文本）
+pub async fn set_sms_mode(cmd_tx: &CommandSender, mode: u8) -> Result<()> {
+    if mode > 1 {
+        return Err(anyhow!("Unsupported SMS mode {}, expected 0 (PDU) or 1 (text)", mode));
+    }
+    let resp = retry::send_with_retry(cmd_tx, &format!("AT+CMGF={}", mode), false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("AT+CMGF={} failed", mode))));
+    }
+    Ok(())
+}
+
+/// 读取短信前确认模组处于 PDU 模式
+///
+/// 短信解码、CMGL/CMGR 的 PDU 提取都以 PDU 模式为前提。模组被切到文本模式
+/// （终端手动下发、SET_SMS_MODE、固件重置等）时记录警告并切回，否则读到的内容会静默解码失败。
+pub async fn ensure_pdu_mode(cmd_tx: &CommandSender) -> Result<()> {
+    let resp = retry::send_with_retry(cmd_tx, "AT+CMGF?", false).await?;
+    match resp.data.as_deref().filter(|_| resp.success).and_then(parse_cmgf) {
+        Some(0) => Ok(()),
+        Some(mode) => {
+            warn!("Modem is in SMS text mode (AT+CMGF={}), switching back to PDU mode", mode);
+            set_sms_mode(cmd_tx, 0).await
+        }
+        None => Err(anyhow!(resp.error.unwrap_or_else(|| "Unexpected AT+CMGF? response".to_string()))),
+    }
+}
+
+/// 短信中心号码类型：145 为国际格式（带 `+`），129 为本地格式
+const SMSC_TYPE_INTERNATIONAL: u8 = 145;
+const SMSC_TYPE_NATIONAL: u8 = 129;
+
+/// 解析 `+CSCA: "<number>",<type>`，未设置短信中心时号码为空串
+pub fn parse_csca(data: &str) -> Option<(String, Option<u8>)> {
+    let rest = data.lines().find_map(|l| l.trim().strip_prefix("+CSCA:"))?;
+    let fields = split_fields(rest);
+    let number = fields.first()?.clone();
+    Some((number, fields.get(1).and_then(|t| t.parse().ok())))
+}
+
+/// 校验并规范化短信中心号码，去掉空格与连字符，返回 (号码, 类型)
+///
+/// 以 `+` 开头的按国际格式（145），否则按本地格式（129）。
+pub fn normalize_smsc(input: &str) -> Result<(String, u8)> {
+    let number: String = input.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+    let (international, digits) = match number.strip_prefix('+') {
+        Some(digits) => (true, digits),
+        None => (false, number.as_str()),
+    };
+    if !(3..=20).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(anyhow!("Invalid SMSC number: {}", input.trim()));
+    }
+    let kind = if international { SMSC_TYPE_INTERNATIONAL } else { SMSC_TYPE_NATIONAL };
+    Ok((number, kind))
+}
+
+/// 查询短信中心号码（`AT+CSCA?`）
+pub async fn query_smsc(cmd_tx: &CommandSender) -> Result<serde_json::Value> {
+    let resp = retry::send_with_retry(cmd_tx, "AT+CSCA?", false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT+CSCA? failed".to_string())));
+    }
+    let (number, kind) = resp.data.as_deref().and_then(parse_csca).ok_or_else(|| anyhow!("Unexpected AT+CSCA? response"))?;
+    Ok(json!({
+        "number": number,
+        "type": kind,
+        "international": kind == Some(SMSC_TYPE_INTERNATIONAL) || number.starts_with('+'),
+        "configured": !number.is_empty(),
+    }))
+}
+
+/// 设置短信中心号码后回读
+pub async fn set_smsc(cmd_tx: &CommandSender, input: &str) -> Result<serde_json::Value> {
+    let (number, kind) = normalize_smsc(input)?;
+    let cmd = format!("AT+CSCA=\"{}\",{}", number, kind);
+    let resp = retry::send_with_retry(cmd_tx, &cmd, true).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    info!("SMSC set to {} (type {})", number, kind);
+    query_smsc(cmd_tx).await
+}
+
+/// 解析 `+CMTI: "<mem>",<index>`，返回存储区与索引
+pub fn parse_cmti(line: &str) -> Option<(String, u32)> {
+    let rest = line.trim().strip_prefix("+CMTI:")?;
+    let (mem, index) = rest.split_once(',')?;
+    let mem = mem.trim().trim_matches('"').to_uppercase();
+    if mem.is_empty() {
+        return None;
+    }
+    Some((mem, index.trim().parse().ok()?))
+}
+
+/// 只切换读取/删除存储区 mem1 的 AT+CPMS，写入与接收存储区保持不变
+pub fn select_read_storage_command(mem: &str) -> String {
+    format!("AT+CPMS=\"{}\"", mem)
+}
+
+/// 切换到 `mem` 前需要恢复的读取存储区：`AT+CPMS?` 中的 mem1 与 `mem` 不同时返回 mem1
+fn storage_to_restore(cpms: &str, mem: &str) -> Option<String> {
+    parse_cpms(cpms).into_iter().next().map(|u| u.storage).filter(|current| current != mem)
+}
+
+/// 让后续 AT+CMGR / AT+CMGD 作用于 `mem`，避免 +CMTI 上报的存储区与当前读取存储区不一致时读错、删错
+///
+/// 返回切换前的读取存储区（本来就是 `mem` 或查询失败时为 None），操作完成后交给 `restore_read_storage`，
+/// 否则 LIST_SMS、用户手动发的 AT+CMGL 等会一直作用在被切换过去的存储区上。
+pub async fn select_read_storage(cmd_tx: &CommandSender, mem: &str) -> Result<Option<String>> {
+    let previous = match retry::send_with_retry(cmd_tx, "AT+CPMS?", false).await {
+        Ok(resp) if resp.success => storage_to_restore(resp.data.as_deref().unwrap_or(""), mem),
+        _ => None,
+    };
+    let cmd = select_read_storage_command(mem);
+    let resp = retry::send_with_retry(cmd_tx, &cmd, false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    Ok(previous)
+}
+
+/// 恢复 `select_read_storage` 切换前的读取存储区，失败只记日志
+pub async fn restore_read_storage(cmd_tx: &CommandSender, previous: Option<String>) {
+    let Some(mem) = previous else {
+        return;
+    };
+    let cmd = select_read_storage_command(&mem);
+    match retry::send_with_retry(cmd_tx, &cmd, false).await {
+        Ok(resp) if resp.success => {}
+        Ok(resp) => warn!("Failed to restore SMS read storage {}: {:?}", mem, resp.error),
+        Err(e) => warn!("Failed to restore SMS read storage {}: {}", mem, e),
+    }
+}
+
+/// `AT+CMGR` 响应的内容
+#[derive(Debug)]
+pub enum CmgrPayload {
+    Pdu(String),
+    /// 模组处于文本模式时的短信（头部状态字段带引号），已按文本解析
+    Text(SmsData),
+    Empty,
+}
+
+/// 解析 `AT+CMGR` 响应，兼容 PDU 模式与文本模式
+///
+/// 文本模式：`+CMGR: "REC UNREAD","+8613800138000",,"24/01/01,12:00:00+32"`，正文在下一行起到 OK 为止。
+/// 按头部判断模式，而不是看正文是否像十六进制，纯数字短信在文本模式下也是合法的十六进制串。
+pub fn parse_cmgr(data: &str) -> CmgrPayload {
+    let mut lines = data.lines().map(str::trim);
+    let Some(header) = lines.find_map(|l| l.strip_prefix("+CMGR:")) else {
+        return CmgrPayload::Empty;
+    };
+    if !header.trim_start().starts_with('"') {
+        return crate::pdu::extract_pdus(data).into_iter().next().map_or(CmgrPayload::Empty, CmgrPayload::Pdu);
+    }
+
+    let fields = split_fields(header);
+    let sender = fields.get(1).cloned().unwrap_or_default();
+    let date = fields.get(3).and_then(|t| parse_text_timestamp(t)).unwrap_or_else(|| Local::now().fixed_offset());
+    let content = lines.take_while(|l| *l != "OK").collect::<Vec<_>>().join("\n");
+    CmgrPayload::Text(SmsData { sender, content, date, partial_info: None, pid: 0 })
+}
+
+/// 文本模式时间戳 `yy/MM/dd,hh:mm:ss±zz`，zz 以 15 分钟为单位
+fn parse_text_timestamp(text: &str) -> Option<DateTime<FixedOffset>> {
+    let sign_pos = text.rfind(['+', '-'])?;
+    let (local, zone) = text.split_at(sign_pos);
+    let quarters: i32 = zone.parse().ok()?;
+    let offset = FixedOffset::east_opt(quarters * 15 * 60)?;
+    let naive = chrono::NaiveDateTime::parse_from_str(local, "%y/%m/%d,%H:%M:%S").ok()?;
+    naive.and_local_timezone(offset).single()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_smsc() {
+        assert_eq!(parse_csca("+CSCA: \"+8613800100500\",145\r\nOK"), Some(("+8613800100500".to_string(), Some(145))));
+        assert_eq!(parse_csca("+CSCA: \"\",129"), Some((String::new(), Some(129))));
+        assert_eq!(parse_csca("OK"), None);
+        assert_eq!(normalize_smsc(" +86 138-0010-0500").unwrap(), ("+8613800100500".to_string(), 145));
+        assert_eq!(normalize_smsc("13800100500").unwrap().1, 129);
+        assert!(normalize_smsc("+86abc").is_err());
+        assert!(normalize_smsc("\"+86\",145").is_err());
+        assert!(normalize_smsc("+").is_err());
+    }
+
+    #[test]
+    fn list_item_carries_original_and_utc_time() {
+        let item = SmsListItem {
+            indexes: vec![1],
+            sender: "10086".to_string(),
+            content: "hi".to_string(),
+            time: DateTime::parse_from_rfc3339("2024-01-02T08:30:00+08:00").unwrap(),
+            is_complete: true,
+            parts_received: 1,
+            parts_count: 1,
+        };
+        let json = serde_json::to_value(&item).unwrap();
+        assert_eq!(json["timeOriginal"], "2024-01-02T08:30:00+08:00");
+        assert_eq!(json["timeUtc"], "2024-01-02T00:30:00Z");
+        assert!(json["time"].is_string());
+    }
+
+    #[test]
+    fn parses_pdu_mode_cmgr() {
+        let data = "+CMGR: 0,,24\r\n00040B913108108300F00000620161214365231050797A5C066DCA35D086F75E6F7C\r\nOK";
+        match parse_cmgr(data) {
+            CmgrPayload::Pdu(pdu) => assert!(pdu.starts_with("00040B91")),
+            other => panic!("expected PDU, got {:?}", other),
+        }
+        assert!(matches!(parse_cmgr("OK"), CmgrPayload::Empty));
+    }
+
+    #[test]
+    fn parses_text_mode_cmgr() {
+        let data = "+CMGR: \"REC UNREAD\",\"+8613800138000\",,\"24/01/02,08:30:00+32\"\r\n1234\r\nsecond line\r\nOK";
+        match parse_cmgr(data) {
+            CmgrPayload::Text(sms) => {
+                assert_eq!(sms.sender, "+8613800138000");
+                // 纯数字正文不能被当成 PDU
+                assert_eq!(sms.content, "1234\nsecond line");
+                assert_eq!(sms.date.to_rfc3339(), "2024-01-02T08:30:00+08:00");
+            }
+            other => panic!("expected text SMS, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn partial_cache_evicts_oldest_beyond_limit() {
+        let entries: Vec<(String, u64)> = vec![("a_1".into(), 30), ("b_2".into(), 10), ("c_3".into(), 20), ("d_4".into(), 5)];
+        let iter = || entries.iter().map(|(k, ts)| (k, *ts));
+        assert!(partial_evictions(iter(), "a_1", 4).is_empty());
+        assert_eq!(partial_evictions(iter(), "a_1", 2), vec!["d_4".to_string(), "b_2".to_string()]);
+        // 当前条目即使最旧也保留
+        assert_eq!(partial_evictions(iter(), "d_4", 3), vec!["b_2".to_string()]);
+    }
+
+    #[test]
+    fn cmti_storage_selects_matching_read_storage() {
+        let (mem, index) = parse_cmti("+CMTI: \"SM\",5").unwrap();
+        assert_eq!((mem.as_str(), index), ("SM", 5));
+        assert_eq!(select_read_storage_command(&mem), "AT+CPMS=\"SM\"");
+
+        let (mem, index) = parse_cmti("+CMTI: \"ME\", 12").unwrap();
+        assert_eq!((mem.as_str(), index), ("ME", 12));
+        assert_eq!(select_read_storage_command(&mem), "AT+CPMS=\"ME\"");
+
+        assert_eq!(parse_cmti("+CMTI: \"\",3"), None);
+        assert_eq!(parse_cmti("+CMTI: \"SM\""), None);
+    }
+
+    #[tokio::test]
+    async fn read_storage_is_restored_after_switch() {
+        use crate::models::ATResponse;
+        let (cmd_tx, sent) = crate::connection::mock::scripted_sender(|cmd| match cmd {
+            "AT+CPMS?" => ATResponse::ok(Some("+CPMS: \"SM\",3,50,\"SM\",3,50,\"SM\",3,50".to_string())),
+            _ => ATResponse::ok(None),
+        });
+        let previous = select_read_storage(&cmd_tx, "ME").await.unwrap();
+        assert_eq!(previous.as_deref(), Some("SM"));
+        restore_read_storage(&cmd_tx, previous).await;
+        assert_eq!(*sent.lock().unwrap(), ["AT+CPMS?", "AT+CPMS=\"ME\"", "AT+CPMS=\"SM\""]);
+
+        // 已经是目标存储区时无需恢复
+        assert_eq!(storage_to_restore("+CPMS: \"ME\",0,100", "ME"), None);
+        assert_eq!(storage_to_restore("ERROR", "ME"), None);
+    }
+}
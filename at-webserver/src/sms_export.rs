@@ -0,0 +1,142 @@
+//! 短信导出
+//!
+//! `EXPORT_SMS:<json|csv>` 合并模组当前存储区中的短信与本服务的接收记录（按发送方和内容去重），
+//! 输出 JSON 数组或 CSV。导出内容按 `EXPORT_CHUNK_SIZE` 分片，逐条回复，避免单个 WebSocket 帧过大。
+
+use crate::sms::{format_timestamp, SmsListItem};
+use crate::sms_history::SmsRecord;
+use serde::Serialize;
+
+/// 每个分片的最大字节数
+pub const EXPORT_CHUNK_SIZE: usize = 32 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+/// 导出的一条短信
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRecord {
+    pub sender: String,
+    pub content: String,
+    pub timestamp: String,
+    pub complete: bool,
+}
+
+/// 合并存储区短信与接收记录，记录中与存储区发送方、内容都相同的视为同一条
+pub fn collect(stored: &[SmsListItem], history: &[SmsRecord]) -> Vec<ExportRecord> {
+    let mut records: Vec<ExportRecord> = stored
+        .iter()
+        .map(|item| ExportRecord {
+            sender: item.sender.clone(),
+            content: item.content.clone(),
+            timestamp: format_timestamp(&item.time),
+            complete: item.is_complete,
+        })
+        .collect();
+    for record in history {
+        if stored.iter().any(|s| s.sender == record.sender && s.content == record.content) {
+            continue;
+        }
+        records.push(ExportRecord {
+            sender: record.sender.clone(),
+            content: record.content.clone(),
+            timestamp: format_timestamp(&record.time.fixed_offset()),
+            complete: record.is_complete,
+        });
+    }
+    records
+}
+
+pub fn render(records: &[ExportRecord], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Json => serde_json::to_string(records).unwrap_or_else(|_| "[]".to_string()),
+        ExportFormat::Csv => {
+            let mut out = String::from("sender,content,timestamp,complete\r\n");
+            for r in records {
+                out.push_str(&format!(
+                    "{},{},{},{}\r\n",
+                    csv_field(&r.sender),
+                    csv_field(&r.content),
+                    csv_field(&r.timestamp),
+                    r.complete
+                ));
+            }
+            out
+        }
+    }
+}
+
+/// 含逗号、引号或换行的字段用双引号包裹，内部引号写两遍（RFC 4180）
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 按字节上限切分，切点落在字符边界上
+pub fn chunks(text: &str, max_len: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while rest.len() > max_len {
+        let mut end = max_len;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (head, tail) = rest.split_at(end);
+        parts.push(head);
+        rest = tail;
+    }
+    if !rest.is_empty() || parts.is_empty() {
+        parts.push(rest);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_escapes_special_characters() {
+        let records = vec![ExportRecord {
+            sender: "10086".to_string(),
+            content: "a,b \"quoted\"\nline".to_string(),
+            timestamp: "2024-01-02T03:04:05+08:00".to_string(),
+            complete: true,
+        }];
+        assert_eq!(
+            render(&records, ExportFormat::Csv),
+            "sender,content,timestamp,complete\r\n10086,\"a,b \"\"quoted\"\"\nline\",2024-01-02T03:04:05+08:00,true\r\n"
+        );
+    }
+
+    #[test]
+    fn chunks_split_on_char_boundaries() {
+        let text = "短信短信";
+        let parts = chunks(text, 4);
+        assert_eq!(parts, vec!["短", "信", "短", "信"]);
+        assert_eq!(chunks("", 4), vec![""]);
+    }
+}
@@ -0,0 +1,88 @@
+//! 已接收短信记录
+//!
+//! 转发后删除、存储满被清理等情况下模组里已经没有原短信，这里独立保留最近收到的短信，
+//! 由 `process_sms` 写入、`GET_SMS_HISTORY` 读取。长短信拼装完成后记一条完整记录，
+//! 超时仍未收齐的按已收到的分段记为不完整。开启 `sms_history_persist` 后写入文件，重启后恢复。
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// 保留的短信条数
+const SMS_HISTORY_LEN: usize = 100;
+
+const PERSIST_PATH: &str = "/etc/at-webserver.sms.json";
+
+static HISTORY: OnceLock<Mutex<SmsHistory>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmsRecord {
+    pub sender: String,
+    pub content: String,
+    pub time: DateTime<Local>,
+    pub is_complete: bool,
+    /// Replace 类短信的类型（1-7），同一发送方同类型的新短信会覆盖旧记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replace_type: Option<u8>,
+}
+
+struct SmsHistory {
+    records: VecDeque<SmsRecord>,
+    persist: bool,
+}
+
+/// 启动时调用，开启持久化时从文件恢复记录
+pub fn init(persist: bool) {
+    let records = if persist {
+        crate::ring_file::load(PERSIST_PATH, SMS_HISTORY_LEN, "SMS history")
+    } else {
+        VecDeque::new()
+    };
+    let _ = HISTORY.set(Mutex::new(SmsHistory { records, persist }));
+}
+
+fn history_lock() -> &'static Mutex<SmsHistory> {
+    HISTORY.get_or_init(|| Mutex::new(SmsHistory { records: VecDeque::new(), persist: false }))
+}
+
+/// 追加一条短信，超过上限时丢弃最旧的
+pub fn record(sender: &str, content: &str, time: DateTime<Local>, is_complete: bool) {
+    push(SmsRecord {
+        sender: sender.to_string(),
+        content: content.to_string(),
+        time,
+        is_complete,
+        replace_type: None,
+    });
+}
+
+/// 记录一条 Replace 类短信，先移除同一发送方同类型的旧记录
+pub fn record_replacing(sender: &str, content: &str, time: DateTime<Local>, replace_type: u8) {
+    history_lock()
+        .lock()
+        .unwrap()
+        .records
+        .retain(|r| !(r.sender == sender && r.replace_type == Some(replace_type)));
+    push(SmsRecord {
+        sender: sender.to_string(),
+        content: content.to_string(),
+        time,
+        is_complete: true,
+        replace_type: Some(replace_type),
+    });
+}
+
+fn push(record: SmsRecord) {
+    let mut history = history_lock().lock().unwrap();
+    crate::ring_file::push_capped(&mut history.records, SMS_HISTORY_LEN, record);
+    if history.persist {
+        crate::ring_file::save(PERSIST_PATH, &history.records, "SMS history");
+    }
+}
+
+/// 最近收到的短信（时间倒序）
+pub fn history() -> Vec<SmsRecord> {
+    history_lock().lock().unwrap().records.iter().rev().cloned().collect()
+}
@@ -0,0 +1,166 @@
+//! 短信逻辑 id 与模组存储索引的映射
+//!
+//! 短信反复读取删除后模组的存储索引会出现空洞，新短信还可能复用刚删除的索引，
+//! 前端按索引操作时容易删错。`COMPACT_SMS` 重新读取全部短信，为每条 PDU 分配逻辑 id：
+//! 同一条短信在多次整理之间保持原 id，新出现的分配新 id。按 id 删除时先回读该索引，
+//! PDU 与映射时不一致（索引已被复用）则拒绝执行，按 id 读取（READ_SMS）同样先校验。
+
+use crate::models::CommandSender;
+use crate::pdu::{extract_pdu_records, read_incoming_sms, IncomingMessage};
+use crate::retry;
+use anyhow::{anyhow, Result};
+use log::warn;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+static INDEX_MAP: Mutex<IndexMap> = Mutex::new(IndexMap { next_id: 1, slots: Vec::new() });
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SmsSlot {
+    /// 逻辑 id，整理后对同一条短信保持不变
+    pub id: u32,
+    /// 当前的模组存储索引
+    pub index: u32,
+}
+
+struct MappedSlot {
+    id: u32,
+    index: u32,
+    pdu: String,
+}
+
+struct IndexMap {
+    next_id: u32,
+    slots: Vec<MappedSlot>,
+}
+
+impl IndexMap {
+    /// 按最新的 `(索引, PDU)` 列表重建映射，PDU 相同的沿用旧 id
+    fn rebuild(&mut self, records: Vec<(u32, String)>) -> Vec<SmsSlot> {
+        let mut reused = HashSet::new();
+        let mut slots = Vec::with_capacity(records.len());
+        for (index, pdu) in records {
+            let id = match self.slots.iter().find(|s| s.pdu == pdu && !reused.contains(&s.id)) {
+                Some(old) => old.id,
+                None => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    id
+                }
+            };
+            reused.insert(id);
+            slots.push(MappedSlot { id, index, pdu });
+        }
+        self.slots = slots;
+        self.slots.iter().map(|s| SmsSlot { id: s.id, index: s.index }).collect()
+    }
+}
+
+/// 读取当前存储区的全部短信并刷新映射
+pub async fn compact(cmd_tx: &CommandSender) -> Result<Vec<SmsSlot>> {
+    if let Err(e) = crate::sms::ensure_pdu_mode(cmd_tx).await {
+        warn!("Failed to verify SMS PDU mode: {}", e);
+    }
+    let resp = retry::send_with_retry(cmd_tx, "AT+CMGL=4", false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| "AT+CMGL failed".to_string())));
+    }
+    let records = extract_pdu_records(resp.data.as_deref().unwrap_or(""))
+        .into_iter()
+        .filter_map(|r| Some((r.index()?, r.pdu)))
+        .collect();
+    Ok(INDEX_MAP.lock().unwrap().rebuild(records))
+}
+
+/// 按逻辑 id 回读当前存储索引上的 PDU，索引上的短信已不是映射时那一条时报错
+async fn verified_slot(cmd_tx: &CommandSender, id: u32) -> Result<(u32, String)> {
+    let (index, pdu) = {
+        let map = INDEX_MAP.lock().unwrap();
+        let slot = map
+            .slots
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| anyhow!("Unknown SMS id {}, run COMPACT_SMS first", id))?;
+        (slot.index, slot.pdu.clone())
+    };
+
+    let resp = retry::send_with_retry(cmd_tx, &format!("AT+CMGR={}", index), false).await?;
+    let current = resp
+        .data
+        .as_deref()
+        .and_then(|d| extract_pdu_records(d).into_iter().next())
+        .map(|r| r.pdu);
+    if current.as_deref() != Some(pdu.as_str()) {
+        return Err(anyhow!("SMS index {} no longer holds id {}, run COMPACT_SMS again", index, id));
+    }
+    Ok((index, pdu))
+}
+
+/// 按逻辑 id 读取并解码短信（READ_SMS），返回当前存储索引与短信内容，不删除
+pub async fn read(cmd_tx: &CommandSender, id: u32) -> Result<(u32, IncomingMessage)> {
+    if let Err(e) = crate::sms::ensure_pdu_mode(cmd_tx).await {
+        warn!("Failed to verify SMS PDU mode: {}", e);
+    }
+    let (index, pdu) = verified_slot(cmd_tx, id).await?;
+    Ok((index, read_incoming_sms(&pdu)?))
+}
+
+/// 按逻辑 id 删除短信，索引上的短信已不是映射时那一条时拒绝删除
+pub async fn delete(cmd_tx: &CommandSender, id: u32) -> Result<u32> {
+    let (index, _) = verified_slot(cmd_tx, id).await?;
+    let resp = retry::send_with_retry(cmd_tx, &format!("AT+CMGD={}", index), false).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("AT+CMGD={} failed", index))));
+    }
+    INDEX_MAP.lock().unwrap().slots.retain(|s| s.id != id);
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ATResponse;
+
+    const PDU_A: &str = "00040B913108108300F00000620161214365231050797A5C066DCA35D086F75E6F7C";
+    const PDU_B: &str = "00440B913108108300F00000620161214365231D0500032A0202906536FB0DBABFE56C320B040FCBE920FAFD1D02";
+
+    /// 假模组：CMGL 返回两条短信，CMGR 按 `stored` 返回索引上当前的 PDU
+    fn fake_modem(stored: &'static [(u32, &'static str)]) -> CommandSender {
+        let (cmd_tx, _) = crate::connection::mock::scripted_sender(move |cmd| {
+            let data = if cmd == "AT+CMGF?" {
+                "+CMGF: 0".to_string()
+            } else if cmd == "AT+CMGL=4" {
+                format!("+CMGL: 3,1,,24\r\n{}\r\n+CMGL: 7,1,,36\r\n{}", PDU_A, PDU_B)
+            } else if let Some(index) = cmd.strip_prefix("AT+CMGR=") {
+                let index: u32 = index.parse().unwrap();
+                stored
+                    .iter()
+                    .find(|(i, _)| *i == index)
+                    .map(|(_, pdu)| format!("+CMGR: 1,,24\r\n{}", pdu))
+                    .unwrap_or_default()
+            } else {
+                String::new()
+            };
+            ATResponse::ok(Some(data))
+        });
+        cmd_tx
+    }
+
+    #[tokio::test]
+    async fn read_translates_id_and_rejects_reused_index() {
+        let cmd_tx = fake_modem(&[(3, PDU_B), (7, PDU_B)]);
+        let slots = compact(&cmd_tx).await.unwrap();
+        let (id_a, id_b) = (slots[0].id, slots[1].id);
+        assert_eq!(slots[1].index, 7);
+
+        // 逻辑 id 换算成存储索引 7 读取
+        let (index, msg) = read(&cmd_tx, id_b).await.unwrap();
+        assert_eq!(index, 7);
+        assert!(matches!(msg, IncomingMessage::Sms(_)));
+
+        // 索引 3 上已经换成别的短信
+        assert!(read(&cmd_tx, id_a).await.unwrap_err().to_string().contains("no longer holds"));
+        assert!(read(&cmd_tx, 9999).await.is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! 发送短信的状态事件
+//!
+//! Actor 每条指令完成后调用 `observe`：`AT+CMGS=` 拿到 `>` 后的正文（以 Ctrl-Z 结尾）提交完成时广播
+//! `sms_sent`，并按 `+CMGS: <mr>` 记下收件人；之后 `+CDS` 状态报告到达时按参考号对应，广播 `sms_delivered`。
+//! 状态报告需要模组开启（`AT+CSMP` 首字节置 SRR、`AT+CNMI` 的 ds=1），未开启时只有 `sms_sent`。
+
+use crate::models::ATResponse;
+use crate::pdu::StatusReport;
+use log::{info, warn};
+use serde_json::json;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// 等待状态报告的短信最多保留条数，参考号只有 0-255，太旧的对应关系已不可靠
+const MAX_PENDING: usize = 64;
+
+/// 上一条 `AT+CMGS=` 已拿到提示符，下一条以 Ctrl-Z 结尾的指令是短信正文
+static AWAITING_BODY: AtomicBool = AtomicBool::new(false);
+
+/// 已发出、等待状态报告的 (参考号, 收件人)
+static PENDING: Mutex<VecDeque<(u8, Option<String>)>> = Mutex::new(VecDeque::new());
+
+/// 从 `+CMGS: <mr>` 中取出消息参考号
+pub fn parse_cmgs_reference(data: &str) -> Option<u8> {
+    data.lines()
+        .find_map(|l| l.trim().strip_prefix("+CMGS:"))
+        .and_then(|rest| rest.split(',').next())
+        .and_then(|mr| mr.trim().parse().ok())
+}
+
+/// TP-ST 对应的投递状态
+pub fn delivery_status(status: u8) -> &'static str {
+    match status {
+        0x00..=0x1F => "delivered",
+        0x20..=0x3F => "pending",
+        _ => "failed",
+    }
+}
+
+/// 观察 Actor 完成的每条指令，发送短信相关的广播 `sms_sent`
+pub fn observe(cmd: &str, resp: &ATResponse) {
+    let cmd = cmd.trim_start();
+    if cmd.len() >= 8 && cmd[..8].eq_ignore_ascii_case("AT+CMGS=") {
+        AWAITING_BODY.store(resp.success, Ordering::Relaxed);
+        if !resp.success {
+            broadcast_sent(None, false, resp.error.as_deref());
+        }
+        return;
+    }
+    if cmd.ends_with('\x1B') {
+        AWAITING_BODY.store(false, Ordering::Relaxed);
+        return;
+    }
+    let Some(body) = cmd.strip_suffix('\x1A') else { return };
+    if !AWAITING_BODY.swap(false, Ordering::Relaxed) {
+        return;
+    }
+
+    let reference = resp.data.as_deref().and_then(parse_cmgs_reference);
+    let sent = resp.success && reference.is_some();
+    if let Some(mr) = reference.filter(|_| sent) {
+        let recipient = crate::pdu::submit_recipient(body.trim());
+        info!("SMS submitted, message reference {}", mr);
+        let mut pending = PENDING.lock().unwrap();
+        pending.retain(|(r, _)| *r != mr);
+        if pending.len() >= MAX_PENDING {
+            pending.pop_front();
+        }
+        pending.push_back((mr, recipient));
+    } else {
+        warn!("SMS submission failed: {:?}", resp.error);
+    }
+    broadcast_sent(reference, sent, resp.error.as_deref());
+}
+
+fn broadcast_sent(reference: Option<u8>, sent: bool, error: Option<&str>) {
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = json!({
+            "type": "sms_sent",
+            "data": {
+                "ref": reference,
+                "status": if sent { "sent" } else { "failed" },
+                "error": error.filter(|_| !sent),
+            }
+        });
+        let _ = tx.send(msg.to_string());
+    }
+}
+
+/// 处理一条状态报告：与已发送的短信对应后广播 `sms_delivered`
+///
+/// 仍在重试（pending）的报告不会结束对应关系，后续的最终报告还能对上。
+pub fn on_status_report(report: &StatusReport) {
+    let status = delivery_status(report.status);
+    let matched = {
+        let mut pending = PENDING.lock().unwrap();
+        let pos = pending.iter().position(|(mr, recipient)| {
+            *mr == report.reference
+                && recipient.as_deref().is_none_or(|r| r.ends_with(&report.recipient) || report.recipient.ends_with(r))
+        });
+        match pos {
+            Some(pos) if status == "pending" => Some(pending[pos].1.clone()),
+            Some(pos) => pending.remove(pos).map(|(_, recipient)| recipient),
+            None => None,
+        }
+    };
+    if matched.is_none() {
+        info!("Status report for unknown message reference {}", report.reference);
+    }
+
+    if let Some(tx) = crate::server::WS_BROADCASTER.get() {
+        let msg = json!({
+            "type": "sms_delivered",
+            "data": {
+                "ref": report.reference,
+                "recipient": report.recipient,
+                "status": status,
+                "statusCode": report.status,
+                "submitted": crate::sms::format_timestamp(&report.submitted),
+                "time": crate::sms::format_timestamp(&report.discharged),
+                "matched": matched.is_some(),
+            }
+        });
+        let _ = tx.send(msg.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_reference_and_delivery_status() {
+        assert_eq!(parse_cmgs_reference("+CMGS: 12\r\nOK"), Some(12));
+        assert_eq!(parse_cmgs_reference("+CMGS: 7,\"24010112000000+32\"\r\nOK"), Some(7));
+        assert_eq!(parse_cmgs_reference("OK"), None);
+        assert_eq!(delivery_status(0x00), "delivered");
+        assert_eq!(delivery_status(0x30), "pending");
+        assert_eq!(delivery_status(0x41), "failed");
+    }
+}
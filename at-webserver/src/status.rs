@@ -0,0 +1,127 @@
+//! `GET_STATUS` 聚合状态
+//!
+//! 面向手机小组件等受限客户端，一次返回连接、注册、运营商、制式、信号与拨号 IP。
+//! 优先取各处缓存的最近结果：信号来自信号 URC，运营商与注册状态来自最近一次查询，IP 来自拨号监控；
+//! 缓存缺失或过期时才补一次快速查询。
+
+use crate::models::CommandSender;
+use crate::operator::OperatorInfo;
+use crate::retry;
+use crate::signal::{self, Rat};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// 运营商缓存的有效期，过期后重新查询 AT+COPS?
+const OPERATOR_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// 信号缓存的有效期，信号 URC 长时间没有上报时改为主动查询
+const SIGNAL_MAX_AGE: Duration = Duration::from_secs(120);
+
+static CACHE: Mutex<StatusCache> = Mutex::new(StatusCache { signal: None, operator: None, ip: None });
+
+struct StatusCache {
+    signal: Option<(SignalState, Instant)>,
+    operator: Option<(OperatorInfo, Instant)>,
+    ip: Option<IpState>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignalState {
+    pub rat: Option<Rat>,
+    pub rsrp: i32,
+    pub sinr: Option<i32>,
+    pub bars: u8,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IpState {
+    pub ipv4: Option<String>,
+    pub ipv6: Option<String>,
+}
+
+/// 信号 URC 或服务小区查询得到新值时调用；sinr 为 None 时沿用同制式的上一次值
+pub fn record_signal(rat: Option<Rat>, rsrp: i32, sinr: Option<i32>) {
+    let mut cache = CACHE.lock().unwrap();
+    let rat = rat.or_else(|| cache.signal.as_ref().and_then(|(s, _)| s.rat));
+    let sinr = sinr.or_else(|| cache.signal.as_ref().filter(|(s, _)| s.rat == rat).and_then(|(s, _)| s.sinr));
+    // ^CERSSI 不区分 LTE/NR，两者 RSRP 分档相同，按 LTE 计算格数
+    let bars = signal::bars(rat.unwrap_or(Rat::Lte), rsrp);
+    cache.signal = Some((SignalState { rat, rsrp, sinr, bars }, Instant::now()));
+}
+
+pub fn record_operator(info: &OperatorInfo) {
+    CACHE.lock().unwrap().operator = Some((info.clone(), Instant::now()));
+}
+
+/// 拨号监控每次检查 AT+CGPADDR 后调用，两个地址都为 None 表示未拨通
+pub fn record_ip(ipv4: Option<String>, ipv6: Option<String>) {
+    CACHE.lock().unwrap().ip = Some(IpState { ipv4, ipv6 });
+}
+
+/// 解析 `^HCSQ: "LTE",<rsrp>,<sinr>,<rsrq>`，作为没有信号缓存时的快速查询
+pub fn parse_hcsq(data: &str) -> Option<(Option<Rat>, i32)> {
+    let rest = data.lines().find_map(|l| l.trim().strip_prefix("^HCSQ:"))?;
+    let mut parts = rest.split(',').map(str::trim);
+    let rat = Rat::from_mode(parts.next()?);
+    let rsrp = parts.next()?.parse().ok().and_then(signal::hcsq_rsrp_dbm)?;
+    Some((rat, rsrp))
+}
+
+/// 汇总当前状态，缓存缺失的部分补查一次
+pub async fn collect(cmd_tx: &CommandSender, operator_names: &HashMap<String, String>) -> serde_json::Value {
+    let (mut signal, mut operator, ip) = {
+        let cache = CACHE.lock().unwrap();
+        (
+            cache.signal.as_ref().filter(|(_, at)| at.elapsed() < SIGNAL_MAX_AGE).map(|(s, _)| s.clone()),
+            cache.operator.as_ref().filter(|(_, at)| at.elapsed() < OPERATOR_MAX_AGE).map(|(o, _)| o.clone()),
+            cache.ip.clone(),
+        )
+    };
+    let connected = crate::metrics::connection_uptime().is_some();
+
+    if connected && operator.is_none() {
+        operator = crate::operator::query(cmd_tx, operator_names).await.ok();
+    }
+    if connected && signal.is_none() {
+        if let Ok(resp) = retry::send_with_retry(cmd_tx, "AT^HCSQ?", false).await {
+            if let Some((rat, rsrp)) = resp.data.as_deref().filter(|_| resp.success).and_then(parse_hcsq) {
+                record_signal(rat, rsrp, None);
+                signal = CACHE.lock().unwrap().signal.as_ref().map(|(s, _)| s.clone());
+            }
+        }
+    }
+
+    let dialed = ip.as_ref().map(|ip| ip.ipv4.is_some() || ip.ipv6.is_some());
+    json!({
+        "connected": connected,
+        "uptime": crate::metrics::connection_uptime().map(|d| d.as_secs()),
+        "registration": operator.as_ref().map_or("unknown", |o| o.registration),
+        "operator": operator.as_ref().and_then(|o| o.operator.clone()),
+        "plmn": operator.as_ref().and_then(|o| o.plmn.clone()),
+        "rat": signal.as_ref().and_then(|s| s.rat).map(|r| json!(r)).or_else(|| operator.as_ref().and_then(|o| o.rat).map(|r| json!(r))),
+        "bars": signal.as_ref().map(|s| s.bars),
+        "rsrp": signal.as_ref().map(|s| s.rsrp),
+        "sinr": signal.as_ref().and_then(|s| s.sinr),
+        "dial": match dialed {
+            Some(true) => "connected",
+            Some(false) => "disconnected",
+            None => "unknown",
+        },
+        "ipv4": ip.as_ref().and_then(|ip| ip.ipv4.clone()),
+        "ipv6": ip.as_ref().and_then(|ip| ip.ipv6.clone()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hcsq_fallback() {
+        assert_eq!(parse_hcsq("^HCSQ: \"LTE\",45,120,30\r\nOK"), Some((Some(Rat::Lte), -95)));
+        assert_eq!(parse_hcsq("^HCSQ: \"NOSERVICE\""), None);
+    }
+}
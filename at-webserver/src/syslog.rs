@@ -54,7 +54,7 @@ impl Log for AppLogger {
 
 pub fn init(config: &Config) -> broadcast::Receiver<String> {
     // Initialize broadcast channel
-    let (tx, rx) = broadcast::channel(100);
+    let (tx, rx) = broadcast::channel(config.websocket_config.broadcast_capacity);
     LOG_CHANNEL.set(tx).expect("Failed to set log channel");
 
     let level = match config.sys_log_config.level.as_str() {
@@ -0,0 +1,92 @@
+//! 可在运行时开关的 URC 上报流
+//!
+//! 关闭时既向模组下发关闭上报的指令，也让对应的 handler 停止广播，
+//! 即使模组不支持关闭指令，前端也不会再收到该类事件。
+
+use crate::client::ATClient;
+use anyhow::{anyhow, Result};
+use log::info;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 每个上报流占一位，置位表示已静音；默认全部开启
+static MUTED: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrcStream {
+    /// ^PDCPDATAINFO，上报频繁
+    Pdcp,
+    /// ^CERSSI 信号变化
+    Signal,
+}
+
+impl UrcStream {
+    pub const ALL: [UrcStream; 2] = [UrcStream::Pdcp, UrcStream::Signal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            UrcStream::Pdcp => "pdcp",
+            UrcStream::Signal => "signal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.name().eq_ignore_ascii_case(name.trim()))
+    }
+
+    fn bit(self) -> u8 {
+        match self {
+            UrcStream::Pdcp => 1 << 0,
+            UrcStream::Signal => 1 << 1,
+        }
+    }
+
+    fn at_command(self, enable: bool) -> String {
+        let n = u8::from(enable);
+        match self {
+            UrcStream::Pdcp => format!("AT^PDCPDATAINFO={}", n),
+            UrcStream::Signal => format!("AT^CERSSI={}", n),
+        }
+    }
+}
+
+pub fn is_enabled(stream: UrcStream) -> bool {
+    MUTED.load(Ordering::Relaxed) & stream.bit() == 0
+}
+
+/// 该行是否属于已静音的上报流，静音后原始行也不再作为 raw_data 广播
+pub fn is_muted_line(line: &str) -> bool {
+    let stream = if line.starts_with("^PDCPDATAINFO:") {
+        UrcStream::Pdcp
+    } else if line.starts_with("^CERSSI:") || line.starts_with("^HCSQ:") {
+        UrcStream::Signal
+    } else {
+        return false;
+    };
+    !is_enabled(stream)
+}
+
+/// 各上报流的开关状态，如 `{"pdcp":true,"signal":false}`
+pub fn state_json() -> String {
+    let state: serde_json::Map<String, serde_json::Value> = UrcStream::ALL
+        .into_iter()
+        .map(|s| (s.name().to_string(), serde_json::Value::Bool(is_enabled(s))))
+        .collect();
+    serde_json::Value::Object(state).to_string()
+}
+
+/// 开关某个上报流：本地广播立即生效，模组侧指令失败时返回错误
+pub async fn set_enabled(client: &ATClient, stream: UrcStream, enable: bool) -> Result<()> {
+    if enable {
+        MUTED.fetch_and(!stream.bit(), Ordering::Relaxed);
+    } else {
+        MUTED.fetch_or(stream.bit(), Ordering::Relaxed);
+    }
+    info!("URC stream {} {}", stream.name(), if enable { "enabled" } else { "muted" });
+
+    let cmd = stream.at_command(enable);
+    let resp = client.send_command(cmd.clone()).await?;
+    if !resp.success {
+        return Err(anyhow!(resp.error.unwrap_or_else(|| format!("{} failed", cmd))));
+    }
+    Ok(())
+}